@@ -9,6 +9,7 @@ use bevy::prelude::*;
 use bevy_camera_extras::*;
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 
+use bevy_jam5::inventory::{Inventory, Item, INVENTORY_SLOTS};
 use bevy_jam5::lockpicking::*;
 use bevy_jam5::player_controller::*;
 
@@ -66,14 +67,7 @@ fn setup(
             material: materials.add(Color::LinearRgba(LinearRgba::BLUE)),
             ..default()
         },
-        Locked {
-            success_zone_width: 10.0,
-            move_on_good_pick: true,
-            zone_slide_settings: SlideSettings::SlideLinear(SlideLinear {
-                speed: 10.0,
-                time_to_target: 1.5,
-            }),
-        },
+        Locked::random(LockDifficulty::new(0.3), &mut rand::thread_rng()),
         Name::new("treasure box"),
     ));
 
@@ -88,7 +82,12 @@ fn setup(
             },
             CharacterControllerBundle::new(Collider::capsule(0.4, 1.0), Vector::NEG_Y * 9.81 * 2.0)
                 .with_movement(30.0, 0.92, 7.0, (30.0 as Scalar).to_radians()),
-            LockPicker { target: None },
+            LockPicker::default(),
+            // some locks need a lockpick or bolt cutters
+            Inventory::with_items(
+                INVENTORY_SLOTS,
+                &[(Item::Lockpick, 5), (Item::BoltCutters, 1)],
+            ),
         ))
         .id();
 
@@ -1,4 +1,4 @@
-use crate::GameState;
+use crate::{levels::LevelCatalog, GameState};
 use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
 
@@ -8,16 +8,16 @@ impl Plugin for AssetLoaderPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, setup).add_loading_state(
             LoadingState::new(GameState::Loading)
-                .continue_to_state(GameState::Spawning)
-                .load_collection::<GltfAssets>(),
+                .continue_to_state(GameState::LevelSelect)
+                .load_collection::<LevelAssets>(),
         );
     }
 }
 
 #[derive(AssetCollection, Resource, Clone)]
-pub struct GltfAssets {
-    #[asset(path = "town.glb")]
-    pub world: Handle<Gltf>,
+pub struct LevelAssets {
+    #[asset(path = "catalog.levels.json")]
+    pub catalog: Handle<LevelCatalog>,
 }
 
 fn setup(asset_server: Res<AssetServer>, mut commands: Commands) {
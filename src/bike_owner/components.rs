@@ -0,0 +1,42 @@
+use bevy::prelude::*;
+
+/// An npc owning one or more [`Bicycle`](crate::world_spawning::on_spawn::Bicycle)s,
+/// who comes back to check on them every now and then.
+#[derive(Component)]
+pub struct BikeOwner {
+    pub bicycles: Vec<Entity>,
+    /// Where the owner stays while away from their bicycles.
+    pub away_position: Vec3,
+}
+
+/// Links a bicycle to its [`BikeOwner`].
+#[derive(Component)]
+pub struct Owned {
+    pub owner: Entity,
+}
+
+/// The state machine driving a [`BikeOwner`].
+#[derive(Component)]
+pub enum OwnerBehaviour {
+    /// Out of the world, comes back when the timer finishes.
+    Away(Timer),
+    /// Walking back to their bicycles.
+    Returning,
+    /// Standing next to their bicycles, leaves when the timer finishes.
+    AtBicycle(Timer),
+    /// Walking away from their bicycles.
+    Leaving,
+    /// Running after a thief, gives up when the timer finishes.
+    Chasing { target: Entity, timer: Timer },
+    /// Lost the thief and is calling for help.
+    Alarmed(Timer),
+}
+
+impl OwnerBehaviour {
+    pub fn is_suspicious(&self) -> bool {
+        return matches!(
+            self,
+            OwnerBehaviour::Chasing { .. } | OwnerBehaviour::Alarmed(_)
+        );
+    }
+}
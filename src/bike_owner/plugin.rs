@@ -0,0 +1,23 @@
+use bevy::prelude::*;
+
+use crate::{simulation_state::SimulationState, GameState};
+
+use super::resources::*;
+use super::systems::*;
+
+pub struct BikeOwnerPlugin;
+
+impl Plugin for BikeOwnerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<BikeOwnerEvent>()
+            .init_resource::<BikeOwnerSettings>()
+            .add_systems(OnEnter(GameState::Playing), spawn_owners)
+            .add_systems(
+                Update,
+                (notice_theft, update_behaviour, move_owners, handle_events)
+                    .chain()
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(in_state(SimulationState::Running)),
+            );
+    }
+}
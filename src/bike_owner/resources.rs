@@ -0,0 +1,50 @@
+use std::ops::Range;
+
+use bevy::prelude::*;
+
+#[derive(Resource)]
+pub struct BikeOwnerSettings {
+    /// Chance of a bicycle without an owner getting one.
+    pub owner_chance: f32,
+    /// Other bicycles inside of this radius are owned by the same owner.
+    pub shared_ownership_radius: f32,
+    /// How far away from their bicycles owners go.
+    pub away_distance: f32,
+    pub away_time: Range<f32>,
+    pub stay_time: Range<f32>,
+    pub walk_speed: f32,
+    pub run_speed: f32,
+    /// Distance at which an owner catches the thief.
+    pub catch_distance: f32,
+    pub chase_time: f32,
+    pub alarm_time: f32,
+    pub catch_penalty: u32,
+    pub alarm_penalty: u32,
+}
+
+impl Default for BikeOwnerSettings {
+    fn default() -> Self {
+        Self {
+            owner_chance: 0.4,
+            shared_ownership_radius: 6.0,
+            away_distance: 25.0,
+            away_time: 30.0..90.0,
+            stay_time: 5.0..15.0,
+            walk_speed: 2.0,
+            run_speed: 6.0,
+            catch_distance: 1.5,
+            chase_time: 10.0,
+            alarm_time: 4.0,
+            catch_penalty: 3,
+            alarm_penalty: 1,
+        }
+    }
+}
+
+#[derive(Event)]
+pub enum BikeOwnerEvent {
+    /// The owner caught the thief.
+    Caught { owner: Entity, thief: Entity },
+    /// The owner lost the thief and raised an alarm.
+    Alarm { owner: Entity },
+}
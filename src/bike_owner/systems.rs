@@ -0,0 +1,326 @@
+use std::f32::consts::TAU;
+
+use bevy::{prelude::*, utils::HashSet};
+use rand::{thread_rng, Rng};
+
+use crate::{
+    car_controller::BicycleLoadedEvent,
+    lockpicking::LockPicker,
+    npc::{horizontal_distance, walk_towards, ARRIVE_DISTANCE},
+    perception::{Perceived, Perception},
+    player_controller::{
+        lockpicking::{LockPickEvent, StopPickReason},
+        pick_up::Hand,
+        Player,
+    },
+    points::PointsAction,
+    world_spawning::on_spawn::Bicycle,
+};
+
+use super::components::*;
+use super::resources::*;
+
+pub fn spawn_owners(
+    q_bicycle: Query<(Entity, &GlobalTransform), (With<Bicycle>, Without<Owned>)>,
+    settings: Res<BikeOwnerSettings>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut commands: Commands,
+) {
+    let mut rng = thread_rng();
+
+    let mesh = meshes.add(Capsule3d::new(0.3, 1.0));
+    let material = materials.add(Color::srgb(0.3, 0.4, 0.8));
+
+    let bicycles: Vec<(Entity, Vec3)> = q_bicycle
+        .iter()
+        .map(|(entity, gtransform)| (entity, gtransform.translation()))
+        .collect();
+
+    let mut owned: HashSet<Entity> = HashSet::new();
+
+    for (bicycle_entity, position) in bicycles.iter() {
+        if owned.contains(bicycle_entity) || !rng.gen_bool(settings.owner_chance as f64) {
+            continue;
+        }
+
+        // bicycles parked close to each other belong to the same owner
+        let owned_bicycles: Vec<Entity> = bicycles
+            .iter()
+            .filter(|(entity, other_position)| {
+                !owned.contains(entity)
+                    && other_position.distance(*position) <= settings.shared_ownership_radius
+            })
+            .map(|(entity, _)| *entity)
+            .collect();
+
+        let angle = rng.gen_range(0.0..TAU);
+        let away_position =
+            *position + Vec3::new(angle.cos(), 0.0, angle.sin()) * settings.away_distance;
+
+        let owner_entity = commands
+            .spawn((
+                Name::new("Bike Owner"),
+                PbrBundle {
+                    mesh: mesh.clone(),
+                    material: material.clone(),
+                    transform: Transform::from_translation(away_position),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                BikeOwner {
+                    bicycles: owned_bicycles.clone(),
+                    away_position,
+                },
+                OwnerBehaviour::Away(Timer::from_seconds(
+                    rng.gen_range(settings.away_time.clone()),
+                    TimerMode::Once,
+                )),
+                Perception::default(),
+                Perceived::default(),
+            ))
+            .id();
+
+        for owned_bicycle in owned_bicycles {
+            commands.entity(owned_bicycle).insert(Owned {
+                owner: owner_entity,
+            });
+            owned.insert(owned_bicycle);
+        }
+    }
+}
+
+/// Starts a chase when an owner notices somebody lockpicking, carrying or loading one of
+/// their bicycles.
+pub fn notice_theft(
+    mut q_owner: Query<(&mut BikeOwner, &Perceived, &mut OwnerBehaviour)>,
+    q_player: Query<Option<&LockPicker>, With<Player>>,
+    q_bicycle: Query<(), With<Bicycle>>,
+    mut bicycle_loaded_er: EventReader<BicycleLoadedEvent>,
+    hand: Res<Hand>,
+    settings: Res<BikeOwnerSettings>,
+) {
+    let loaded: HashSet<Entity> = bicycle_loaded_er.read().map(|ev| ev.bicycle).collect();
+
+    for (mut owner, perceived, mut behaviour) in q_owner.iter_mut() {
+        let loading = owner
+            .bicycles
+            .iter()
+            .any(|bicycle| loaded.contains(bicycle));
+
+        // bicycles that are loaded onto the car stop existing, their theft is only noticed while
+        // it happens
+        owner.bicycles.retain(|bicycle| q_bicycle.contains(*bicycle));
+
+        if behaviour.is_suspicious() || matches!(*behaviour, OwnerBehaviour::Away(_)) {
+            continue;
+        }
+
+        let mut thief_entity = None;
+
+        for entity in perceived.seen.iter().chain(perceived.heard.iter()) {
+            let Ok(picker) = q_player.get(*entity) else {
+                continue;
+            };
+
+            let lockpicking = picker
+                .and_then(|picker| picker.target)
+                .is_some_and(|target| owner.bicycles.contains(&target));
+
+            let carrying = match *hand {
+                Hand::Some { entity, .. } => owner.bicycles.contains(&entity),
+                Hand::Empty => false,
+            };
+
+            let seen = perceived.seen.contains(entity);
+
+            if lockpicking || (seen && (carrying || loading)) {
+                thief_entity = Some(*entity);
+                break;
+            }
+        }
+
+        if let Some(target) = thief_entity {
+            *behaviour = OwnerBehaviour::Chasing {
+                target,
+                timer: Timer::from_seconds(settings.chase_time, TimerMode::Once),
+            };
+        }
+    }
+}
+
+pub fn update_behaviour(
+    time: Res<Time>,
+    settings: Res<BikeOwnerSettings>,
+    mut q_owner: Query<(
+        Entity,
+        &mut BikeOwner,
+        &mut OwnerBehaviour,
+        &mut Visibility,
+        &Perceived,
+        &Transform,
+    )>,
+    q_target: Query<&GlobalTransform, Without<BikeOwner>>,
+    mut owner_ew: EventWriter<BikeOwnerEvent>,
+) {
+    let mut rng = thread_rng();
+
+    for (owner_entity, mut owner, mut behaviour, mut visibility, perceived, transform) in
+        q_owner.iter_mut()
+    {
+        let position = transform.translation;
+
+        let next = match &mut *behaviour {
+            OwnerBehaviour::Away(timer) => {
+                // forget about bicycles that were taken while away
+                owner.bicycles.retain(|bicycle| q_target.contains(*bicycle));
+
+                if !owner.bicycles.is_empty() && timer.tick(time.delta()).finished() {
+                    *visibility = Visibility::Inherited;
+                    Some(OwnerBehaviour::Returning)
+                } else {
+                    None
+                }
+            }
+            OwnerBehaviour::Returning => match closest_bicycle(&owner, position, &q_target) {
+                None => Some(OwnerBehaviour::Leaving),
+                Some(target) if horizontal_distance(position, target) <= ARRIVE_DISTANCE => {
+                    Some(OwnerBehaviour::AtBicycle(Timer::from_seconds(
+                        rng.gen_range(settings.stay_time.clone()),
+                        TimerMode::Once,
+                    )))
+                }
+                Some(_) => None,
+            },
+            OwnerBehaviour::AtBicycle(timer) => {
+                if timer.tick(time.delta()).finished() {
+                    Some(OwnerBehaviour::Leaving)
+                } else {
+                    None
+                }
+            }
+            OwnerBehaviour::Leaving => {
+                if horizontal_distance(position, owner.away_position) <= ARRIVE_DISTANCE {
+                    *visibility = Visibility::Hidden;
+                    Some(OwnerBehaviour::Away(Timer::from_seconds(
+                        rng.gen_range(settings.away_time.clone()),
+                        TimerMode::Once,
+                    )))
+                } else {
+                    None
+                }
+            }
+            OwnerBehaviour::Chasing { target, timer } => match q_target.get(*target) {
+                Err(_) => Some(OwnerBehaviour::Leaving),
+                Ok(target_gtransform) => {
+                    if horizontal_distance(position, target_gtransform.translation())
+                        <= settings.catch_distance
+                    {
+                        owner_ew.send(BikeOwnerEvent::Caught {
+                            owner: owner_entity,
+                            thief: *target,
+                        });
+                        Some(OwnerBehaviour::Leaving)
+                    } else {
+                        // the owner only gives up once the thief is out of sight
+                        if perceived.seen.contains(target) {
+                            timer.reset();
+                        }
+
+                        if timer.tick(time.delta()).finished() {
+                            owner_ew.send(BikeOwnerEvent::Alarm {
+                                owner: owner_entity,
+                            });
+                            Some(OwnerBehaviour::Alarmed(Timer::from_seconds(
+                                settings.alarm_time,
+                                TimerMode::Once,
+                            )))
+                        } else {
+                            None
+                        }
+                    }
+                }
+            },
+            OwnerBehaviour::Alarmed(timer) => {
+                if timer.tick(time.delta()).finished() {
+                    Some(OwnerBehaviour::Leaving)
+                } else {
+                    None
+                }
+            }
+        };
+
+        if let Some(next) = next {
+            *behaviour = next;
+        }
+    }
+}
+
+pub fn move_owners(
+    time: Res<Time>,
+    settings: Res<BikeOwnerSettings>,
+    mut q_owner: Query<(&BikeOwner, &OwnerBehaviour, &mut Transform)>,
+    q_target: Query<&GlobalTransform, Without<BikeOwner>>,
+) {
+    for (owner, behaviour, mut transform) in q_owner.iter_mut() {
+        let (target, speed) = match behaviour {
+            OwnerBehaviour::Returning => (
+                closest_bicycle(owner, transform.translation, &q_target),
+                settings.walk_speed,
+            ),
+            OwnerBehaviour::Leaving => (Some(owner.away_position), settings.walk_speed),
+            OwnerBehaviour::Chasing { target, .. } => (
+                q_target
+                    .get(*target)
+                    .ok()
+                    .map(|gtransform| gtransform.translation()),
+                settings.run_speed,
+            ),
+            _ => continue,
+        };
+
+        if let Some(target) = target {
+            walk_towards(&mut transform, target, speed, time.delta_seconds());
+        }
+    }
+}
+
+pub fn handle_events(
+    mut owner_er: EventReader<BikeOwnerEvent>,
+    settings: Res<BikeOwnerSettings>,
+    q_picker: Query<&LockPicker>,
+    mut points_action_ew: EventWriter<PointsAction>,
+    mut lock_pick_ew: EventWriter<LockPickEvent>,
+) {
+    for ev in owner_er.read() {
+        match ev {
+            BikeOwnerEvent::Caught { thief, .. } => {
+                points_action_ew.send(PointsAction::Decrement(settings.catch_penalty));
+
+                // getting caught ends the lockpicking attempt
+                if q_picker.get(*thief).is_ok_and(|picker| picker.target.is_some()) {
+                    lock_pick_ew.send(LockPickEvent::StopPick {
+                        picker: *thief,
+                        reason: StopPickReason::Caught,
+                    });
+                }
+            }
+            BikeOwnerEvent::Alarm { .. } => {
+                points_action_ew.send(PointsAction::Decrement(settings.alarm_penalty));
+            }
+        }
+    }
+}
+
+fn closest_bicycle(
+    owner: &BikeOwner,
+    position: Vec3,
+    q_target: &Query<&GlobalTransform, Without<BikeOwner>>,
+) -> Option<Vec3> {
+    return owner
+        .bicycles
+        .iter()
+        .filter_map(|bicycle| q_target.get(*bicycle).ok())
+        .map(|gtransform| gtransform.translation())
+        .min_by(|a, b| a.distance(position).total_cmp(&b.distance(position)));
+}
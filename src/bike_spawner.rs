@@ -0,0 +1,162 @@
+use std::time::Duration;
+
+use bevy::{
+    gltf::{GltfMesh, GltfNode},
+    prelude::*,
+};
+use rand::{seq::IteratorRandom, thread_rng, Rng};
+
+use crate::{
+    levels::reset_resource,
+    simulation_state::SimulationState,
+    world_spawning::{
+        on_spawn::{Bicycle, ParkingSpot},
+        spawn_node, CityPieces, NodeExtras, SpawnHook,
+    },
+    GameState,
+};
+
+// The city refills in cycles: every cycle a few bicycles are parked at free parking spots. The
+// bicycles are made from the `Bicycle` piece of `city_pieces.glb`, their value and lock are
+// rolled when they are spawned, like the bicycles of the map.
+
+pub fn plugin(app: &mut App) {
+    app.register_type::<BikeSpawnerSettings>()
+        .init_resource::<BikeSpawnerSettings>()
+        .init_resource::<BikeSpawner>()
+        .add_systems(
+            OnEnter(GameState::LoadingLevel),
+            reset_resource::<BikeSpawner>,
+        )
+        .add_systems(
+            Update,
+            park_bicycles
+                .run_if(in_state(GameState::Playing))
+                .run_if(in_state(SimulationState::Running)),
+        );
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct BikeSpawnerSettings {
+    /// Seconds between two refills.
+    pub cycle_length: f32,
+    pub bicycles_per_cycle: u32,
+    /// No bicycles are parked while there are this many in the city.
+    pub max_bicycles: usize,
+    /// Share of the parked bicycles that are parked illegally, from 0 to 1.
+    pub illegal_ratio: f32,
+    /// A parking spot is taken when a bicycle is closer to it than this.
+    pub spot_radius: f32,
+}
+
+impl Default for BikeSpawnerSettings {
+    fn default() -> Self {
+        return Self {
+            cycle_length: 60.0,
+            bicycles_per_cycle: 4,
+            max_bicycles: 40,
+            illegal_ratio: 0.6,
+            spot_radius: 1.0,
+        };
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct BikeSpawner {
+    timer: Timer,
+    parked: u32,
+}
+
+fn park_bicycles(
+    time: Res<Time>,
+    settings: Res<BikeSpawnerSettings>,
+    mut spawner: ResMut<BikeSpawner>,
+    q_parking_spot: Query<(&ParkingSpot, &GlobalTransform)>,
+    q_bicycle: Query<&GlobalTransform, With<Bicycle>>,
+    city_pieces: Res<CityPieces>,
+    gltfs: Res<Assets<Gltf>>,
+    gltf_nodes: Res<Assets<GltfNode>>,
+    gltf_meshes: Res<Assets<GltfMesh>>,
+    spawn_hook: Res<SpawnHook>,
+    mut commands: Commands,
+) {
+    let cycle_length = Duration::from_secs_f32(settings.cycle_length.max(1.0));
+    if spawner.timer.duration() != cycle_length {
+        spawner.timer = Timer::new(cycle_length, TimerMode::Repeating);
+    }
+
+    if !spawner.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    // levels that aren't generated may be played without the city pieces
+    let Some(city_pieces) = gltfs.get(&city_pieces.0) else {
+        return;
+    };
+
+    let Some((template_handle, template)) = city_pieces
+        .named_nodes
+        .get("Bicycle")
+        .and_then(|handle| Some((handle, gltf_nodes.get(handle)?)))
+    else {
+        warn!("there is no bicycle piece to park");
+        return;
+    };
+
+    let bicycles: Vec<Vec3> = q_bicycle
+        .iter()
+        .map(|gtransform| gtransform.translation())
+        .collect();
+
+    let mut free_spots: Vec<(ParkingSpot, Transform)> = q_parking_spot
+        .iter()
+        .filter(|(_, gtransform)| {
+            bicycles
+                .iter()
+                .all(|bicycle| bicycle.distance(gtransform.translation()) > settings.spot_radius)
+        })
+        .map(|(spot, gtransform)| (*spot, gtransform.compute_transform()))
+        .collect();
+
+    let count = (settings.bicycles_per_cycle as usize)
+        .min(settings.max_bicycles.saturating_sub(bicycles.len()));
+
+    let mut rng = thread_rng();
+
+    for _ in 0..count {
+        let illegal = rng.gen_bool(settings.illegal_ratio.clamp(0.0, 1.0) as f64);
+
+        // the other kind of spot is used when all spots of a kind are taken
+        let Some(index) = free_spots
+            .iter()
+            .enumerate()
+            .filter(|(_, (spot, _))| spot.illegal == illegal)
+            .map(|(index, _)| index)
+            .choose(&mut rng)
+            .or_else(|| (0..free_spots.len()).choose(&mut rng))
+        else {
+            break;
+        };
+
+        let (spot, spot_transform) = free_spots.swap_remove(index);
+        let keywords = if spot.illegal {
+            "Bicycle Illegal"
+        } else {
+            "Bicycle"
+        };
+
+        spawner.parked += 1;
+
+        spawn_node(
+            &format!("{}.{:03}", keywords, spawner.parked),
+            ParkingSpot::bicycle_transform(spot_transform) * template.transform,
+            template_handle,
+            NodeExtras::parse("Bicycle", template.extras.as_ref()),
+            &gltf_nodes,
+            &gltf_meshes,
+            &spawn_hook,
+            &mut commands,
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use bevy::{prelude::*, transform::TransformSystem};
+use bevy_camera_extras::{CameraControls, CameraMode};
+
+use crate::damping::SmoothDamp;
+
+// The camera extras put the camera behind the car every frame, so every bump of the hovering car
+// shakes the camera. This smooths the position the camera extras picked. In first person the
+// camera stays on the head of the player.
+
+pub fn plugin(app: &mut App) {
+    app.register_type::<CameraFollowSettings>()
+        .init_resource::<CameraFollowSettings>()
+        .add_systems(Update, add_camera_follow)
+        .add_systems(
+            PostUpdate,
+            follow.before(TransformSystem::TransformPropagate),
+        );
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct CameraFollowSettings {
+    pub smooth_time: f32,
+    pub max_speed: f32,
+}
+
+impl Default for CameraFollowSettings {
+    fn default() -> Self {
+        return Self {
+            smooth_time: 0.15,
+            max_speed: 60.0,
+        };
+    }
+}
+
+#[derive(Component)]
+pub struct CameraFollow {
+    /// The smoothed position, `None` until the camera follows something in third person.
+    position: Option<Vec3>,
+    attached_to: Entity,
+}
+
+fn add_camera_follow(
+    settings: Res<CameraFollowSettings>,
+    q_camera: Query<(Entity, &CameraControls), Added<CameraControls>>,
+    mut commands: Commands,
+) {
+    for (camera_entity, camera_controls) in q_camera.iter() {
+        commands.entity(camera_entity).insert((
+            CameraFollow {
+                position: None,
+                attached_to: camera_controls.attach_to,
+            },
+            SmoothDamp::new(settings.smooth_time).with_max_speed(settings.max_speed),
+        ));
+    }
+}
+
+fn follow(
+    time: Res<Time>,
+    settings: Res<CameraFollowSettings>,
+    mut q_camera: Query<(
+        &mut Transform,
+        &CameraControls,
+        &mut CameraFollow,
+        &mut SmoothDamp,
+    )>,
+) {
+    for (mut transform, camera_controls, mut camera_follow, mut smooth_damp) in q_camera.iter_mut()
+    {
+        smooth_damp.smooth_time = settings.smooth_time;
+        smooth_damp.max_speed = settings.max_speed;
+
+        let third_person = matches!(camera_controls.camera_mode, CameraMode::ThirdPerson(_));
+
+        // getting into or out of the car moves the camera at once
+        if !third_person || camera_follow.attached_to != camera_controls.attach_to {
+            camera_follow.position = None;
+            camera_follow.attached_to = camera_controls.attach_to;
+            smooth_damp.reset();
+        }
+
+        if !third_person {
+            continue;
+        }
+
+        let target = transform.translation;
+        let position = camera_follow.position.unwrap_or(target);
+        let smoothed = smooth_damp.calculate(position, target, time.delta_seconds());
+
+        camera_follow.position = Some(smoothed);
+        transform.translation = smoothed;
+    }
+}
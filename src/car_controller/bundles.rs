@@ -1,6 +1,7 @@
 use avian3d::{math::*, prelude::*};
 use bevy::prelude::*;
 
+use crate::damping::{Pid, PidConfig};
 use crate::player_car_swap::Ridable;
 use crate::player_controller::CollisionMask;
 
@@ -22,7 +23,7 @@ pub struct MovementBundle {
     pub acceleration: MovementAcceleration,
     pub damping: MovementDampingFactor,
     pub behaviour: CarBehaviour,
-    pub pid: PID,
+    pub pid: Pid,
 }
 
 impl MovementBundle {
@@ -34,7 +35,7 @@ impl MovementBundle {
         float_amplitude: Scalar,
         float_period: Scalar,
         gas_mileage: Scalar,
-        pid: PID,
+        pid: Pid,
     ) -> Self {
         Self {
             acceleration: MovementAcceleration {
@@ -53,9 +54,18 @@ impl MovementBundle {
     }
 }
 
+/// Keeps the car at its float height by setting its vertical velocity.
+fn hover_pid() -> Pid {
+    return Pid::new(
+        PidConfig::new(2.5, 0.25, 0.025)
+            .with_integral_limit(1.0)
+            .with_output_limit(10.0),
+    );
+}
+
 impl Default for MovementBundle {
     fn default() -> Self {
-        Self::new(30.0, 20.0, 0.9, 0.75, 0.4, 3.0, 0.1, PID::default())
+        Self::new(30.0, 20.0, 0.9, 0.75, 0.4, 3.0, 0.1, hover_pid())
     }
 }
 
@@ -92,7 +102,7 @@ impl CarControllerBundle {
             float_amplitude,
             float_period,
             gas_mileage,
-            PID::default(),
+            hover_pid(),
         );
         self
     }
@@ -8,5 +8,5 @@ pub mod ui;
 pub use bundles::CarControllerBundle;
 pub use components::CarController;
 pub use plugin::CarControllerPlugin;
-pub use resources::{CarAction, CarDimensions, CarProperties};
+pub use resources::{BicycleLoadedEvent, CarAction, CarDimensions, CarProperties};
 
@@ -11,6 +11,7 @@ pub struct CarControllerPlugin;
 impl Plugin for CarControllerPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<CarAction>()
+            .add_event::<BicycleLoadedEvent>()
             .add_event::<MovementAction>()
             .add_plugins(CarUIPlugin)
             .add_systems(
@@ -12,6 +12,14 @@ pub enum CarAction {
     Refuel,
 }
 
+/// Sent when a bicycle gets stuck to the bed of a car.
+#[derive(Event)]
+pub struct BicycleLoadedEvent {
+    /// The bicycle that was loaded, it is despawned when it gets stuck.
+    pub bicycle: Entity,
+    pub illegal: bool,
+}
+
 #[derive(Resource)]
 pub struct CarDimensions {
     pub length: f32,
@@ -1,6 +1,7 @@
 use avian3d::{math::*, prelude::*};
 use bevy::prelude::*;
 
+use crate::damping::Pid;
 use crate::lockpicking::Locked;
 use crate::player_car_swap::{Ridable, Rider};
 use crate::world_spawning::on_spawn::{Bicycle, Illegal, MapElement};
@@ -103,7 +104,7 @@ pub fn handle_car_actions(
 
 pub fn make_car_float(
     time: Res<Time>,
-    mut controllers: Query<(&CarBehaviour, &mut PID, &mut LinearVelocity)>,
+    mut controllers: Query<(&CarBehaviour, &mut Pid, &mut LinearVelocity)>,
     q_car_transform: Query<&Transform, With<CarController>>,
     q_entities: Query<(Option<&Parent>, Option<&MapElement>)>,
     spatial_query: SpatialQuery,
@@ -136,7 +137,10 @@ pub fn make_car_float(
                 * behaviour.float_amplitude
                 + behaviour.float_height;
             linear_velocity.y =
-                pid.compute(desired_height, hit.time_of_impact, time.delta_seconds());
+                pid.update(desired_height, hit.time_of_impact, time.delta_seconds());
+        } else {
+            // the car is off the ground, the integral and the height are stale when it lands
+            pid.reset();
         }
     }
 }
@@ -176,6 +180,7 @@ pub fn stick_bicycles(
         (&GlobalTransform, &Children, Option<&Illegal>),
         (With<Bicycle>, Without<CarController>),
     >,
+    mut bicycle_loaded_ew: EventWriter<BicycleLoadedEvent>,
     mut commands: Commands,
 ) {
     let car_entity = q_car_controller.get_single().unwrap();
@@ -205,6 +210,11 @@ pub fn stick_bicycles(
 
                     let sticked_bicycle = sticked_bicycle_commands.id();
 
+                    bicycle_loaded_ew.send(BicycleLoadedEvent {
+                        bicycle: parent_entity,
+                        illegal: illegal.is_some(),
+                    });
+
                     for child_entity in children {
                         commands.entity(*child_entity).set_parent(sticked_bicycle);
                     }
@@ -1,23 +1,32 @@
+use std::{f32::consts::PI, fmt};
+
 use bevy::{
     asset::LoadState,
     prelude::*,
     render::render_resource::{
-        Extent3d, TextureDescriptor, TextureViewDescriptor, TextureViewDimension,
+        Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureViewDescriptor,
+        TextureViewDimension,
     },
 };
 
 /// Usage
 ///
-/// 1: Add the `CustomSkyboxPlugin` to the app.
+/// 1: Add the `CubemapFactoryPlugin` to the app.
 ///
-/// 2: Query the `CubemapFactory` resource and call `load_from_folder` on it.
+/// 2: Query the `CubemapFactory` resource and call one of the `load_*` functions on it, the
+/// returned handle gets the cubemap once all source images are loaded.
 ///
+/// 3: Optionally listen for [`CubemapReady`] and [`CubemapFailed`] events.
 ///
+/// The sources can be six separate faces, a single image with the faces laid out as a cross, or
+/// an equirectangular panorama that is converted on the CPU.
 pub struct CubemapFactoryPlugin;
 
 impl Plugin for CubemapFactoryPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(CubemapFactory::default())
+            .add_event::<CubemapReady>()
+            .add_event::<CubemapFailed>()
             .add_systems(Update, create_cubemap.run_if(factory_non_empty));
     }
 }
@@ -62,21 +71,128 @@ impl CubemapHandle {
     }
 }
 
+/// The images a cubemap is made from.
+#[derive(Clone, PartialEq, Eq)]
+pub enum CubemapSource {
+    /// Six square images of the same size, one per face.
+    Faces(CubemapHandle),
+    /// One image with the faces laid out as a horizontal (4 x 3 faces) or vertical (3 x 4 faces)
+    /// cross. The layout is picked from the size of the image.
+    Cross(Handle<Image>),
+    /// A panorama with a 2:1 aspect ratio, every face of the cubemap is `face_size` pixels wide.
+    Equirectangular {
+        image: Handle<Image>,
+        face_size: u32,
+    },
+}
+
+impl CubemapSource {
+    fn images(&self) -> Vec<Handle<Image>> {
+        return match self {
+            CubemapSource::Faces(cubemap_handle) => cubemap_handle.as_vec(),
+            CubemapSource::Cross(image) => vec![image.clone()],
+            CubemapSource::Equirectangular { image, .. } => vec![image.clone()],
+        };
+    }
+}
+
+/// Sent when a cubemap was created and its handle can be used.
+#[derive(Event, Clone)]
+pub struct CubemapReady {
+    pub cubemap: Handle<Image>,
+}
+
+/// Sent when a cubemap could not be created, the handle stays empty.
+#[derive(Event, Clone)]
+pub struct CubemapFailed {
+    pub cubemap: Handle<Image>,
+    pub error: CubemapError,
+}
+
+#[derive(Clone, Debug)]
+pub enum CubemapError {
+    /// A source image could not be loaded.
+    LoadFailed(String),
+    /// The faces have to be squares.
+    NotSquare {
+        width: u32,
+        height: u32,
+    },
+    FaceSizeMismatch {
+        expected: u32,
+        found: u32,
+    },
+    FormatMismatch {
+        expected: TextureFormat,
+        found: TextureFormat,
+    },
+    /// Only formats with whole pixels can be copied, not compressed ones.
+    UnsupportedFormat(TextureFormat),
+    /// The size of a cross image isn't 4 x 3 or 3 x 4 faces.
+    BadCrossLayout {
+        width: u32,
+        height: u32,
+    },
+    /// The image has less data than its size says.
+    MissingData,
+}
+
+impl fmt::Display for CubemapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            CubemapError::LoadFailed(err) => write!(f, "could not load a source image: {}", err),
+            CubemapError::NotSquare { width, height } => {
+                write!(f, "faces have to be square, found {}x{}", width, height)
+            }
+            CubemapError::FaceSizeMismatch { expected, found } => write!(
+                f,
+                "faces have different sizes, expected {} but found {}",
+                expected, found
+            ),
+            CubemapError::FormatMismatch { expected, found } => write!(
+                f,
+                "faces have different formats, expected {:?} but found {:?}",
+                expected, found
+            ),
+            CubemapError::UnsupportedFormat(format) => {
+                write!(f, "the format {:?} is not supported", format)
+            }
+            CubemapError::BadCrossLayout { width, height } => write!(
+                f,
+                "a {}x{} image is not a horizontal or vertical cross",
+                width, height
+            ),
+            CubemapError::MissingData => write!(f, "an image has less data than its size"),
+        };
+    }
+}
+
+impl std::error::Error for CubemapError {}
+
 #[derive(Resource, Default)]
-pub struct CubemapFactory(Vec<(Handle<Image>, CubemapHandle)>);
+pub struct CubemapFactory(Vec<(Handle<Image>, CubemapSource)>);
 
 impl CubemapFactory {
-    pub fn create_from_cubemap_handle(
+    pub fn create_from_source(
         &mut self,
-        cubemap_handle: CubemapHandle,
+        source: CubemapSource,
         images: Res<Assets<Image>>,
     ) -> Handle<Image> {
         let handle = images.reserve_handle();
-        self.0.push((handle.clone(), cubemap_handle));
+        self.0.push((handle.clone(), source));
 
         return handle;
     }
 
+    pub fn create_from_cubemap_handle(
+        &mut self,
+        cubemap_handle: CubemapHandle,
+        images: Res<Assets<Image>>,
+    ) -> Handle<Image> {
+        return self.create_from_source(CubemapSource::Faces(cubemap_handle), images);
+    }
+
+    /// Loads the faces `px.png`, `nx.png`, `py.png`, `ny.png`, `pz.png` and `nz.png` of a folder.
     pub fn load_from_folder(
         &mut self,
         path: impl Into<String>,
@@ -86,68 +202,338 @@ impl CubemapFactory {
         let cubemap_handle = CubemapHandle::load(path, asset_server);
         return self.create_from_cubemap_handle(cubemap_handle, images);
     }
+
+    /// Loads an image with the faces laid out as a cross.
+    pub fn load_cross(
+        &mut self,
+        path: impl Into<String>,
+        asset_server: Res<AssetServer>,
+        images: Res<Assets<Image>>,
+    ) -> Handle<Image> {
+        let image = asset_server.load(path.into());
+        return self.create_from_source(CubemapSource::Cross(image), images);
+    }
+
+    /// Loads an equirectangular panorama.
+    pub fn load_equirectangular(
+        &mut self,
+        path: impl Into<String>,
+        face_size: u32,
+        asset_server: Res<AssetServer>,
+        images: Res<Assets<Image>>,
+    ) -> Handle<Image> {
+        let image = asset_server.load(path.into());
+        return self
+            .create_from_source(CubemapSource::Equirectangular { image, face_size }, images);
+    }
+}
+
+enum SourceState {
+    Loading,
+    Loaded,
+    Failed(CubemapError),
+}
+
+fn source_state(
+    source: &CubemapSource,
+    assets: &AssetServer,
+    images: &Assets<Image>,
+) -> SourceState {
+    for handle in source.images() {
+        // images that were added by hand aren't known to the asset server
+        if images.contains(&handle) {
+            continue;
+        }
+
+        match assets.get_load_state(&handle) {
+            Some(LoadState::Failed(err)) => {
+                return SourceState::Failed(CubemapError::LoadFailed(err.to_string()));
+            }
+            None => {
+                return SourceState::Failed(CubemapError::LoadFailed(
+                    "the image is not loading".to_string(),
+                ));
+            }
+            Some(_) => return SourceState::Loading,
+        }
+    }
+
+    return SourceState::Loaded;
 }
 
 fn create_cubemap(
     mut cubemap_factory: ResMut<CubemapFactory>,
     assets: Res<AssetServer>,
     mut images: ResMut<Assets<Image>>,
+    mut ready_ew: EventWriter<CubemapReady>,
+    mut failed_ew: EventWriter<CubemapFailed>,
 ) {
-    let mut to_be_removed: Vec<usize> = Vec::new();
+    // cubemaps that are still loading go back into the queue
+    let pending = std::mem::take(&mut cubemap_factory.0);
 
-    'blk: for (i, (result_handle, cubemap_handle)) in cubemap_factory.0.iter().enumerate() {
-        let handles = cubemap_handle.as_vec();
+    for (result_handle, source) in pending {
+        let result = match source_state(&source, &assets, &images) {
+            SourceState::Loading => {
+                cubemap_factory.0.push((result_handle, source));
+                continue;
+            }
+            SourceState::Failed(error) => Err(error),
+            SourceState::Loaded => build_cubemap(&source, &images),
+        };
 
-        for handle in handles.iter() {
-            if assets.get_load_state(handle).unwrap() != LoadState::Loaded {
-                continue 'blk;
+        match result {
+            Ok(image) => {
+                images.insert(&result_handle, image);
+                ready_ew.send(CubemapReady {
+                    cubemap: result_handle,
+                });
+            }
+            Err(error) => {
+                error!("failed to create a cubemap: {}", error);
+                failed_ew.send(CubemapFailed {
+                    cubemap: result_handle,
+                    error,
+                });
             }
         }
+    }
+}
 
-        let textures: Vec<Image> = handles
-            .into_iter()
-            .map(|x| images.get(&x).unwrap().clone())
-            .collect();
-
-        let width = textures[0].width();
-        let height = textures[0].height();
-        let array_layers = 6;
+/// A square face of a cubemap, the pixels are stored row by row.
+struct Face {
+    size: u32,
+    data: Vec<u8>,
+}
 
-        let texture_descriptor = textures[0].texture_descriptor.clone();
+fn build_cubemap(source: &CubemapSource, images: &Assets<Image>) -> Result<Image, CubemapError> {
+    let get = |handle: &Handle<Image>| {
+        return images
+            .get(handle)
+            .ok_or_else(|| CubemapError::LoadFailed("the image was removed".to_string()));
+    };
 
-        let mut data: Vec<u8> = vec![0; (width * height * 4 * array_layers) as usize];
-        for (i, image) in textures.into_iter().enumerate() {
-            let offset = (i as u32 * width * height * 4) as usize;
+    let (faces, descriptor) = match source {
+        CubemapSource::Faces(cubemap_handle) => {
+            let textures = cubemap_handle
+                .as_vec()
+                .iter()
+                .map(get)
+                .collect::<Result<Vec<&Image>, CubemapError>>()?;
 
-            let start = offset;
-            let end = offset + image.data.len();
+            let first = textures[0];
+            let faces = textures
+                .iter()
+                .map(|image| face_from_image(image, first))
+                .collect::<Result<Vec<Face>, CubemapError>>()?;
 
-            data[start..end].copy_from_slice(&image.data);
+            (faces, first.texture_descriptor.clone())
+        }
+        CubemapSource::Cross(image) => {
+            let image = get(image)?;
+            (faces_from_cross(image)?, image.texture_descriptor.clone())
         }
+        CubemapSource::Equirectangular { image, face_size } => {
+            let image = get(image)?;
+            (
+                faces_from_equirectangular(image, *face_size)?,
+                image.texture_descriptor.clone(),
+            )
+        }
+    };
+
+    let size = faces[0].size;
+    let data = faces.into_iter().flat_map(|face| face.data).collect();
 
-        let image = Image {
-            data,
-            texture_descriptor: TextureDescriptor {
-                size: Extent3d {
-                    width,
-                    height,
-                    depth_or_array_layers: array_layers,
-                },
-                ..texture_descriptor
+    return Ok(Image {
+        data,
+        texture_descriptor: TextureDescriptor {
+            size: Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: 6,
             },
-            texture_view_descriptor: Some(TextureViewDescriptor {
-                dimension: Some(TextureViewDimension::Cube),
-                ..default()
-            }),
+            mip_level_count: 1,
+            dimension: TextureDimension::D2,
+            ..descriptor
+        },
+        texture_view_descriptor: Some(TextureViewDescriptor {
+            dimension: Some(TextureViewDimension::Cube),
             ..default()
-        };
+        }),
+        ..default()
+    });
+}
+
+/// Bytes per pixel of the format of the image.
+fn pixel_size(image: &Image) -> Result<usize, CubemapError> {
+    let format = image.texture_descriptor.format;
+
+    if format.block_dimensions() != (1, 1) {
+        return Err(CubemapError::UnsupportedFormat(format));
+    }
+
+    return format
+        .block_copy_size(None)
+        .map(|size| size as usize)
+        .ok_or(CubemapError::UnsupportedFormat(format));
+}
+
+/// The first layer of the first mip level of the image.
+fn first_layer(image: &Image) -> Result<&[u8], CubemapError> {
+    let length = image.width() as usize * image.height() as usize * pixel_size(image)?;
+    return image.data.get(..length).ok_or(CubemapError::MissingData);
+}
+
+fn face_from_image(image: &Image, first: &Image) -> Result<Face, CubemapError> {
+    let (width, height) = (image.width(), image.height());
+
+    if width != height {
+        return Err(CubemapError::NotSquare { width, height });
+    }
 
-        images.insert(result_handle, image);
+    if width != first.width() {
+        return Err(CubemapError::FaceSizeMismatch {
+            expected: first.width(),
+            found: width,
+        });
+    }
 
-        to_be_removed.push(i);
+    let (expected, found) = (
+        first.texture_descriptor.format,
+        image.texture_descriptor.format,
+    );
+    if expected != found {
+        return Err(CubemapError::FormatMismatch { expected, found });
     }
 
-    for i in to_be_removed {
-        cubemap_factory.0.remove(i);
+    return Ok(Face {
+        size: width,
+        data: first_layer(image)?.to_vec(),
+    });
+}
+
+fn faces_from_cross(image: &Image) -> Result<Vec<Face>, CubemapError> {
+    let (width, height) = (image.width(), image.height());
+    let bad_layout = CubemapError::BadCrossLayout { width, height };
+
+    // the cell of each face in the order px, nx, py, ny, pz, nz, and whether it's upside down
+    let (size, cells) = if width * 3 == height * 4 && width % 4 == 0 {
+        //    +y
+        // -x +z +x -z
+        //    -y
+        (
+            width / 4,
+            [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (3, 1)].map(|cell| (cell, false)),
+        )
+    } else if width * 4 == height * 3 && width % 3 == 0 {
+        //    +y
+        // -x +z +x
+        //    -y
+        //    -z (upside down)
+        (
+            width / 3,
+            [
+                ((2, 1), false),
+                ((0, 1), false),
+                ((1, 0), false),
+                ((1, 2), false),
+                ((1, 1), false),
+                ((1, 3), true),
+            ],
+        )
+    } else {
+        return Err(bad_layout);
+    };
+
+    let pixel_size = pixel_size(image)?;
+    let data = first_layer(image)?;
+    let row_length = width as usize * pixel_size;
+    let face_row_length = size as usize * pixel_size;
+
+    let faces = cells
+        .into_iter()
+        .map(|((column, row), upside_down)| {
+            let mut face = Vec::with_capacity(face_row_length * size as usize);
+
+            for y in 0..size as usize {
+                let source_y = row as usize * size as usize + y;
+                let start = source_y * row_length + column as usize * face_row_length;
+                let source_row = &data[start..start + face_row_length];
+
+                face.extend_from_slice(source_row);
+            }
+
+            if upside_down {
+                face = face
+                    .chunks_exact(pixel_size)
+                    .rev()
+                    .flatten()
+                    .copied()
+                    .collect();
+            }
+
+            return Face { size, data: face };
+        })
+        .collect();
+
+    return Ok(faces);
+}
+
+/// Samples the panorama in the direction of every pixel of every face. The nearest pixel is
+/// copied, so any uncompressed format works.
+fn faces_from_equirectangular(image: &Image, face_size: u32) -> Result<Vec<Face>, CubemapError> {
+    let (width, height) = (image.width(), image.height());
+    let pixel_size = pixel_size(image)?;
+    let data = first_layer(image)?;
+
+    if face_size == 0 || width == 0 || height == 0 {
+        return Err(CubemapError::NotSquare {
+            width: face_size,
+            height: face_size,
+        });
     }
+
+    // the direction of a point on each face, `u` goes right and `v` goes down on the face
+    let directions: [fn(f32, f32) -> Vec3; 6] = [
+        |u, v| Vec3::new(1.0, -v, -u),
+        |u, v| Vec3::new(-1.0, -v, u),
+        |u, v| Vec3::new(u, 1.0, v),
+        |u, v| Vec3::new(u, -1.0, -v),
+        |u, v| Vec3::new(u, -v, 1.0),
+        |u, v| Vec3::new(-u, -v, -1.0),
+    ];
+
+    let faces = directions
+        .into_iter()
+        .map(|direction| {
+            let mut face = Vec::with_capacity(face_size as usize * face_size as usize * pixel_size);
+
+            for y in 0..face_size {
+                for x in 0..face_size {
+                    let u = (x as f32 + 0.5) / face_size as f32 * 2.0 - 1.0;
+                    let v = (y as f32 + 0.5) / face_size as f32 * 2.0 - 1.0;
+                    let direction = direction(u, v).normalize();
+
+                    let longitude = direction.x.atan2(-direction.z);
+                    let latitude = direction.y.asin();
+
+                    let source_x = ((longitude / (2.0 * PI) + 0.5) * width as f32) as u32;
+                    let source_y = ((0.5 - latitude / PI) * height as f32) as u32;
+
+                    let index = (source_y.min(height - 1) as usize * width as usize
+                        + source_x.min(width - 1) as usize)
+                        * pixel_size;
+
+                    face.extend_from_slice(&data[index..index + pixel_size]);
+                }
+            }
+
+            return Face {
+                size: face_size,
+                data: face,
+            };
+        })
+        .collect();
+
+    return Ok(faces);
 }
@@ -1,23 +1,39 @@
 use bevy::prelude::*;
 
-pub fn reflect_plugin(app: &mut App) {
-    app.register_type::<TransformPid>()
-        .register_type::<SmoothDamp>();
-}
+use crate::{simulation_state::SimulationState, GameState};
 
-#[derive(Component, Default, Reflect)]
-#[reflect(Component)]
-pub struct Pid {
-    pub config: PidConfig,
-    pub integral: f32,
-    pub previous_error: f32,
+// Controllers that move something towards a target over time, shared by everything that needs
+// smooth movement: the hovering car, held objects and the car camera.
+//
+// The PID controllers
+// - take the derivative of the measurement instead of the error, so a jumping setpoint doesn't
+//   kick the output,
+// - stop integrating while the output is saturated and limit the integral, so it doesn't wind up
+//   while the target is out of reach,
+// - clamp their output.
+//
+// Controllers keep state between frames, which is wrong after the game was paused or the
+// controlled thing was teleported. They are reset when the simulation resumes and when a level
+// starts, call `reset` for other changes.
+
+pub fn plugin(app: &mut App) {
+    app.register_type::<Pid>()
+        .register_type::<Vec3Pid>()
+        .register_type::<TransformPid>()
+        .register_type::<SmoothDamp>()
+        .add_systems(OnEnter(SimulationState::Running), reset_controllers)
+        .add_systems(OnEnter(GameState::Playing), reset_controllers);
 }
 
-#[derive(Reflect)]
+#[derive(Reflect, Clone, Copy, Debug)]
 pub struct PidConfig {
     pub kp: f32,
     pub ki: f32,
     pub kd: f32,
+    /// Largest absolute value of the integral term, i.e. `ki * integral`.
+    pub integral_limit: f32,
+    /// Largest absolute value of the output, for vectors the largest length.
+    pub output_limit: f32,
 }
 
 impl Default for PidConfig {
@@ -26,58 +42,196 @@ impl Default for PidConfig {
             kp: 2.0,
             ki: 0.1,
             kd: 0.01,
+            integral_limit: f32::INFINITY,
+            output_limit: f32::INFINITY,
         }
     }
 }
 
+impl PidConfig {
+    pub fn new(kp: f32, ki: f32, kd: f32) -> Self {
+        return Self {
+            kp,
+            ki,
+            kd,
+            ..default()
+        };
+    }
+
+    pub fn with_integral_limit(mut self, integral_limit: f32) -> Self {
+        self.integral_limit = integral_limit;
+        return self;
+    }
+
+    pub fn with_output_limit(mut self, output_limit: f32) -> Self {
+        self.output_limit = output_limit;
+        return self;
+    }
+}
+
+#[derive(Component, Reflect, Clone, Default, Debug)]
+#[reflect(Component)]
+pub struct Pid {
+    pub config: PidConfig,
+    integral: f32,
+    previous_measurement: Option<f32>,
+}
+
 impl Pid {
-    // Desired_value should probably be a set point in space, instead of chasing a
-    // moving target, but assuming it's a continuous value in time... it should be somewhat fine.
-    pub fn compute(&mut self, error: f32, delta_time: f32) -> f32 {
-        self.integral += error * delta_time;
-        let derivative = (error - self.previous_error) / delta_time;
-        self.previous_error = error;
-        let config = &self.config;
-        return config.kp * error + config.ki * self.integral + config.kd * derivative;
+    pub fn new(config: PidConfig) -> Self {
+        return Self {
+            config,
+            ..default()
+        };
+    }
+
+    /// Forgets the integral and the previous measurement.
+    pub fn reset(&mut self) {
+        self.integral = 0.0;
+        self.previous_measurement = None;
     }
 
-    pub fn compute_float(&mut self, actual: f32, desired: f32, delta_time: f32) -> f32 {
-        let error = desired - actual;
-        self.compute(error, delta_time)
+    pub fn update(&mut self, setpoint: f32, measurement: f32, delta_time: f32) -> f32 {
+        let config = self.config;
+        let error = setpoint - measurement;
+
+        if delta_time <= 0.0 {
+            return (config.kp * error + config.ki * self.integral)
+                .clamp(-config.output_limit, config.output_limit);
+        }
+
+        let derivative = match self.previous_measurement {
+            Some(previous) => -(measurement - previous) / delta_time,
+            None => 0.0,
+        };
+        self.previous_measurement = Some(measurement);
+
+        let integral = self.integral + error * delta_time;
+        let output = config.kp * error + config.ki * integral + config.kd * derivative;
+
+        // only integrate when it doesn't push a saturated output further out
+        if output.abs() <= config.output_limit || output.signum() != error.signum() {
+            self.integral = integral;
+        }
+
+        if config.ki != 0.0 {
+            let limit = config.integral_limit / config.ki.abs();
+            self.integral = self.integral.clamp(-limit, limit);
+        }
+
+        let output = config.kp * error + config.ki * self.integral + config.kd * derivative;
+        return output.clamp(-config.output_limit, config.output_limit);
     }
 }
 
-#[derive(Reflect, Default)]
+/// A PID controller for every axis, sharing one config.
+#[derive(Component, Reflect, Clone, Default, Debug)]
+#[reflect(Component)]
 pub struct Vec3Pid {
-    pub pid: Pid,
-    pub previous: Vec3,
+    pub config: PidConfig,
+    integral: Vec3,
+    previous_measurement: Option<Vec3>,
 }
 
 impl Vec3Pid {
-    pub fn compute_vec3(&mut self, actual: Vec3, desired: Vec3, delta_time: f32) -> Vec3 {
-        let error = desired - actual;
+    pub fn new(config: PidConfig) -> Self {
+        return Self {
+            config,
+            ..default()
+        };
+    }
+
+    /// Forgets the integral and the previous measurement.
+    pub fn reset(&mut self) {
+        self.integral = Vec3::ZERO;
+        self.previous_measurement = None;
+    }
 
-        let sign = error.normalize().dot(self.previous).signum();
-        let velocity = self.pid.compute(sign * error.length(), delta_time);
+    pub fn update(&mut self, setpoint: Vec3, measurement: Vec3, delta_time: f32) -> Vec3 {
+        let config = self.config;
+        let error = setpoint - measurement;
 
-        println!("{:?}", sign);
+        if delta_time <= 0.0 {
+            return (config.kp * error + config.ki * self.integral)
+                .clamp_length_max(config.output_limit);
+        }
+
+        let derivative = match self.previous_measurement {
+            Some(previous) => -(measurement - previous) / delta_time,
+            None => Vec3::ZERO,
+        };
+        self.previous_measurement = Some(measurement);
 
-        self.previous = error;
+        let integral = self.integral + error * delta_time;
+        let output = config.kp * error + config.ki * integral + config.kd * derivative;
+
+        // only integrate when it doesn't push a saturated output further out
+        if output.length() <= config.output_limit || output.dot(error) <= 0.0 {
+            self.integral = integral;
+        }
 
-        error.normalize() * velocity
+        if config.ki != 0.0 {
+            self.integral = self
+                .integral
+                .clamp_length_max(config.integral_limit / config.ki.abs());
+        }
+
+        let output = config.kp * error + config.ki * self.integral + config.kd * derivative;
+        return output.clamp_length_max(config.output_limit);
     }
 }
 
-#[derive(Reflect, Component, Default)]
+/// Controls the velocities of a body to move it to a transform.
+#[derive(Reflect, Component, Clone, Default, Debug)]
 #[reflect(Component)]
 pub struct TransformPid {
+    /// Gives the linear velocity from the position.
     pub translation: Vec3Pid,
+    /// Gives the angular speed from the angle that is left to turn.
     pub rotation: Pid,
 }
 
-#[derive(Component, Reflect)]
+impl TransformPid {
+    pub fn new(translation: PidConfig, rotation: PidConfig) -> Self {
+        return Self {
+            translation: Vec3Pid::new(translation),
+            rotation: Pid::new(rotation),
+        };
+    }
+
+    pub fn reset(&mut self) {
+        self.translation.reset();
+        self.rotation.reset();
+    }
+
+    /// The linear and the angular velocity that move `current` towards `target`.
+    pub fn update(
+        &mut self,
+        current: &Transform,
+        target: &Transform,
+        delta_time: f32,
+    ) -> (Vec3, Vec3) {
+        let linear_velocity =
+            self.translation
+                .update(target.translation, current.translation, delta_time);
+
+        let (axis, mut angle) = (target.rotation * current.rotation.inverse()).to_axis_angle();
+        if angle > std::f32::consts::PI {
+            angle -= std::f32::consts::TAU;
+        }
+
+        // the angle that is left is measured, the goal is to have none left
+        let angular_speed = -self.rotation.update(0.0, angle, delta_time);
+
+        return (linear_velocity, axis * angular_speed);
+    }
+}
+
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component)]
 pub struct SmoothDamp {
     pub smooth_time: f32,
+    pub max_speed: f32,
     pub velocity: Vec3,
 }
 
@@ -85,16 +239,27 @@ impl SmoothDamp {
     pub fn new(smooth_time: f32) -> Self {
         Self {
             smooth_time,
+            max_speed: f32::INFINITY,
             velocity: Vec3::ZERO,
         }
     }
 
+    pub fn with_max_speed(mut self, max_speed: f32) -> Self {
+        self.max_speed = max_speed;
+        return self;
+    }
+
+    pub fn reset(&mut self) {
+        self.velocity = Vec3::ZERO;
+    }
+
     pub fn calculate(&mut self, current: Vec3, target: Vec3, delta_time: f32) -> Vec3 {
         smooth_damp(
             current,
             target,
             &mut self.velocity,
             self.smooth_time,
+            self.max_speed,
             delta_time,
         )
     }
@@ -105,9 +270,12 @@ pub fn smooth_damp(
     target: Vec3,
     current_velocity: &mut Vec3,
     smooth_time: f32,
+    max_speed: f32,
     delta_time: f32,
 ) -> Vec3 {
-    let max_speed = f32::INFINITY;
+    if delta_time <= 0.0 {
+        return current;
+    }
 
     // Based on Game Programming Gems 4 Chapter 1.10
     let smooth_time = smooth_time.max(0.0001);
@@ -150,3 +318,210 @@ pub fn smooth_damp(
 
     return output;
 }
+
+fn reset_controllers(
+    mut q_pid: Query<&mut Pid>,
+    mut q_vec3_pid: Query<&mut Vec3Pid>,
+    mut q_transform_pid: Query<&mut TransformPid>,
+    mut q_smooth_damp: Query<&mut SmoothDamp>,
+) {
+    for mut pid in q_pid.iter_mut() {
+        pid.reset();
+    }
+
+    for mut pid in q_vec3_pid.iter_mut() {
+        pid.reset();
+    }
+
+    for mut pid in q_transform_pid.iter_mut() {
+        pid.reset();
+    }
+
+    for mut smooth_damp in q_smooth_damp.iter_mut() {
+        smooth_damp.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+
+    const CASES: usize = 200;
+    const STEPS: usize = 100;
+
+    fn random_config(rng: &mut StdRng) -> PidConfig {
+        return PidConfig::new(
+            rng.gen_range(0.0..10.0),
+            rng.gen_range(0.0..5.0),
+            rng.gen_range(0.0..1.0),
+        )
+        .with_integral_limit(rng.gen_range(0.1..10.0))
+        .with_output_limit(rng.gen_range(0.1..20.0));
+    }
+
+    fn random_vec3(rng: &mut StdRng, range: f32) -> Vec3 {
+        return Vec3::new(
+            rng.gen_range(-range..range),
+            rng.gen_range(-range..range),
+            rng.gen_range(-range..range),
+        );
+    }
+
+    /// Random setpoints, measurements and frame times.
+    fn random_steps(rng: &mut StdRng) -> Vec<(f32, f32, f32)> {
+        return (0..STEPS)
+            .map(|_| {
+                (
+                    rng.gen_range(-100.0..100.0),
+                    rng.gen_range(-100.0..100.0),
+                    rng.gen_range(0.001..0.1),
+                )
+            })
+            .collect();
+    }
+
+    #[test]
+    fn output_stays_within_limit() {
+        let mut rng = StdRng::seed_from_u64(1);
+
+        for _ in 0..CASES {
+            let config = random_config(&mut rng);
+            let mut pid = Pid::new(config);
+            let mut vec3_pid = Vec3Pid::new(config);
+
+            for (setpoint, measurement, delta_time) in random_steps(&mut rng) {
+                let output = pid.update(setpoint, measurement, delta_time);
+                assert!(output.abs() <= config.output_limit);
+
+                let output = vec3_pid.update(
+                    random_vec3(&mut rng, 100.0),
+                    random_vec3(&mut rng, 100.0),
+                    delta_time,
+                );
+                assert!(output.length() <= config.output_limit + 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn integral_term_stays_within_limit() {
+        let mut rng = StdRng::seed_from_u64(2);
+
+        for _ in 0..CASES {
+            let config = random_config(&mut rng);
+            let mut pid = Pid::new(config);
+            let mut vec3_pid = Vec3Pid::new(config);
+
+            for (setpoint, measurement, delta_time) in random_steps(&mut rng) {
+                pid.update(setpoint, measurement, delta_time);
+                assert!((config.ki * pid.integral).abs() <= config.integral_limit + 1e-4);
+
+                vec3_pid.update(
+                    random_vec3(&mut rng, 100.0),
+                    random_vec3(&mut rng, 100.0),
+                    delta_time,
+                );
+                assert!(config.ki * vec3_pid.integral.length() <= config.integral_limit + 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn integral_doesnt_wind_up_while_saturated() {
+        let config = PidConfig::new(1.0, 1.0, 0.0).with_output_limit(1.0);
+        let mut pid = Pid::new(config);
+
+        // the target is out of reach for a long time
+        for _ in 0..1000 {
+            assert_eq!(pid.update(100.0, 0.0, 0.1), 1.0);
+        }
+
+        // so the controller lets go as soon as the target is passed
+        assert!(pid.update(0.0, 0.5, 0.1) < 0.0);
+    }
+
+    #[test]
+    fn setpoint_jumps_dont_kick_the_derivative() {
+        let mut rng = StdRng::seed_from_u64(3);
+
+        for _ in 0..CASES {
+            // only the derivative term
+            let kd = rng.gen_range(0.1..10.0);
+            let mut pid = Pid::new(PidConfig::new(0.0, 0.0, kd));
+            let mut previous_measurement = None;
+
+            for (setpoint, _, delta_time) in random_steps(&mut rng) {
+                // the measurement moves slowly while the setpoint jumps around
+                let measurement = rng.gen_range(-1.0..1.0);
+                let output = pid.update(setpoint, measurement, delta_time);
+
+                let expected = match previous_measurement {
+                    Some(previous) => -kd * (measurement - previous) / delta_time,
+                    None => 0.0,
+                };
+                assert!((output - expected).abs() <= 1e-3 * expected.abs().max(1.0));
+
+                previous_measurement = Some(measurement);
+            }
+        }
+    }
+
+    #[test]
+    fn reset_is_like_a_new_controller() {
+        let mut rng = StdRng::seed_from_u64(4);
+
+        for _ in 0..CASES {
+            let config = random_config(&mut rng);
+            let mut pid = Pid::new(config);
+            let mut vec3_pid = Vec3Pid::new(config);
+
+            for (setpoint, measurement, delta_time) in random_steps(&mut rng) {
+                pid.update(setpoint, measurement, delta_time);
+                vec3_pid.update(Vec3::splat(setpoint), Vec3::splat(measurement), delta_time);
+            }
+
+            pid.reset();
+            vec3_pid.reset();
+
+            let mut fresh = Pid::new(config);
+            let mut fresh_vec3 = Vec3Pid::new(config);
+
+            for (setpoint, measurement, delta_time) in random_steps(&mut rng) {
+                assert_eq!(
+                    pid.update(setpoint, measurement, delta_time),
+                    fresh.update(setpoint, measurement, delta_time)
+                );
+
+                let (setpoint, measurement) = (Vec3::splat(setpoint), Vec3::splat(measurement));
+                assert_eq!(
+                    vec3_pid.update(setpoint, measurement, delta_time),
+                    fresh_vec3.update(setpoint, measurement, delta_time)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn smooth_damp_never_overshoots() {
+        let mut rng = StdRng::seed_from_u64(5);
+
+        for _ in 0..CASES {
+            let target = random_vec3(&mut rng, 100.0);
+            let mut current = random_vec3(&mut rng, 100.0);
+            let mut smooth_damp = SmoothDamp::new(rng.gen_range(0.01..2.0))
+                .with_max_speed(rng.gen_range(1.0..1000.0));
+
+            for _ in 0..STEPS {
+                let delta_time = rng.gen_range(0.001..0.2);
+                let next = smooth_damp.calculate(current, target, delta_time);
+
+                // the new position is never past the target, seen from the old one
+                assert!((target - current).dot(next - target) <= 1e-3);
+
+                current = next;
+            }
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use avian3d::collision::CollidingEntities;
+use bevy::prelude::*;
+
+use crate::{
+    car_controller::{CarAction, CarController},
+    world_spawning::RegisterSpawnKeyword,
+    GameState,
+};
+
+pub fn plugin(app: &mut App) {
+    app.register_spawn_keyword("GasStation", |commands, _| {
+        commands.insert(GasStation);
+    })
+    .add_systems(Update, refuel.run_if(in_state(GameState::Playing)));
+}
+
+#[derive(Component)]
+pub struct GasStation;
+
+fn refuel(
+    q_gas_station: Query<&CollidingEntities, (With<GasStation>, Changed<CollidingEntities>)>,
+    q_child: Query<Option<&Parent>>,
+    q_car_controller: Query<(), With<CarController>>,
+    mut car_action_ew: EventWriter<CarAction>,
+) {
+    for colliding_entities in q_gas_station.iter() {
+        for colliding_entity in colliding_entities.iter() {
+            let Ok(Some(parent)) = q_child.get(*colliding_entity) else {
+                continue;
+            };
+
+            if q_car_controller.contains(parent.get()) {
+                car_action_ew.send(CarAction::Refuel);
+                return;
+            }
+        }
+    }
+}
@@ -3,15 +3,27 @@ use bevy::prelude::*;
 
 use crate::{
     car_controller::{components::Sticky, CarAction, CarController},
-    points::PointsAction,
-    world_spawning::on_spawn::Illegal,
+    inventory::{Inventory, Item},
+    lockpicking::Locked,
+    player_controller::Player,
+    points::{Points, PointsAction},
+    world_spawning::{
+        on_spawn::{Bicycle, Illegal},
+        RegisterSpawnKeyword,
+    },
     GameState,
 };
 
 pub fn plugin(app: &mut App) {
-    app.add_event::<DropOffBicyclesEvent>().add_systems(
+    app.register_spawn_keyword("Home", |commands, _| {
+        commands.insert(Home);
+    })
+    .add_event::<DropOffBicyclesEvent>()
+    .init_resource::<ToolShop>()
+    .add_systems(OnEnter(GameState::Playing), spawn_shop_ui)
+    .add_systems(
         Update,
-        (on_enter, drop_off_bicycles)
+        (on_enter, drop_off_bicycles, (buy_tools, update_shop_ui))
             .chain()
             .run_if(in_state(GameState::Playing)),
     );
@@ -20,74 +32,187 @@ pub fn plugin(app: &mut App) {
 #[derive(Component)]
 pub struct Home;
 
+/// Tools the player can buy with points while standing at home.
+#[derive(Resource)]
+pub struct ToolShop {
+    pub lockpick_price: u32,
+}
+
+impl Default for ToolShop {
+    fn default() -> Self {
+        Self { lockpick_price: 1 }
+    }
+}
+
+#[derive(Component)]
+struct ShopUi;
+
+fn spawn_shop_ui(shop: Res<ToolShop>, mut commands: Commands) {
+    commands
+        .spawn((
+            ShopUi,
+            NodeBundle {
+                style: Style {
+                    display: Display::Block,
+                    margin: UiRect {
+                        left: Val::Auto,
+                        right: Val::Auto,
+                        top: Val::Auto,
+                        bottom: Val::Px(70.0),
+                    },
+                    padding: UiRect::all(Val::Px(15.0)),
+                    ..Default::default()
+                },
+                visibility: Visibility::Hidden,
+                ..Default::default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                format!("[B] Buy a lockpick ({} points)", shop.lockpick_price),
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                    ..Default::default()
+                },
+            ));
+        });
+}
+
+fn player_at_home(q_home: &Query<&CollidingEntities, With<Home>>, player_entity: Entity) -> bool {
+    return q_home
+        .iter()
+        .any(|colliding_entities| colliding_entities.contains(&player_entity));
+}
+
+fn buy_tools(
+    keys: Res<ButtonInput<KeyCode>>,
+    shop: Res<ToolShop>,
+    q_home: Query<&CollidingEntities, With<Home>>,
+    mut q_player: Query<(Entity, &mut Inventory), With<Player>>,
+    q_points: Query<&Points>,
+    mut points_action_ew: EventWriter<PointsAction>,
+) {
+    if !keys.just_pressed(KeyCode::KeyB) {
+        return;
+    }
+
+    let Ok((player_entity, mut inventory)) = q_player.get_single_mut() else {
+        return;
+    };
+
+    if !player_at_home(&q_home, player_entity) {
+        return;
+    }
+
+    let Ok(points) = q_points.get_single() else {
+        return;
+    };
+
+    // nothing is bought when the inventory is full
+    if points.get() >= shop.lockpick_price && inventory.add(Item::Lockpick, 1) == 0 {
+        points_action_ew.send(PointsAction::Decrement(shop.lockpick_price));
+    }
+}
+
+fn update_shop_ui(
+    q_home: Query<&CollidingEntities, With<Home>>,
+    q_player: Query<Entity, With<Player>>,
+    mut q_shop_ui: Query<&mut Visibility, With<ShopUi>>,
+) {
+    let Ok(player_entity) = q_player.get_single() else {
+        return;
+    };
+
+    let at_home = player_at_home(&q_home, player_entity);
+
+    for mut visibility in q_shop_ui.iter_mut() {
+        *visibility = if at_home {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
 fn on_enter(
     q_home: Query<&CollidingEntities, (With<Home>, Changed<CollidingEntities>)>,
     q_child: Query<Option<&Parent>>,
     q_car_controller: Query<(Option<&CarController>, Option<&Children>)>,
-    q_sticky: Query<Option<&Sticky>>,
+    mut q_sticky: Query<(&mut Sticky, &mut CollidingEntities), Without<Home>>,
+    q_bicycle: Query<(), (With<Bicycle>, Without<Locked>)>,
     mut drop_off_bicycles_ew: EventWriter<DropOffBicyclesEvent>,
     mut car_action_ew: EventWriter<CarAction>,
 ) {
     for colliding_entities in q_home.iter() {
+        let mut bicycles = Vec::new();
+
         for colliding_entity in colliding_entities.iter() {
-            let parent = q_child.get(*colliding_entity).unwrap();
+            let Ok(Some(parent)) = q_child.get(*colliding_entity) else {
+                continue;
+            };
+
+            let parent_entity = parent.get();
+
+            // bicycles carried or thrown in on foot
+            if q_bicycle.contains(parent_entity) {
+                if !bicycles.contains(&parent_entity) {
+                    bicycles.push(parent_entity);
+                }
 
-            if let Some(parent) = parent {
-                let parent_entity = parent.get();
+                continue;
+            }
 
-                let (car_controller, children) = q_car_controller.get(parent_entity).unwrap();
-                if car_controller.is_some() {
-                    car_action_ew.send(CarAction::Refuel);
+            let Ok((car_controller, children)) = q_car_controller.get(parent_entity) else {
+                continue;
+            };
 
-                    let children = children.unwrap();
+            if car_controller.is_some() {
+                car_action_ew.send(CarAction::Refuel);
 
-                    let mut sticky_entities = Vec::new();
-                    for child_entity in children {
-                        if let Some(sticky) = q_sticky.get(*child_entity).unwrap() {
-                            if !sticky.entities.is_empty() {
-                                sticky_entities.push(*child_entity);
-                            }
-                        }
-                    }
+                let children = children.unwrap();
 
-                    if !sticky_entities.is_empty() {
-                        drop_off_bicycles_ew.send(DropOffBicyclesEvent(sticky_entities));
+                // bicycles on the bed of the car
+                for child_entity in children {
+                    if let Ok((mut sticky, mut sticky_colliding_entities)) =
+                        q_sticky.get_mut(*child_entity)
+                    {
+                        bicycles.append(&mut sticky.entities);
+                        sticky_colliding_entities.clear();
                     }
                 }
             }
         }
+
+        if !bicycles.is_empty() {
+            drop_off_bicycles_ew.send(DropOffBicyclesEvent(bicycles));
+        }
     }
 }
 
+/// Bicycles that are handed in at home, either attached to the car or on their own.
 #[derive(Event)]
 pub struct DropOffBicyclesEvent(Vec<Entity>);
 
 fn drop_off_bicycles(
     mut er: EventReader<DropOffBicyclesEvent>,
-    mut q_sticky: Query<(&mut Sticky, &mut CollidingEntities)>,
     q_bicycle: Query<Option<&Illegal>>,
     mut points_action_ew: EventWriter<PointsAction>,
     mut commands: Commands,
 ) {
     for ev in er.read() {
-        for sticky_entity in &ev.0 {
-            let (mut sticky, mut colliding_entities) = q_sticky.get_mut(*sticky_entity).unwrap();
-
-            for fake_bicycle_entity in &sticky.entities {
-                let illegal = q_bicycle.get(*fake_bicycle_entity).unwrap();
-                if illegal.is_some() {
-                    points_action_ew.send(PointsAction::Increment(1));
-                } else {
-                    points_action_ew.send(PointsAction::Decrement(2));
-                }
+        for bicycle_entity in &ev.0 {
+            let Ok(illegal) = q_bicycle.get(*bicycle_entity) else {
+                continue;
+            };
 
-                commands.entity(*fake_bicycle_entity).despawn_recursive();
+            if illegal.is_some() {
+                points_action_ew.send(PointsAction::Increment(1));
+            } else {
+                points_action_ew.send(PointsAction::Decrement(2));
             }
 
-            sticky.entities.clear();
-            colliding_entities.clear();
+            commands.entity(*bicycle_entity).despawn_recursive();
         }
-
-        return;
     }
 }
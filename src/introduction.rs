@@ -118,7 +118,12 @@ struct Template {
     content: Entity,
 }
 
-fn spawn_template(mut commands: Commands, mut next_state: ResMut<NextState<IntroductionState>>) {
+fn spawn_template(
+    introduction: Res<Introduction>,
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<IntroductionState>>,
+    mut rules_ui_ew: EventWriter<MenuAction<RulesUi>>,
+) {
     let root: Option<Entity>;
     let mut title: Option<Entity> = None;
     let mut content: Option<Entity> = None;
@@ -251,5 +256,10 @@ fn spawn_template(mut commands: Commands, mut next_state: ResMut<NextState<Intro
         content: content.unwrap(),
     });
 
-    next_state.set(IntroductionState::TemplateReady);
+    // the introduction is only shown before the first shift, later ones start with the rules
+    if introduction.current < introduction.pages.len() {
+        next_state.set(IntroductionState::TemplateReady);
+    } else {
+        rules_ui_ew.send(MenuAction::Show);
+    }
 }
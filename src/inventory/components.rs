@@ -0,0 +1,203 @@
+use bevy::prelude::*;
+
+/// Small items that are kept in the [`Inventory`] instead of being held in the hand.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Item {
+    Lockpick,
+    BoltCutters,
+    FuelCan,
+    PermitSticker,
+    EvidencePhoto,
+}
+
+impl Item {
+    pub fn name(&self) -> &'static str {
+        return match self {
+            Item::Lockpick => "lockpicks",
+            Item::BoltCutters => "bolt cutters",
+            Item::FuelCan => "fuel cans",
+            Item::PermitSticker => "permit stickers",
+            Item::EvidencePhoto => "evidence photos",
+        };
+    }
+
+    /// Short name shown on the hotbar.
+    pub fn label(&self) -> &'static str {
+        return match self {
+            Item::Lockpick => "Pick",
+            Item::BoltCutters => "Cutters",
+            Item::FuelCan => "Fuel",
+            Item::PermitSticker => "Permit",
+            Item::EvidencePhoto => "Photo",
+        };
+    }
+
+    /// How many of the item fit into a single slot.
+    pub fn max_stack(&self) -> u32 {
+        return match self {
+            Item::Lockpick => 10,
+            Item::BoltCutters => 1,
+            Item::FuelCan => 1,
+            Item::PermitSticker => 5,
+            Item::EvidencePhoto => 10,
+        };
+    }
+
+    pub fn color(&self) -> Color {
+        return match self {
+            Item::Lockpick => Color::srgb(0.7, 0.7, 0.75),
+            Item::BoltCutters => Color::srgb(0.9, 0.3, 0.2),
+            Item::FuelCan => Color::srgb(0.9, 0.7, 0.1),
+            Item::PermitSticker => Color::srgb(0.2, 0.6, 0.9),
+            Item::EvidencePhoto => Color::srgb(0.9, 0.9, 0.9),
+        };
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ItemStack {
+    pub item: Item,
+    pub amount: u32,
+}
+
+impl ItemStack {
+    pub fn new(item: Item, amount: u32) -> Self {
+        Self { item, amount }
+    }
+}
+
+/// Slots of small items the player carries around, one of them is selected on the hotbar.
+#[derive(Component)]
+pub struct Inventory {
+    slots: Vec<Option<ItemStack>>,
+    selected: usize,
+}
+
+impl Inventory {
+    pub fn new(size: usize) -> Self {
+        Self {
+            slots: vec![None; size],
+            selected: 0,
+        }
+    }
+
+    /// An inventory starting with the items, items that don't fit are lost.
+    pub fn with_items(size: usize, items: &[(Item, u32)]) -> Self {
+        let mut inventory = Self::new(size);
+
+        for (item, amount) in items {
+            inventory.add(*item, *amount);
+        }
+
+        return inventory;
+    }
+
+    pub fn slots(&self) -> &[Option<ItemStack>] {
+        return &self.slots;
+    }
+
+    pub fn selected(&self) -> usize {
+        return self.selected;
+    }
+
+    pub fn selected_stack(&self) -> Option<ItemStack> {
+        return self.slots.get(self.selected).copied().flatten();
+    }
+
+    pub fn select(&mut self, index: usize) {
+        if index < self.slots.len() {
+            self.selected = index;
+        }
+    }
+
+    /// Moves the selection by `delta` slots, wrapping around at the ends.
+    pub fn scroll(&mut self, delta: i32) {
+        let size = self.slots.len() as i32;
+
+        if size == 0 {
+            return;
+        }
+
+        self.selected = (self.selected as i32 + delta).rem_euclid(size) as usize;
+    }
+
+    pub fn count(&self, item: Item) -> u32 {
+        return self
+            .slots
+            .iter()
+            .flatten()
+            .filter(|stack| stack.item == item)
+            .map(|stack| stack.amount)
+            .sum();
+    }
+
+    pub fn has(&self, item: Item) -> bool {
+        return self.count(item) > 0;
+    }
+
+    /// Adds the items to existing stacks first and then to empty slots, returns how many didn't
+    /// fit.
+    pub(crate) fn add(&mut self, item: Item, mut amount: u32) -> u32 {
+        for stack in self.slots.iter_mut().flatten() {
+            if stack.item == item {
+                let added = amount.min(item.max_stack().saturating_sub(stack.amount));
+                stack.amount += added;
+                amount -= added;
+            }
+        }
+
+        for slot in self.slots.iter_mut().filter(|slot| slot.is_none()) {
+            if amount == 0 {
+                break;
+            }
+
+            let added = amount.min(item.max_stack());
+            *slot = Some(ItemStack::new(item, added));
+            amount -= added;
+        }
+
+        return amount;
+    }
+
+    /// Removes the items, returns false and leaves the inventory as it is if there aren't enough.
+    pub(crate) fn remove(&mut self, item: Item, mut amount: u32) -> bool {
+        if self.count(item) < amount {
+            return false;
+        }
+
+        for slot in self.slots.iter_mut() {
+            let Some(stack) = slot.as_mut().filter(|stack| stack.item == item) else {
+                continue;
+            };
+
+            let removed = amount.min(stack.amount);
+            stack.amount -= removed;
+            amount -= removed;
+
+            if stack.amount == 0 {
+                *slot = None;
+            }
+
+            if amount == 0 {
+                break;
+            }
+        }
+
+        return true;
+    }
+}
+
+#[derive(Component)]
+pub struct HotbarUIRoot;
+
+/// A slot of the hotbar, `index` is the index of the slot in the [`Inventory`].
+#[derive(Component)]
+pub struct HotbarSlot {
+    pub index: usize,
+}
+
+/// The name and amount of the items in a [`HotbarSlot`].
+#[derive(Component)]
+pub struct HotbarSlotText {
+    pub index: usize,
+}
@@ -0,0 +1,38 @@
+use bevy::prelude::*;
+
+use crate::{
+    player_controller::pick_up::UpPickable, world_spawning::RegisterSpawnKeyword, GameState,
+};
+
+use super::components::Item;
+use super::resources::*;
+use super::systems::*;
+
+pub struct InventoryPlugin;
+
+impl Plugin for InventoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_spawn_keyword("Lockpicks", |commands, _| {
+            commands.insert(UpPickable::item(Item::Lockpick, 3));
+        })
+        .register_spawn_keyword("BoltCutters", |commands, _| {
+            commands.insert(UpPickable::item(Item::BoltCutters, 1));
+        })
+        .register_spawn_keyword("FuelCan", |commands, _| {
+            commands.insert(UpPickable::item(Item::FuelCan, 1));
+        })
+        .register_spawn_keyword("PermitStickers", |commands, _| {
+            commands.insert(UpPickable::item(Item::PermitSticker, 2));
+        })
+        .register_spawn_keyword("EvidencePhoto", |commands, _| {
+            commands.insert(UpPickable::item(Item::EvidencePhoto, 1));
+        })
+        .init_resource::<HotbarSettings>()
+        .add_systems(OnEnter(GameState::Playing), setup_hotbar_ui)
+        .add_systems(Update, select_slot.run_if(in_state(GameState::Playing)))
+        .add_systems(
+            PostUpdate,
+            update_hotbar_ui.run_if(in_state(GameState::Playing)),
+        );
+    }
+}
@@ -0,0 +1,21 @@
+use bevy::prelude::*;
+
+/// Number of slots of the inventory of the player.
+pub const INVENTORY_SLOTS: usize = 6;
+
+#[derive(Resource)]
+pub struct HotbarSettings {
+    pub slot_size: f32,
+    pub selected_color: Color,
+    pub slot_color: Color,
+}
+
+impl Default for HotbarSettings {
+    fn default() -> Self {
+        Self {
+            slot_size: 60.0,
+            selected_color: Color::WHITE,
+            slot_color: Color::srgba(1.0, 1.0, 1.0, 0.2),
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use bevy::{input::mouse::MouseWheel, prelude::*};
+
+use crate::player_controller::Player;
+
+use super::components::*;
+use super::resources::*;
+
+pub fn setup_hotbar_ui(settings: Res<HotbarSettings>, mut commands: Commands) {
+    commands
+        .spawn((
+            HotbarUIRoot,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(15.0),
+                    bottom: Val::Px(15.0),
+                    column_gap: Val::Px(5.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ))
+        .with_children(|parent| {
+            for index in 0..INVENTORY_SLOTS {
+                parent
+                    .spawn((
+                        HotbarSlot { index },
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Px(settings.slot_size),
+                                height: Val::Px(settings.slot_size),
+                                border: UiRect::all(Val::Px(2.0)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..Default::default()
+                            },
+                            background_color: Color::srgba(0.0, 0.0, 0.0, 0.6).into(),
+                            border_color: settings.slot_color.into(),
+                            ..Default::default()
+                        },
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            HotbarSlotText { index },
+                            TextBundle::from_section(
+                                "",
+                                TextStyle {
+                                    font_size: 14.0,
+                                    color: Color::WHITE,
+                                    ..Default::default()
+                                },
+                            )
+                            .with_text_justify(JustifyText::Center),
+                        ));
+                    });
+            }
+        });
+}
+
+const SLOT_KEYS: [KeyCode; INVENTORY_SLOTS] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+];
+
+/// Selects a slot with the mouse wheel or the number keys.
+pub fn select_slot(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut mouse_wheel_er: EventReader<MouseWheel>,
+    mut q_inventory: Query<&mut Inventory, With<Player>>,
+) {
+    let Ok(mut inventory) = q_inventory.get_single_mut() else {
+        return;
+    };
+
+    let scroll: f32 = mouse_wheel_er.read().map(|ev| ev.y).sum();
+
+    if scroll != 0.0 {
+        // scrolling down moves to the right
+        inventory.scroll(-scroll.signum() as i32);
+    }
+
+    if let Some(index) = SLOT_KEYS.iter().position(|key| keys.just_pressed(*key)) {
+        inventory.select(index);
+    }
+}
+
+pub fn update_hotbar_ui(
+    q_inventory: Query<&Inventory, (With<Player>, Changed<Inventory>)>,
+    settings: Res<HotbarSettings>,
+    mut q_slot: Query<(&HotbarSlot, &mut BorderColor)>,
+    mut q_slot_text: Query<(&HotbarSlotText, &mut Text)>,
+) {
+    let Ok(inventory) = q_inventory.get_single() else {
+        return;
+    };
+
+    for (slot, mut border_color) in q_slot.iter_mut() {
+        *border_color = if slot.index == inventory.selected() {
+            settings.selected_color.into()
+        } else {
+            settings.slot_color.into()
+        };
+    }
+
+    for (slot_text, mut text) in q_slot_text.iter_mut() {
+        let stack = inventory.slots().get(slot_text.index).copied().flatten();
+
+        match stack {
+            Some(stack) => {
+                text.sections[0].value = format!("{}\n{}", stack.item.label(), stack.amount);
+                text.sections[0].style.color = stack.item.color();
+            }
+            None => {
+                text.sections[0].value = String::new();
+            }
+        }
+    }
+}
@@ -0,0 +1,16 @@
+use bevy::prelude::*;
+
+#[derive(Component)]
+pub struct LevelSelectUIRoot;
+
+/// An entry of the level select, `index` is the index of the level in the catalog.
+#[derive(Component)]
+pub struct LevelSelectEntry {
+    pub index: usize,
+}
+
+#[derive(Component)]
+pub struct LevelDescriptionText;
+
+#[derive(Component)]
+pub struct DifficultyText;
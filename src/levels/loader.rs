@@ -0,0 +1,60 @@
+use std::fmt;
+
+use bevy::asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext};
+
+use super::resources::LevelCatalog;
+
+/// Loads [`LevelCatalog`]s from `.levels.json` files.
+#[derive(Default)]
+pub struct LevelCatalogLoader;
+
+#[derive(Debug)]
+pub enum LevelCatalogLoaderError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for LevelCatalogLoaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            LevelCatalogLoaderError::Io(err) => write!(f, "could not read level catalog: {}", err),
+            LevelCatalogLoaderError::Json(err) => write!(f, "invalid level catalog: {}", err),
+        };
+    }
+}
+
+impl std::error::Error for LevelCatalogLoaderError {}
+
+impl From<std::io::Error> for LevelCatalogLoaderError {
+    fn from(err: std::io::Error) -> Self {
+        return LevelCatalogLoaderError::Io(err);
+    }
+}
+
+impl From<serde_json::Error> for LevelCatalogLoaderError {
+    fn from(err: serde_json::Error) -> Self {
+        return LevelCatalogLoaderError::Json(err);
+    }
+}
+
+impl AssetLoader for LevelCatalogLoader {
+    type Asset = LevelCatalog;
+    type Settings = ();
+    type Error = LevelCatalogLoaderError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<LevelCatalog, LevelCatalogLoaderError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        return Ok(serde_json::from_slice(&bytes)?);
+    }
+
+    fn extensions(&self) -> &[&str] {
+        return &["levels.json"];
+    }
+}
@@ -0,0 +1,11 @@
+mod components;
+mod loader;
+mod plugin;
+mod resources;
+mod systems;
+
+pub use components::*;
+pub use loader::*;
+pub use plugin::LevelsPlugin;
+pub use resources::*;
+pub use systems::reset_resource;
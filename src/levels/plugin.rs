@@ -0,0 +1,34 @@
+use bevy::prelude::*;
+
+use crate::GameState;
+
+use super::loader::LevelCatalogLoader;
+use super::resources::*;
+use super::systems::*;
+
+pub struct LevelsPlugin;
+
+impl Plugin for LevelsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<LevelCatalog>()
+            .init_asset_loader::<LevelCatalogLoader>()
+            .init_resource::<LevelSelection>()
+            .add_systems(
+                OnEnter(GameState::LevelSelect),
+                (despawn_level, setup_level_select_ui).chain(),
+            )
+            .add_systems(
+                Update,
+                (navigate_level_select, update_level_select_ui)
+                    .chain()
+                    .run_if(in_state(GameState::LevelSelect)),
+            )
+            .add_systems(OnExit(GameState::LevelSelect), despawn_level_select_ui)
+            .add_systems(OnEnter(GameState::LoadingLevel), start_loading_level)
+            .add_systems(
+                Update,
+                wait_for_level.run_if(in_state(GameState::LoadingLevel)),
+            )
+            .add_systems(OnEnter(GameState::Playing), mount_starting_vehicle);
+    }
+}
@@ -0,0 +1,119 @@
+use bevy::{prelude::*, utils::HashSet};
+use serde::Deserialize;
+
+use crate::time_of_day::{DayPhase, TimeOfDay};
+
+/// The levels that can be picked before a shift, loaded from a `.levels.json` file.
+#[derive(Asset, TypePath, Deserialize, Clone, Debug)]
+pub struct LevelCatalog {
+    pub levels: Vec<Level>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Level {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub map: LevelMap,
+    /// The rules of the rule book, they decide which bicycles are parked illegally.
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+    /// Length of the shift in seconds, shifts without a length only end when getting arrested.
+    #[serde(default)]
+    pub shift_length: Option<f32>,
+    #[serde(default)]
+    pub starting_vehicle: StartingVehicle,
+    /// The time of day the shift starts at, in hours since midnight.
+    #[serde(default = "default_start_hour")]
+    pub start_hour: f32,
+}
+
+fn default_start_hour() -> f32 {
+    return 12.0;
+}
+
+/// A rule of the rule book, written either as just its text or as an object with the part of the
+/// day it is in effect e.g. `{"text": "...", "during": "night"}`.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(from = "RuleEntry")]
+pub struct Rule {
+    pub text: String,
+    /// The rule is always in effect when this is `None`.
+    pub during: Option<DayPhase>,
+    /// Bicycles closer than this to a street lamp break the rule while it is in effect e.g.
+    /// `"near_lamps": 4.0`. Rules without it are only decided by the map.
+    pub near_lamps: Option<f32>,
+}
+
+impl Rule {
+    pub fn in_effect(&self, time_of_day: &TimeOfDay) -> bool {
+        return match self.during {
+            Some(phase) => time_of_day.phase() == phase,
+            None => true,
+        };
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RuleEntry {
+    Always(String),
+    Detailed {
+        text: String,
+        #[serde(default)]
+        during: Option<DayPhase>,
+        #[serde(default)]
+        near_lamps: Option<f32>,
+    },
+}
+
+impl From<RuleEntry> for Rule {
+    fn from(entry: RuleEntry) -> Self {
+        return match entry {
+            RuleEntry::Always(text) => Rule {
+                text,
+                during: None,
+                near_lamps: None,
+            },
+            RuleEntry::Detailed {
+                text,
+                during,
+                near_lamps,
+            } => Rule {
+                text,
+                during,
+                near_lamps,
+            },
+        };
+    }
+}
+
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum LevelMap {
+    /// Path of a hand built glTF world e.g. `town.glb`.
+    Gltf(String),
+    /// A city made by the generator.
+    Generated { seed: u64, blocks: [u32; 2] },
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum StartingVehicle {
+    #[default]
+    OnFoot,
+    Car,
+}
+
+/// The level that is being played, or was played last.
+#[derive(Resource, Clone, Debug)]
+pub struct CurrentLevel(pub Level);
+
+/// Index of the level highlighted in the level select.
+#[derive(Resource, Default)]
+pub struct LevelSelection(pub usize);
+
+/// Entities that existed before the first level was loaded. Everything else belongs to a level and
+/// is despawned when going back to the level select.
+#[derive(Resource)]
+pub(crate) struct PersistentEntities(pub(crate) HashSet<Entity>);
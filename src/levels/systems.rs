@@ -0,0 +1,385 @@
+use bevy::{
+    asset::LoadState,
+    prelude::*,
+    window::{CursorGrabMode, PrimaryWindow},
+};
+
+use crate::{
+    asset_loading::LevelAssets,
+    player_car_swap::RideAction,
+    player_controller::Player,
+    resources::Difficulty,
+    world_spawning::{on_spawn::Car, CityPieces, WorldSource},
+    GameState,
+};
+
+use super::components::*;
+use super::resources::*;
+
+/// Puts the default value of a resource back when a new level is loaded, for resources that hold
+/// the state of a level.
+pub fn reset_resource<R: Resource + Default>(mut commands: Commands) {
+    commands.insert_resource(R::default());
+}
+
+pub(super) fn despawn_level(
+    persistent_entities: Option<Res<PersistentEntities>>,
+    q_entities: Query<(Entity, Option<&Parent>)>,
+    mut commands: Commands,
+) {
+    let Some(persistent_entities) = persistent_entities else {
+        // no level was played yet
+        return;
+    };
+
+    for (entity, parent) in q_entities.iter() {
+        if persistent_entities.0.contains(&entity) {
+            continue;
+        }
+
+        // children are despawned with the first ancestor that belongs to the level
+        let is_level_root = match parent {
+            Some(parent) => persistent_entities.0.contains(&parent.get()),
+            None => true,
+        };
+
+        if is_level_root {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+pub(super) fn setup_level_select_ui(
+    level_assets: Res<LevelAssets>,
+    catalogs: Res<Assets<LevelCatalog>>,
+    mut q_windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut commands: Commands,
+) {
+    let mut window = q_windows.single_mut();
+    window.cursor.grab_mode = CursorGrabMode::None;
+    window.cursor.visible = true;
+
+    let Some(catalog) = catalogs.get(&level_assets.catalog) else {
+        error!("the level catalog is not loaded");
+        return;
+    };
+
+    // the level select is shown before the world and its camera are spawned
+    commands.spawn((
+        LevelSelectUIRoot,
+        Name::new("Level Select Camera"),
+        Camera2dBundle::default(),
+    ));
+
+    commands
+        .spawn((
+            LevelSelectUIRoot,
+            Name::new("Level Select"),
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    display: Display::Flex,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        display: Display::Flex,
+                        flex_direction: FlexDirection::Column,
+                        width: Val::Vh(80.0),
+                        padding: UiRect::all(Val::Px(15.0)),
+                        border: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    background_color: BackgroundColor(Color::srgb(0.8, 0.8, 0.8)),
+                    border_color: BorderColor(Color::BLACK),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(TextBundle {
+                        style: Style {
+                            align_self: AlignSelf::Center,
+                            ..default()
+                        },
+                        text: Text::from_section(
+                            "Choose a District",
+                            TextStyle {
+                                font_size: 60.0,
+                                color: Color::BLACK,
+                                ..default()
+                            },
+                        ),
+                        ..default()
+                    });
+
+                    parent.spawn(TextBundle {
+                        style: Style {
+                            align_self: AlignSelf::Center,
+                            margin: UiRect::vertical(Val::Px(5.0)),
+                            ..default()
+                        },
+                        text: Text::from_section(
+                            "(Use [W]/[S] to choose, [A]/[D] to change the difficulty and [Enter] to start your shift.)",
+                            TextStyle {
+                                font_size: 15.0,
+                                color: Color::hsl(0.0, 0.0449, 0.349),
+                                ..default()
+                            },
+                        ),
+                        ..default()
+                    });
+
+                    for (index, level) in catalog.levels.iter().enumerate() {
+                        parent.spawn((
+                            LevelSelectEntry { index },
+                            TextBundle {
+                                style: Style {
+                                    margin: UiRect::top(Val::Px(10.0)),
+                                    ..default()
+                                },
+                                text: Text::from_section(
+                                    level.name.clone(),
+                                    TextStyle {
+                                        font_size: 30.0,
+                                        color: Color::BLACK,
+                                        ..default()
+                                    },
+                                ),
+                                ..default()
+                            },
+                        ));
+                    }
+
+                    parent.spawn((
+                        LevelDescriptionText,
+                        TextBundle {
+                            style: Style {
+                                margin: UiRect::top(Val::Px(20.0)),
+                                ..default()
+                            },
+                            text: Text::from_section(
+                                "",
+                                TextStyle {
+                                    font_size: 20.0,
+                                    color: Color::BLACK,
+                                    ..default()
+                                },
+                            ),
+                            ..default()
+                        },
+                    ));
+
+                    parent.spawn((
+                        DifficultyText,
+                        TextBundle {
+                            style: Style {
+                                margin: UiRect::top(Val::Px(20.0)),
+                                ..default()
+                            },
+                            text: Text::from_section(
+                                "",
+                                TextStyle {
+                                    font_size: 25.0,
+                                    color: Color::BLACK,
+                                    ..default()
+                                },
+                            ),
+                            ..default()
+                        },
+                    ));
+                });
+        });
+}
+
+pub(super) fn despawn_level_select_ui(
+    q_ui_root: Query<Entity, With<LevelSelectUIRoot>>,
+    mut commands: Commands,
+) {
+    for entity in q_ui_root.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub(super) fn navigate_level_select(
+    keys: Res<ButtonInput<KeyCode>>,
+    level_assets: Res<LevelAssets>,
+    catalogs: Res<Assets<LevelCatalog>>,
+    mut selection: ResMut<LevelSelection>,
+    mut difficulty: ResMut<Difficulty>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut commands: Commands,
+) {
+    let Some(catalog) = catalogs.get(&level_assets.catalog) else {
+        return;
+    };
+
+    let level_count = catalog.levels.len();
+    if level_count == 0 {
+        return;
+    }
+
+    if keys.any_just_pressed([KeyCode::KeyW, KeyCode::ArrowUp]) {
+        selection.0 = (selection.0 + level_count - 1) % level_count;
+    }
+    if keys.any_just_pressed([KeyCode::KeyS, KeyCode::ArrowDown]) {
+        selection.0 = (selection.0 + 1) % level_count;
+    }
+
+    if keys.any_just_pressed([KeyCode::KeyA, KeyCode::ArrowLeft]) {
+        *difficulty = match *difficulty {
+            Difficulty::Easy | Difficulty::Normal => Difficulty::Easy,
+            Difficulty::Hard => Difficulty::Normal,
+        };
+    }
+    if keys.any_just_pressed([KeyCode::KeyD, KeyCode::ArrowRight]) {
+        *difficulty = match *difficulty {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal | Difficulty::Hard => Difficulty::Hard,
+        };
+    }
+
+    if keys.just_pressed(KeyCode::Enter) {
+        let level = catalog.levels[selection.0.min(level_count - 1)].clone();
+        commands.insert_resource(CurrentLevel(level));
+        next_state.set(GameState::LoadingLevel);
+    }
+}
+
+pub(super) fn update_level_select_ui(
+    level_assets: Res<LevelAssets>,
+    catalogs: Res<Assets<LevelCatalog>>,
+    selection: Res<LevelSelection>,
+    difficulty: Res<Difficulty>,
+    mut q_entry: Query<(&LevelSelectEntry, &mut Text)>,
+    mut q_description: Query<&mut Text, (With<LevelDescriptionText>, Without<LevelSelectEntry>)>,
+    mut q_difficulty: Query<
+        &mut Text,
+        (
+            With<DifficultyText>,
+            Without<LevelSelectEntry>,
+            Without<LevelDescriptionText>,
+        ),
+    >,
+) {
+    let Some(catalog) = catalogs.get(&level_assets.catalog) else {
+        return;
+    };
+
+    for (entry, mut text) in q_entry.iter_mut() {
+        let selected = entry.index == selection.0;
+        let name = &catalog.levels[entry.index].name;
+
+        text.sections[0].value = if selected {
+            format!("> {}", name)
+        } else {
+            name.clone()
+        };
+        text.sections[0].style.color = if selected {
+            Color::BLACK
+        } else {
+            Color::hsl(0.0, 0.0, 0.4)
+        };
+    }
+
+    if let (Ok(mut text), Some(level)) = (
+        q_description.get_single_mut(),
+        catalog.levels.get(selection.0),
+    ) {
+        let shift_length = match level.shift_length {
+            Some(seconds) => format!("Shift length: {} minutes", (seconds / 60.0).ceil()),
+            None => "Shift length: until you get arrested".to_string(),
+        };
+
+        text.sections[0].value = format!("{}\n\n{}", level.description, shift_length);
+    }
+
+    if let Ok(mut text) = q_difficulty.get_single_mut() {
+        text.sections[0].value = format!("Difficulty: < {:?} >", *difficulty);
+    }
+}
+
+pub(super) fn start_loading_level(
+    level: Res<CurrentLevel>,
+    persistent_entities: Option<Res<PersistentEntities>>,
+    q_entities: Query<Entity>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    if persistent_entities.is_none() {
+        commands.insert_resource(PersistentEntities(q_entities.iter().collect()));
+    }
+
+    // loading the pieces again is free once they are loaded
+    commands.insert_resource(CityPieces(asset_server.load(CityPieces::PATH)));
+
+    commands.insert_resource(match &level.0.map {
+        LevelMap::Gltf(path) => WorldSource::Gltf(asset_server.load(path.clone())),
+        LevelMap::Generated { seed, blocks } => WorldSource::Generated {
+            seed: *seed,
+            blocks: UVec2::from_array(*blocks),
+        },
+    });
+}
+
+pub(super) fn wait_for_level(
+    world_source: Res<WorldSource>,
+    city_pieces: Res<CityPieces>,
+    asset_server: Res<AssetServer>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let pieces_failed = matches!(
+        asset_server.get_load_state(&city_pieces.0),
+        Some(LoadState::Failed(_))
+    );
+    let pieces_done = pieces_failed || asset_server.is_loaded_with_dependencies(&city_pieces.0);
+
+    let handle = match world_source.as_ref() {
+        WorldSource::Gltf(handle) => handle,
+        // generated cities can't be made without their pieces
+        WorldSource::Generated { .. } => &city_pieces.0,
+    };
+
+    if let Some(LoadState::Failed(err)) = asset_server.get_load_state(handle) {
+        error!("failed to load the level: {}", err);
+        next_state.set(GameState::LevelSelect);
+    } else if pieces_done && asset_server.is_loaded_with_dependencies(handle) {
+        if pieces_failed {
+            // the level still works, only no bicycles are parked during the shift
+            warn!(
+                "Failed to load `{}`, no bicycles are parked during the shift.",
+                CityPieces::PATH
+            );
+        }
+
+        next_state.set(GameState::Spawning);
+    }
+}
+
+pub(super) fn mount_starting_vehicle(
+    level: Res<CurrentLevel>,
+    mut q_player: Query<&mut Transform, With<Player>>,
+    q_car: Query<(Entity, &Transform), (With<Car>, Without<Player>)>,
+    mut ride_action_ew: EventWriter<RideAction>,
+) {
+    if level.0.starting_vehicle != StartingVehicle::Car {
+        return;
+    }
+
+    let (Ok(mut player_transform), Ok((car_entity, car_transform))) =
+        (q_player.get_single_mut(), q_car.get_single())
+    else {
+        warn!("the level starts in the car, but there is no player or car");
+        return;
+    };
+
+    // mounting only works when the player is next to the car
+    player_transform.translation = car_transform.translation;
+    ride_action_ew.send(RideAction::Mount(car_entity));
+}
@@ -4,23 +4,42 @@ pub mod asset_loading;
 pub mod lockpicking;
 pub mod world_spawning;
 
+pub mod bike_owner;
+pub mod bike_spawner;
+pub mod camera_follow;
 pub mod car_controller;
 pub mod home;
+pub mod inventory;
+pub mod levels;
+pub mod minimap;
+pub mod navigation;
 pub mod player_car_swap;
 pub mod player_controller;
 pub mod points;
+pub mod police;
 pub mod simulation_state;
 
 pub mod cubemap_factory;
+pub mod damping;
+pub mod gas_station;
 pub mod introduction;
+pub mod npc;
 pub mod pause_menu;
+pub mod perception;
 pub mod resources;
 pub mod rules;
+pub mod shift;
+pub mod sky;
+pub mod time_of_day;
+pub mod weather;
 
 #[derive(States, Clone, PartialEq, Eq, Hash, Debug, Default)]
 pub enum GameState {
     #[default]
     Loading,
+    LevelSelect,
+    LoadingLevel,
     Spawning,
     Playing,
+    ShiftOver,
 }
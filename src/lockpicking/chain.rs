@@ -0,0 +1,173 @@
+use super::*;
+use crate::player_controller::Player;
+use bevy::{
+    color::palettes::css::{DARK_GRAY, ORANGE},
+    prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (
+            spawn_chain_ui,
+            apply_chain_actions,
+            cut_chains,
+            update_chain_ui,
+        )
+            .chain()
+            .after(start_minigames)
+            .after(read_lockpick_input),
+    );
+}
+
+/// State of a chain cutting minigame.
+#[derive(Component, Clone)]
+pub struct ChainCut {
+    /// How far the chain is cut, between 0 and 1.
+    pub progress: f32,
+    pub cut_time: f32,
+    pub cutting: bool,
+}
+
+impl ChainCut {
+    pub fn new(cut_time: f32) -> Self {
+        Self {
+            progress: 0.0,
+            cut_time,
+            cutting: false,
+        }
+    }
+
+    pub fn start(&mut self) {
+        self.cutting = true;
+    }
+
+    /// Stops cutting, returns whether the cutters slipped before the chain was cut.
+    pub fn release(&mut self) -> bool {
+        let slipped = self.cutting && self.progress > 0.0;
+
+        self.cutting = false;
+        self.progress = 0.0;
+
+        return slipped;
+    }
+
+    /// Cuts while the cutters are held, returns whether the chain was cut through.
+    pub fn advance(&mut self, delta_secs: f32) -> bool {
+        if !self.cutting {
+            return false;
+        }
+
+        self.progress += delta_secs / self.cut_time.max(f32::EPSILON);
+
+        if self.progress < 1.0 {
+            return false;
+        }
+
+        self.cutting = false;
+        self.progress = 0.0;
+        return true;
+    }
+}
+
+/// The bar showing the progress of the cut.
+#[derive(Component)]
+pub struct ChainCutProgress {
+    pub lock: Entity,
+}
+
+fn spawn_chain_ui(
+    q_chain_cut: Query<(Entity, &LockPickTarget), Added<ChainCut>>,
+    q_player: Query<(), With<Player>>,
+    mut commands: Commands,
+) {
+    for (lock, target) in q_chain_cut.iter() {
+        if !has_ui(target, &q_player) {
+            continue;
+        }
+
+        commands
+            .spawn((
+                LockPickMenu { lock },
+                Name::new("chain cutting minigame ui"),
+                NodeBundle {
+                    style: Style {
+                        width: Val::Percent(40.0),
+                        height: Val::Percent(5.0),
+                        justify_self: JustifySelf::Center,
+                        align_self: AlignSelf::Center,
+                        border: UiRect::all(Val::Px(5.0)),
+                        ..default()
+                    },
+                    background_color: BackgroundColor(Color::Srgba(DARK_GRAY)),
+                    border_color: BorderColor(Color::BLACK),
+                    ..default()
+                },
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    ChainCutProgress { lock },
+                    NodeBundle {
+                        style: Style {
+                            width: Val::Percent(0.0),
+                            height: Val::Percent(100.0),
+                            ..default()
+                        },
+                        background_color: BackgroundColor(Color::Srgba(ORANGE)),
+                        ..default()
+                    },
+                ));
+            });
+    }
+}
+
+/// Holding commit cuts, letting go before the chain is cut counts as a failure.
+fn apply_chain_actions(
+    mut input_er: EventReader<LockPickInput>,
+    q_picker: Query<&LockPicker>,
+    mut q_chain_cut: Query<(&mut ChainCut, &mut LockPickTarget)>,
+    mut commands: Commands,
+) {
+    for input in input_er.read() {
+        let Some(entity) = session_of(input.picker, &q_picker) else {
+            continue;
+        };
+
+        let Ok((mut chain_cut, mut target)) = q_chain_cut.get_mut(entity) else {
+            continue;
+        };
+
+        match input.action {
+            LockPickAction::Commit => chain_cut.start(),
+            LockPickAction::Release if chain_cut.release() => {
+                resolve_pick(false, entity, &mut target, &mut commands);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn cut_chains(
+    mut q_chain_cut: Query<(Entity, &mut ChainCut, &mut LockPickTarget)>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, mut chain_cut, mut target) in q_chain_cut.iter_mut() {
+        if chain_cut.advance(time.delta_seconds()) {
+            resolve_pick(true, entity, &mut target, &mut commands);
+        }
+    }
+}
+
+fn update_chain_ui(
+    q_chain_cut: Query<&ChainCut>,
+    mut q_progress: Query<(&ChainCutProgress, &mut Style)>,
+) {
+    for (progress, mut style) in q_progress.iter_mut() {
+        let Ok(chain_cut) = q_chain_cut.get(progress.lock) else {
+            continue;
+        };
+
+        style.width = Val::Percent(chain_cut.progress.min(1.0) * 100.0);
+    }
+}
@@ -2,6 +2,16 @@ use bevy::{
     ecs::component::{ComponentHooks, StorageType},
     prelude::*,
 };
+use rand::Rng;
+
+use crate::{
+    inventory::{Inventory, Item},
+    perception::Noise,
+    player_controller::{
+        lockpicking::{LockPickEvent, StopPickReason},
+        CharacterController,
+    },
+};
 
 /// Ui settings for sliding
 pub enum SlideSettings {
@@ -15,24 +25,278 @@ pub struct SlideLinear {
     pub time_to_target: f32,
 }
 
-/// Data of a slide of a widget.
-#[derive(Component)]
-pub struct SlideTarget {
-    pub speed: f32,
-    pub start_pos: f32,
-    pub target_pos: f32,
+/// State of the sliding zone minigame, positions are in percent of the bar.
+#[derive(Component, Clone)]
+pub struct SliderMinigame {
+    pub pick_position: f32,
+    /// Position of the left edge of the success zone.
+    pub zone_position: f32,
+    pub zone_width: f32,
+    pub move_on_good_pick: bool,
+    pub slide: Option<ZoneSlide>,
+}
+
+/// A slide of the success zone to a random position.
+#[derive(Clone)]
+pub struct ZoneSlide {
+    pub from: f32,
+    pub to: f32,
+    pub elapsed: f32,
     pub time_to_target: f32,
-    pub start_time_secs: f32,
 }
 
-#[derive(Component)]
-pub struct RandomizePos;
+impl SliderMinigame {
+    pub fn new(locked: &Locked, rng: &mut impl Rng) -> Self {
+        let zone_width = locked.success_zone_width.clamp(0.0, 100.0);
+        let zone_position = random_zone_position(zone_width, rng);
+
+        let slide = match &locked.zone_slide_settings {
+            SlideSettings::NoSlide => None,
+            SlideSettings::SlideLinear(settings) => Some(ZoneSlide {
+                from: zone_position,
+                to: random_zone_position(zone_width, rng),
+                elapsed: 0.0,
+                time_to_target: settings.time_to_target,
+            }),
+        };
+
+        Self {
+            pick_position: 0.0,
+            zone_position,
+            zone_width,
+            move_on_good_pick: locked.move_on_good_pick,
+            slide,
+        }
+    }
+
+    pub fn move_pick(&mut self, delta: f32) {
+        self.place_pick(self.pick_position + delta);
+    }
+
+    pub fn place_pick(&mut self, position: f32) {
+        self.pick_position = position.clamp(0.0, 100.0);
+    }
+
+    pub fn is_pick_in_zone(&self) -> bool {
+        return (self.zone_position..=self.zone_position + self.zone_width)
+            .contains(&self.pick_position);
+    }
+
+    /// Slides the success zone.
+    pub fn advance(&mut self, delta_secs: f32, rng: &mut impl Rng) {
+        let zone_width = self.zone_width;
+
+        let Some(slide) = &mut self.slide else {
+            return;
+        };
+
+        slide.elapsed += delta_secs;
+
+        let progress = (slide.elapsed / slide.time_to_target.max(f32::EPSILON)).min(1.0);
+        self.zone_position = slide.from.lerp(slide.to, progress);
+
+        if progress >= 1.0 {
+            slide.from = self.zone_position;
+            slide.to = random_zone_position(zone_width, rng);
+            slide.elapsed = 0.0;
+        }
+    }
+
+    /// Commits the pick at its current position, returns whether it was in the success zone.
+    pub fn commit(&mut self, rng: &mut impl Rng) -> bool {
+        let hit = self.is_pick_in_zone();
+
+        if hit && self.move_on_good_pick {
+            self.zone_position = random_zone_position(self.zone_width, rng);
+
+            if let Some(slide) = &mut self.slide {
+                slide.from = self.zone_position;
+                slide.elapsed = 0.0;
+            }
+        }
+
+        return hit;
+    }
+}
+
+fn random_zone_position(zone_width: f32, rng: &mut impl Rng) -> f32 {
+    return rng.gen_range(0.0..=(100.0 - zone_width));
+}
+
+/// The kind of lock, decides which minigame is played to open it.
+#[derive(Clone, Copy, PartialEq)]
+pub enum LockType {
+    /// Click the sliding success zone.
+    PinTumbler,
+    /// Same minigame as the pin tumbler but it takes more successful picks.
+    ULock,
+    /// Stop the dial on the numbers of the code one after the other.
+    CombinationDial { code_length: u32, turn_speed: f32 },
+    /// Can only be cut with bolt cutters, hold to cut through.
+    Chain { cut_time: f32 },
+}
+
+impl LockType {
+    pub fn name(&self) -> &'static str {
+        return match self {
+            LockType::PinTumbler => "Pin Tumbler Lock",
+            LockType::ULock => "U-Lock",
+            LockType::CombinationDial { .. } => "Combination Lock",
+            LockType::Chain { .. } => "Chain Lock",
+        };
+    }
+
+    /// Whether the lock is opened with the sliding zone minigame.
+    pub fn is_slider(&self) -> bool {
+        return matches!(self, LockType::PinTumbler | LockType::ULock);
+    }
+
+    /// The tool the picker needs to have to attempt the lock.
+    pub fn required_tool(&self) -> Option<Tool> {
+        return match self {
+            LockType::PinTumbler | LockType::ULock => Some(Tool::Lockpick),
+            LockType::Chain { .. } => Some(Tool::BoltCutters),
+            LockType::CombinationDial { .. } => None,
+        };
+    }
+
+    pub fn instructions(&self) -> &'static str {
+        return match self {
+            LockType::PinTumbler | LockType::ULock => {
+                "(Move the pick with the mouse or [A]/[D] and click or press [Space] on the green as many times as you can.)"
+            }
+            LockType::CombinationDial { .. } => {
+                "(Click or press [Space] when the dial shows the highlighted number to enter the combination.)"
+            }
+            LockType::Chain { .. } => {
+                "(Hold the mouse button or [Space] to cut the chain, letting go too early makes the cutters slip.)"
+            }
+        };
+    }
+}
+
+/// Tools needed for some locks, they are kept in the [`Inventory`] of the picker.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Tool {
+    Lockpick,
+    BoltCutters,
+}
+
+impl Tool {
+    pub fn item(&self) -> Item {
+        return match self {
+            Tool::Lockpick => Item::Lockpick,
+            Tool::BoltCutters => Item::BoltCutters,
+        };
+    }
+
+    /// Whether one of the tool is used up when a lockpicking attempt fails.
+    pub fn breaks(&self) -> bool {
+        return match self {
+            Tool::Lockpick => true,
+            Tool::BoltCutters => false,
+        };
+    }
+}
+
+/// How hard a lock is to open, between 0 and 1.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct LockDifficulty(f32);
+
+impl LockDifficulty {
+    pub fn new(rating: f32) -> Self {
+        Self(rating.clamp(0.0, 1.0))
+    }
+
+    /// Difficulty of a lock on a bicycle, `value` goes from 0 for the cheapest to 1 for the most
+    /// expensive bicycles and `global_factor` comes from the difficulty of the game.
+    pub fn rate(value: f32, global_factor: f32) -> Self {
+        return Self::new((0.2 + 0.6 * value) * global_factor);
+    }
+
+    pub fn get(&self) -> f32 {
+        return self.0;
+    }
+
+    pub fn label(&self) -> &'static str {
+        return match self.0 {
+            x if x < 0.25 => "Easy",
+            x if x < 0.5 => "Medium",
+            x if x < 0.75 => "Hard",
+            _ => "Very Hard",
+        };
+    }
+
+    pub fn color(&self) -> Color {
+        return Color::srgb(0.3, 1.0, 0.3).mix(&Color::srgb(1.0, 0.2, 0.2), self.0);
+    }
+}
 
 /// Marks object that can be lockpicked, `Locked` is removed on a successful lockpick.
+///
+/// The success zone settings are only used by locks with a slider minigame.
 pub struct Locked {
+    pub lock_type: LockType,
+    pub difficulty: LockDifficulty,
     pub success_zone_width: f32,
     pub move_on_good_pick: bool,
     pub zone_slide_settings: SlideSettings,
+    pub successful_picks_before_unlock: u32,
+    pub failed_picks_before_break: u32,
+    /// Seconds the picker has to open the lock, `None` if there is no limit.
+    pub time_budget: Option<f32>,
+}
+
+impl Locked {
+    /// Generates a random lock, harder locks have smaller and faster zones, longer codes and
+    /// need more successful picks.
+    pub fn random(difficulty: LockDifficulty, rng: &mut impl Rng) -> Self {
+        let d = difficulty.get();
+
+        let lock_type = match rng.gen_range(0..10) {
+            0..=4 => LockType::PinTumbler,
+            5..=6 => LockType::ULock,
+            7..=8 => LockType::CombinationDial {
+                code_length: 2 + (d * 3.0).round() as u32,
+                turn_speed: 3.0.lerp(6.0, d),
+            },
+            _ => LockType::Chain {
+                cut_time: 1.5.lerp(3.5, d),
+            },
+        };
+
+        let successful_picks_before_unlock = match lock_type {
+            LockType::PinTumbler => 2 + (d * 2.0).round() as u32,
+            LockType::ULock => 4 + (d * 3.0).round() as u32,
+            LockType::CombinationDial { code_length, .. } => code_length.saturating_sub(1),
+            LockType::Chain { .. } => 0,
+        };
+
+        let failed_picks_before_break = match lock_type {
+            LockType::PinTumbler | LockType::ULock => (d < 0.4) as u32 + 1,
+            LockType::CombinationDial { .. } | LockType::Chain { .. } => (d < 0.6) as u32 + 1,
+        };
+
+        // cutting a chain is already a race against the clock
+        let time_budget = match lock_type {
+            LockType::Chain { .. } => None,
+            _ => (d >= 0.35).then(|| 30.0.lerp(12.0, d)),
+        };
+
+        Self {
+            lock_type,
+            difficulty,
+            success_zone_width: 16.0.lerp(5.0, d),
+            move_on_good_pick: true,
+            zone_slide_settings: SlideSettings::SlideLinear(SlideLinear {
+                speed: 10.0,
+                time_to_target: 2.0.lerp(0.8, d),
+            }),
+            successful_picks_before_unlock,
+            failed_picks_before_break,
+            time_budget,
+        }
+    }
 }
 
 impl Component for Locked {
@@ -58,6 +322,76 @@ pub struct LockPickTarget {
     pub failed_picks_before_break: u32,
     pub successful_pick_counter: u32,
     pub failed_pick_counter: u32,
+    /// The tool used on the lock, one is used up if the attempt fails.
+    pub tool: Option<Tool>,
+    /// Seconds left to open the lock, `None` if there is no limit.
+    pub time_left: Option<f32>,
+    /// Set when something outside of the minigame ended the session.
+    pub interrupted: Option<StopPickReason>,
+}
+
+impl LockPickTarget {
+    pub fn new(picker: Entity, locked: &Locked) -> Self {
+        Self {
+            picker,
+            successful_picks_before_unlock: locked.successful_picks_before_unlock,
+            failed_picks_before_break: locked.failed_picks_before_break,
+            successful_pick_counter: 0,
+            failed_pick_counter: 0,
+            tool: locked.lock_type.required_tool(),
+            time_left: locked.time_budget,
+            interrupted: None,
+        }
+    }
+
+    /// Counts down the time budget, returns whether the time is up.
+    pub fn tick(&mut self, delta_secs: f32) -> bool {
+        let Some(time_left) = self.time_left.as_mut() else {
+            return false;
+        };
+
+        *time_left = (*time_left - delta_secs).max(0.0);
+        return *time_left == 0.0;
+    }
+
+    /// Whether there were enough successful picks to open the lock.
+    pub fn is_unlocked(&self) -> bool {
+        return self.successful_picks_before_unlock < self.successful_pick_counter;
+    }
+
+    /// Counts a successful pick, returns whether the lock opens.
+    pub fn succeed(&mut self) -> bool {
+        self.successful_pick_counter += 1;
+        return self.is_unlocked();
+    }
+
+    /// Counts a failed pick, returns whether the attempt is over.
+    pub fn fail(&mut self) -> bool {
+        self.failed_pick_counter += 1;
+        return self.is_broken();
+    }
+
+    /// Whether there were too many failed picks.
+    pub fn is_broken(&self) -> bool {
+        return self.failed_picks_before_break < self.failed_pick_counter;
+    }
+
+    /// Why the session ended, assuming it did.
+    pub fn stop_reason(&self) -> StopPickReason {
+        if let Some(reason) = self.interrupted {
+            return reason;
+        }
+
+        if self.is_unlocked() {
+            return StopPickReason::Unlocked;
+        }
+
+        if self.is_broken() {
+            return StopPickReason::Broken;
+        }
+
+        return StopPickReason::Cancelled;
+    }
 }
 
 impl Component for LockPickTarget {
@@ -69,10 +403,178 @@ impl Component for LockPickTarget {
         hooks.on_remove(|mut world, targeted_entity, _component_id| {
             // Grab the data that's about to be removed
             let targetable = world.get::<LockPickTarget>(targeted_entity).unwrap();
-            // Track down the entity that's targeting us
-            let mut targeting = world.get_mut::<LockPicker>(targetable.picker).unwrap();
-            // And clear its target, cleaning up any dangling references
-            targeting.target = None;
+            let picker = targetable.picker;
+            let reason = targetable.stop_reason();
+            // interruptions are already announced by whoever sent them
+            let announce = targetable.interrupted.is_none();
+            let broken_tool = targetable
+                .tool
+                .filter(|tool| targetable.is_broken() && tool.breaks());
+            // Track down the entity that's targeting us, it's gone when the level is torn down
+            if let Some(mut targeting) = world.get_mut::<LockPicker>(picker) {
+                // And clear its target, cleaning up any dangling references
+                targeting.target = None;
+            }
+
+            // a failed attempt costs the tool
+            if let Some(tool) = broken_tool {
+                if let Some(mut inventory) = world.get_mut::<Inventory>(picker) {
+                    inventory.remove(tool.item(), 1);
+                }
+            }
+
+            // let the picker move again
+            if let Some(mut character_controller) = world.get_mut::<CharacterController>(picker) {
+                character_controller.locked = false;
+            }
+            if let Some(mut picker_commands) = world.commands().get_entity(picker) {
+                picker_commands.remove::<Noise>();
+            }
+
+            if announce {
+                world.send_event(LockPickEvent::StopPick { picker, reason });
+            }
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    fn lock(zone_slide_settings: SlideSettings, move_on_good_pick: bool) -> Locked {
+        Locked {
+            lock_type: LockType::PinTumbler,
+            difficulty: LockDifficulty::new(0.5),
+            success_zone_width: 10.0,
+            move_on_good_pick,
+            zone_slide_settings,
+            successful_picks_before_unlock: 2,
+            failed_picks_before_break: 1,
+            time_budget: None,
+        }
+    }
+
+    fn zone_center(minigame: &SliderMinigame) -> f32 {
+        return minigame.zone_position + minigame.zone_width / 2.0;
+    }
+
+    #[test]
+    fn pick_stays_on_the_bar() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut minigame = SliderMinigame::new(&lock(SlideSettings::NoSlide, false), &mut rng);
+
+        minigame.move_pick(30.0);
+        minigame.move_pick(-10.0);
+        assert_eq!(minigame.pick_position, 20.0);
+
+        minigame.move_pick(-50.0);
+        assert_eq!(minigame.pick_position, 0.0);
+
+        minigame.place_pick(150.0);
+        assert_eq!(minigame.pick_position, 100.0);
+    }
+
+    #[test]
+    fn commits_count_towards_unlocking_and_breaking() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let locked = lock(SlideSettings::NoSlide, false);
+        let mut minigame = SliderMinigame::new(&locked, &mut rng);
+        let mut target = LockPickTarget::new(Entity::PLACEHOLDER, &locked);
+
+        let hit = zone_center(&minigame);
+        let miss = if hit > 50.0 { 0.0 } else { 100.0 };
+
+        minigame.place_pick(hit);
+        assert!(minigame.commit(&mut rng));
+        assert!(!target.succeed());
+
+        minigame.place_pick(miss);
+        assert!(!minigame.commit(&mut rng));
+        assert!(!target.fail());
+
+        minigame.place_pick(hit);
+        assert!(minigame.commit(&mut rng));
+        assert!(!target.succeed());
+
+        assert_eq!(target.successful_pick_counter, 2);
+        assert_eq!(target.failed_pick_counter, 1);
+
+        // one more of either ends the session
+        let mut broken = LockPickTarget { ..target };
+        assert!(broken.fail());
+        assert_eq!(broken.stop_reason(), StopPickReason::Broken);
+
+        assert!(target.succeed());
+        assert_eq!(target.stop_reason(), StopPickReason::Unlocked);
+    }
+
+    #[test]
+    fn good_picks_move_the_zone() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut minigame = SliderMinigame::new(&lock(SlideSettings::NoSlide, true), &mut rng);
+
+        for _ in 0..20 {
+            let zone_position = minigame.zone_position;
+
+            // a miss leaves the zone where it is
+            minigame.place_pick(if zone_center(&minigame) > 50.0 { 0.0 } else { 100.0 });
+            assert!(!minigame.commit(&mut rng));
+            assert_eq!(minigame.zone_position, zone_position);
+
+            minigame.place_pick(zone_center(&minigame));
+            assert!(minigame.commit(&mut rng));
+            assert_ne!(minigame.zone_position, zone_position);
+            assert!((0.0..=90.0).contains(&minigame.zone_position));
+        }
+    }
+
+    #[test]
+    fn zone_slides_to_its_target() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let settings = SlideSettings::SlideLinear(SlideLinear {
+            speed: 10.0,
+            time_to_target: 2.0,
+        });
+        let mut minigame = SliderMinigame::new(&lock(settings, false), &mut rng);
+
+        let ZoneSlide { from, to, .. } = minigame.slide.clone().unwrap();
+
+        minigame.advance(1.0, &mut rng);
+        assert!((minigame.zone_position - (from + to) / 2.0).abs() < 1e-4);
+
+        // at the target the zone starts sliding somewhere else
+        minigame.advance(1.0, &mut rng);
+        assert!((minigame.zone_position - to).abs() < 1e-4);
+
+        let slide = minigame.slide.as_ref().unwrap();
+        assert_eq!(slide.from, minigame.zone_position);
+        assert_eq!(slide.elapsed, 0.0);
+
+        for _ in 0..200 {
+            minigame.advance(0.1, &mut rng);
+            assert!((0.0..=90.0).contains(&minigame.zone_position));
+        }
+    }
+
+    #[test]
+    fn target_outlives_its_picker() {
+        let mut world = World::new();
+        world.init_resource::<Events<LockPickEvent>>();
+
+        let picker = world.spawn(LockPicker::default()).id();
+        let lock = lock(SlideSettings::NoSlide, false);
+        let target = world.spawn(LockPickTarget::new(picker, &lock)).id();
+        world.entity_mut(picker).get_mut::<LockPicker>().unwrap().target = Some(target);
+
+        // the level is torn down in any order
+        world.despawn(picker);
+        world.despawn(target);
+        world.flush();
+
+        assert!(world.get_entity(target).is_none());
+    }
+
+}
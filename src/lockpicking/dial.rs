@@ -0,0 +1,204 @@
+use super::*;
+use crate::player_controller::Player;
+use bevy::{color::palettes::css::DARK_GREEN, prelude::*};
+use rand::Rng;
+
+/// Number of digits on the dial.
+const DIAL_DIGITS: u32 = 10;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (
+            spawn_dial_ui,
+            turn_dials,
+            apply_dial_actions,
+            update_dial_ui,
+        )
+            .chain()
+            .after(start_minigames)
+            .after(read_lockpick_input),
+    );
+}
+
+/// State of a combination dial minigame.
+#[derive(Component, Clone)]
+pub struct Dial {
+    pub code: Vec<u32>,
+    /// How many digits of the code were entered.
+    pub entered: usize,
+    /// Angle of the dial in digits.
+    pub position: f32,
+    pub turn_speed: f32,
+}
+
+impl Dial {
+    pub fn new(code_length: u32, turn_speed: f32, rng: &mut impl Rng) -> Self {
+        Self {
+            code: (0..code_length)
+                .map(|_| rng.gen_range(0..DIAL_DIGITS))
+                .collect(),
+            entered: 0,
+            position: 0.0,
+            turn_speed,
+        }
+    }
+
+    pub fn current_digit(&self) -> u32 {
+        return self.position.floor() as u32 % DIAL_DIGITS;
+    }
+
+    pub fn advance(&mut self, delta_secs: f32) {
+        self.position = (self.position + self.turn_speed * delta_secs) % DIAL_DIGITS as f32;
+    }
+
+    /// Enters the current digit, returns whether it was the next digit of the code.
+    pub fn commit(&mut self) -> bool {
+        if self.code.get(self.entered) != Some(&self.current_digit()) {
+            return false;
+        }
+
+        self.entered += 1;
+        return true;
+    }
+}
+
+/// The digit the dial is currently on.
+#[derive(Component)]
+pub struct DialDigit {
+    pub lock: Entity,
+}
+
+/// A digit of the code, `index` is the position in the code.
+#[derive(Component)]
+pub struct DialCodeDigit {
+    pub lock: Entity,
+    pub index: usize,
+}
+
+fn spawn_dial_ui(
+    q_dial: Query<(Entity, &Dial, &LockPickTarget), Added<Dial>>,
+    q_player: Query<(), With<Player>>,
+    mut commands: Commands,
+) {
+    for (lock, dial, target) in q_dial.iter() {
+        if !has_ui(target, &q_player) {
+            continue;
+        }
+
+        commands
+            .spawn((
+                LockPickMenu { lock },
+                Name::new("combination dial minigame ui"),
+                NodeBundle {
+                    style: Style {
+                        padding: UiRect::all(Val::Px(15.0)),
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        justify_self: JustifySelf::Center,
+                        align_self: AlignSelf::Center,
+                        row_gap: Val::Px(10.0),
+                        ..default()
+                    },
+                    background_color: BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+                    border_radius: BorderRadius::all(Val::Px(10.0)),
+                    ..default()
+                },
+            ))
+            .with_children(|parent| {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            column_gap: Val::Px(10.0),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        for (index, digit) in dial.code.iter().enumerate() {
+                            parent.spawn((
+                                DialCodeDigit { lock, index },
+                                TextBundle::from_section(
+                                    digit.to_string(),
+                                    TextStyle {
+                                        font_size: 30.0,
+                                        color: Color::WHITE,
+                                        ..default()
+                                    },
+                                ),
+                            ));
+                        }
+                    });
+
+                parent.spawn((
+                    DialDigit { lock },
+                    TextBundle::from_section(
+                        "0",
+                        TextStyle {
+                            font_size: 60.0,
+                            color: Color::WHITE,
+                            ..default()
+                        },
+                    ),
+                ));
+            });
+    }
+}
+
+fn turn_dials(mut q_dial: Query<&mut Dial>, time: Res<Time>) {
+    for mut dial in q_dial.iter_mut() {
+        dial.advance(time.delta_seconds());
+    }
+}
+
+fn apply_dial_actions(
+    mut input_er: EventReader<LockPickInput>,
+    q_picker: Query<&LockPicker>,
+    mut q_dial: Query<(&mut Dial, &mut LockPickTarget)>,
+    mut commands: Commands,
+) {
+    for input in input_er.read() {
+        if input.action != LockPickAction::Commit {
+            continue;
+        }
+
+        let Some(entity) = session_of(input.picker, &q_picker) else {
+            continue;
+        };
+
+        let Ok((mut dial, mut target)) = q_dial.get_mut(entity) else {
+            continue;
+        };
+
+        let success = dial.commit();
+        resolve_pick(success, entity, &mut target, &mut commands);
+    }
+}
+
+fn update_dial_ui(
+    q_dial: Query<&Dial>,
+    mut q_digit: Query<(&DialDigit, &mut Text), Without<DialCodeDigit>>,
+    mut q_code_digit: Query<(&DialCodeDigit, &mut Text), Without<DialDigit>>,
+) {
+    for (digit, mut text) in q_digit.iter_mut() {
+        let Ok(dial) = q_dial.get(digit.lock) else {
+            continue;
+        };
+
+        text.sections[0].value = dial.current_digit().to_string();
+    }
+
+    for (code_digit, mut text) in q_code_digit.iter_mut() {
+        let Ok(dial) = q_dial.get(code_digit.lock) else {
+            continue;
+        };
+
+        text.sections[0].style.color = if code_digit.index < dial.entered {
+            Color::Srgba(DARK_GREEN)
+        } else if code_digit.index == dial.entered {
+            Color::srgb(1.0, 0.8, 0.1)
+        } else {
+            Color::WHITE
+        };
+    }
+}
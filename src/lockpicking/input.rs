@@ -0,0 +1,114 @@
+use bevy::{input::gamepad::GamepadAxisType, prelude::*, window::PrimaryWindow};
+
+use crate::player_controller::Player;
+
+use super::LockPicker;
+
+/// Width of the slider bar in percent of the window.
+pub const SLIDER_BAR_WIDTH: f32 = 80.0;
+
+/// Input for the lockpicking minigames, independent of the device it comes from.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LockPickAction {
+    /// Moves the pick by an amount in percent of the bar.
+    MovePick(f32),
+    /// Places the pick at a position in percent of the bar.
+    PlacePick(f32),
+    /// Pressing the commit button.
+    Commit,
+    /// Letting go of the commit button.
+    Release,
+}
+
+/// A [`LockPickAction`] of a [`LockPicker`], it only affects the session of that picker.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct LockPickInput {
+    pub picker: Entity,
+    pub action: LockPickAction,
+}
+
+#[derive(Resource)]
+pub struct LockPickInputSettings {
+    /// Speed of the pick when moved with the keyboard or a gamepad in percent of the bar per second.
+    pub pick_speed: f32,
+    pub gamepad_deadzone: f32,
+}
+
+impl Default for LockPickInputSettings {
+    fn default() -> Self {
+        Self {
+            pick_speed: 60.0,
+            gamepad_deadzone: 0.2,
+        }
+    }
+}
+
+/// Turns keyboard, mouse and gamepad input into [`LockPickAction`]s of the player.
+pub fn read_lockpick_input(
+    q_player: Query<Entity, (With<Player>, With<LockPicker>)>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut cursor_moved_er: EventReader<CursorMoved>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    settings: Res<LockPickInputSettings>,
+    time: Res<Time>,
+    mut input_ew: EventWriter<LockPickInput>,
+) {
+    let Ok(picker) = q_player.get_single() else {
+        return;
+    };
+
+    let mut send = |action| {
+        input_ew.send(LockPickInput { picker, action });
+    };
+
+    let mut direction = keys.any_pressed([KeyCode::KeyD, KeyCode::ArrowRight]) as i8 as f32
+        - keys.any_pressed([KeyCode::KeyA, KeyCode::ArrowLeft]) as i8 as f32;
+
+    let mut commit = keys.any_just_pressed([KeyCode::Space, KeyCode::Enter])
+        || mouse.just_pressed(MouseButton::Left);
+    let mut release = keys.any_just_released([KeyCode::Space, KeyCode::Enter])
+        || mouse.just_released(MouseButton::Left);
+
+    for gamepad in gamepads.iter() {
+        let stick = gamepad_axes
+            .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX))
+            .unwrap_or(0.0);
+
+        if stick.abs() > settings.gamepad_deadzone {
+            direction += stick;
+        }
+
+        let button = GamepadButton::new(gamepad, GamepadButtonType::South);
+        commit |= gamepad_buttons.just_pressed(button);
+        release |= gamepad_buttons.just_released(button);
+    }
+
+    if let Some(cursor_moved) = cursor_moved_er.read().last() {
+        if let Ok(window) = q_windows.get_single() {
+            let window_position = cursor_moved.position.x / window.width() * 100.0;
+            let bar_start = (100.0 - SLIDER_BAR_WIDTH) / 2.0;
+
+            send(LockPickAction::PlacePick(
+                (window_position - bar_start) / SLIDER_BAR_WIDTH * 100.0,
+            ));
+        }
+    }
+
+    if direction != 0.0 {
+        send(LockPickAction::MovePick(
+            direction.clamp(-1.0, 1.0) * settings.pick_speed * time.delta_seconds(),
+        ));
+    }
+
+    if commit {
+        send(LockPickAction::Commit);
+    }
+
+    if release {
+        send(LockPickAction::Release);
+    }
+}
@@ -5,3 +5,11 @@ pub struct LockPickingInstructionsUIRoot;
 
 #[derive(Component)]
 pub struct LockPickingInstruction;
+
+/// Shows the type and difficulty of the lock the player is looking at.
+#[derive(Component)]
+pub struct LockInfo;
+
+/// Shows the time left to open the lock that is being picked.
+#[derive(Component)]
+pub struct LockPickTimeLeft;
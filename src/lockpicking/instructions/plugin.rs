@@ -9,6 +9,9 @@ pub struct LockPickingUIPlugin;
 impl Plugin for LockPickingUIPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(OnEnter(GameState::Playing), setup_ui)
-            .add_systems(PostUpdate, update_ui.run_if(in_state(GameState::Playing)));
+            .add_systems(
+                PostUpdate,
+                (update_ui, update_lock_info, update_time_left).run_if(in_state(GameState::Playing)),
+            );
     }
 }
@@ -1,6 +1,14 @@
 use bevy::prelude::*;
 
-use crate::player_controller::lockpicking::LockPickEvent;
+use avian3d::spatial_query::{SpatialQuery, SpatialQueryFilter};
+
+use crate::{
+    lockpicking::{LockPickTarget, LockPicker, Locked},
+    player_controller::{
+        lockpicking::{LockPickEvent, MissingToolEvent},
+        Player,
+    },
+};
 
 use super::components::*;
 
@@ -39,19 +47,188 @@ pub fn setup_ui(mut commands: Commands) {
                     ..Default::default()
                 },
             ));
+
+            parent.spawn((
+                LockPickTimeLeft,
+                TextBundle {
+                    text: Text::from_section("", middle_of_screen_info_text_style()),
+                    visibility: Visibility::Hidden,
+                    ..Default::default()
+                },
+            ));
+        });
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                top: Val::Percent(55.0),
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                LockInfo,
+                TextBundle {
+                    text: Text::from_section(
+                        "",
+                        TextStyle {
+                            font_size: 20.0,
+                            ..Default::default()
+                        },
+                    ),
+                    visibility: Visibility::Hidden,
+                    ..Default::default()
+                },
+            ));
         });
 }
 
+/// How far away a lock can be to show its info, same as the interaction distance.
+const LOCK_INFO_DISTANCE: f32 = 2.0;
+
+/// Shows how hard the lock the player is looking at is before they start picking it.
+pub fn update_lock_info(
+    spatial_query: SpatialQuery,
+    q_camera: Query<&Transform, With<Camera>>,
+    q_player: Query<(Entity, &LockPicker), With<Player>>,
+    q_parent: Query<&Parent>,
+    q_locked: Query<&Locked>,
+    mut q_lock_info: Query<(&mut Text, &mut Visibility), With<LockInfo>>,
+) {
+    let Ok((mut text, mut visibility)) = q_lock_info.get_single_mut() else {
+        return;
+    };
+
+    *visibility = Visibility::Hidden;
+
+    let (Ok(transform), Ok((player_entity, picker))) = (q_camera.get_single(), q_player.get_single())
+    else {
+        return;
+    };
+
+    if picker.target.is_some() {
+        return;
+    }
+
+    let Some(hit) = spatial_query.cast_ray(
+        transform.translation,
+        transform.forward(),
+        LOCK_INFO_DISTANCE,
+        true,
+        SpatialQueryFilter::default().with_excluded_entities([player_entity]),
+    ) else {
+        return;
+    };
+
+    let Some(locked) = q_parent
+        .get(hit.entity)
+        .ok()
+        .and_then(|parent| q_locked.get(parent.get()).ok())
+    else {
+        return;
+    };
+
+    text.sections[0].value = format!(
+        "{} - {}",
+        locked.lock_type.name(),
+        locked.difficulty.label()
+    );
+    text.sections[0].style.color = locked.difficulty.color();
+    *visibility = Visibility::Visible;
+}
+
+/// How long the missing tool and interruption messages stay on screen.
+const MISSING_TOOL_MESSAGE_SECS: f32 = 2.0;
+
+/// Shows the instructions and messages of the session of the player.
 pub fn update_ui(
     mut event_reader: EventReader<LockPickEvent>,
-    mut q_lock_instruction_vis: Query<&mut Visibility, With<LockPickingInstruction>>,
+    mut missing_tool_er: EventReader<MissingToolEvent>,
+    q_player: Query<(), With<Player>>,
+    q_lock: Query<&Locked, With<LockPickTarget>>,
+    mut q_lock_instruction: Query<(&mut Text, &mut Visibility), With<LockPickingInstruction>>,
+    mut message_timer: Local<Option<Timer>>,
+    time: Res<Time>,
 ) {
     for event in event_reader.read() {
-        for mut vis in q_lock_instruction_vis.iter_mut() {
+        let (LockPickEvent::Pick { picker, .. } | LockPickEvent::StopPick { picker, .. }) = event;
+
+        if !q_player.contains(*picker) {
+            continue;
+        }
+
+        for (mut text, mut vis) in q_lock_instruction.iter_mut() {
             match event {
-                LockPickEvent::Pick(_) => { *vis = Visibility::Visible; },
-                LockPickEvent::StopPick => { *vis = Visibility::Hidden; },
+                LockPickEvent::Pick { lock, .. } => {
+                    // the pick didn't start
+                    let Ok(locked) = q_lock.get(*lock) else {
+                        continue;
+                    };
+
+                    text.sections[0].value = locked.lock_type.instructions().to_string();
+                    *vis = Visibility::Visible;
+                    *message_timer = None;
+                },
+                LockPickEvent::StopPick { reason, .. } => match reason.message() {
+                    Some(message) => {
+                        text.sections[0].value = message.to_string();
+                        *message_timer = Some(Timer::from_seconds(MISSING_TOOL_MESSAGE_SECS, TimerMode::Once));
+                    },
+                    None => { *vis = Visibility::Hidden; },
+                },
+            }
+        }
+    }
+
+    for MissingToolEvent { picker, tool } in missing_tool_er.read() {
+        if !q_player.contains(*picker) {
+            continue;
+        }
+
+        for (mut text, mut vis) in q_lock_instruction.iter_mut() {
+            text.sections[0].value = format!("(You have no {} to open this lock.)", tool.item().name());
+            *vis = Visibility::Visible;
+        }
+
+        *message_timer = Some(Timer::from_seconds(MISSING_TOOL_MESSAGE_SECS, TimerMode::Once));
+    }
+
+    if let Some(timer) = message_timer.as_mut() {
+        if timer.tick(time.delta()).just_finished() {
+            for (_, mut vis) in q_lock_instruction.iter_mut() {
+                *vis = Visibility::Hidden;
             }
+
+            *message_timer = None;
         }
     }
 }
+
+/// Shows how much time the player has left to open the lock, if it has a time budget.
+pub fn update_time_left(
+    q_player: Query<&LockPicker, With<Player>>,
+    q_target: Query<&LockPickTarget>,
+    mut q_time_left: Query<(&mut Text, &mut Visibility), With<LockPickTimeLeft>>,
+) {
+    let Ok((mut text, mut visibility)) = q_time_left.get_single_mut() else {
+        return;
+    };
+
+    let Some(time_left) = q_player
+        .get_single()
+        .ok()
+        .and_then(|picker| picker.target)
+        .and_then(|target| q_target.get(target).ok())
+        .and_then(|target| target.time_left)
+    else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+
+    text.sections[0].value = format!("Time left: {:.1}s", time_left);
+    *visibility = Visibility::Visible;
+}
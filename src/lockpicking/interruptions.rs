@@ -0,0 +1,120 @@
+use super::*;
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    perception::Perceived,
+    player_controller::lockpicking::{LockPickEvent, StopPickReason},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    // the interruptions are handled next frame, so npcs that noticed the player still see them
+    // lockpicking this frame
+    app.init_resource::<LockPickInterruptSettings>()
+        .add_systems(
+            PostUpdate,
+            (
+                tick_time_budgets,
+                interrupt_when_noticed,
+                interrupt_on_impact,
+            )
+                .run_if(any_with_component::<LockPickTarget>),
+        );
+}
+
+#[derive(Resource)]
+pub struct LockPickInterruptSettings {
+    /// Speed something has to bump into the bike with to knock it.
+    pub knock_speed: f32,
+    /// Speed something has to bump into the picker with to count as a hit.
+    pub hit_speed: f32,
+}
+
+impl Default for LockPickInterruptSettings {
+    fn default() -> Self {
+        Self {
+            knock_speed: 2.0,
+            hit_speed: 4.0,
+        }
+    }
+}
+
+/// Ends sessions that ran out of time.
+fn tick_time_budgets(
+    mut q_target: Query<&mut LockPickTarget>,
+    time: Res<Time>,
+    mut lock_pick_ew: EventWriter<LockPickEvent>,
+) {
+    for mut target in q_target.iter_mut() {
+        if target.tick(time.delta_seconds()) {
+            lock_pick_ew.send(LockPickEvent::StopPick {
+                picker: target.picker,
+                reason: StopPickReason::TimeUp,
+            });
+        }
+    }
+}
+
+fn interrupt_when_noticed(
+    q_target: Query<&LockPickTarget>,
+    q_perceived: Query<&Perceived>,
+    mut lock_pick_ew: EventWriter<LockPickEvent>,
+) {
+    for target in q_target.iter() {
+        if q_perceived
+            .iter()
+            .any(|perceived| perceived.noticed(target.picker))
+        {
+            lock_pick_ew.send(LockPickEvent::StopPick {
+                picker: target.picker,
+                reason: StopPickReason::Noticed,
+            });
+        }
+    }
+}
+
+/// Ends sessions when something bumps into the bike or the picker hard enough.
+fn interrupt_on_impact(
+    mut collision_started_er: EventReader<CollisionStarted>,
+    q_target: Query<(Entity, &LockPickTarget)>,
+    q_collider_parent: Query<&ColliderParent>,
+    q_velocity: Query<&LinearVelocity>,
+    settings: Res<LockPickInterruptSettings>,
+    mut lock_pick_ew: EventWriter<LockPickEvent>,
+) {
+    let body = |collider: Entity| {
+        q_collider_parent
+            .get(collider)
+            .map_or(collider, |parent| parent.get())
+    };
+
+    let speed = |entity: Entity| {
+        q_velocity
+            .get(entity)
+            .map_or(0.0, |velocity| velocity.length())
+    };
+
+    for CollisionStarted(a, b) in collision_started_er.read() {
+        let (a, b) = (body(*a), body(*b));
+
+        for (target_entity, target) in q_target.iter() {
+            for (this, other) in [(a, b), (b, a)] {
+                if other == target_entity || other == target.picker {
+                    continue;
+                }
+
+                if this == target_entity && speed(other) > settings.knock_speed {
+                    lock_pick_ew.send(LockPickEvent::StopPick {
+                        picker: target.picker,
+                        reason: StopPickReason::Knocked,
+                    });
+                } else if this == target.picker && speed(other) > settings.hit_speed {
+                    lock_pick_ew.send(LockPickEvent::StopPick {
+                        picker: target.picker,
+                        reason: StopPickReason::Hit,
+                    });
+                }
+            }
+        }
+    }
+}
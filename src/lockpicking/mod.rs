@@ -1,10 +1,19 @@
+mod chain;
 mod components;
+mod dial;
+mod input;
+mod interruptions;
 mod plugins;
 mod systems;
 mod ui;
 mod instructions;
 
+pub use chain::{ChainCut, ChainCutProgress};
 pub use components::*;
+pub use dial::{Dial, DialCodeDigit, DialDigit};
+pub use input::{LockPickAction, LockPickInput, LockPickInputSettings, SLIDER_BAR_WIDTH};
+pub use interruptions::LockPickInterruptSettings;
 pub use plugins::*;
+use input::read_lockpick_input;
 use systems::*;
 use ui::*;
@@ -1,6 +1,8 @@
 use bevy::prelude::*;
 use instructions::LockPickingUIPlugin;
 
+use crate::GameState;
+
 use super::*;
 
 pub struct LockPickingPlugin;
@@ -8,28 +10,29 @@ pub struct LockPickingPlugin;
 impl Plugin for LockPickingPlugin {
     fn build(&self, app: &mut App) {
         app
-        .add_plugins(LockPickingUIPlugin)
+        .add_event::<LockPickInput>()
+        .init_resource::<LockPickInputSettings>()
+        .add_plugins((LockPickingUIPlugin, dial::plugin, chain::plugin, interruptions::plugin))
         .add_systems(
             Update,
             (
-                randomize_lockpick_zone_position,
-                slide_sliding_pick_zones,
+                read_lockpick_input.run_if(any_with_component::<LockPickTarget>),
+                start_minigames,
+                slide_pick_zones,
+                apply_slider_actions,
                 spawn_lockpicking_minigame_ui,
                 despawn_lockpicking_minigame_ui,
-                adjust_lockpick_position,
-                check_fail_clicks,
-                check_success_clicks,
+                update_lockpicking_minigame_ui,
             )
                 .chain(),
         )
         .add_systems(
             Update,
             (
-                on_remove_lock.after(check_success_clicks),
-                on_remove_lockpick_target
-                    .after(check_success_clicks)
-                    .after(check_fail_clicks),
+                on_remove_lock.after(apply_slider_actions),
+                on_remove_lockpick_target.after(apply_slider_actions),
             ),
-        );
+        )
+        .add_systems(OnExit(GameState::Playing), stop_lockpicking);
     }
 }
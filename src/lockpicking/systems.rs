@@ -1,89 +1,132 @@
-use crate::player_controller::{lockpicking::LockPickEvent, pick_up::UpPickable, CharacterController};
+use crate::player_controller::pick_up::UpPickable;
 
 use super::*;
 
 use avian3d::dynamics::rigid_body::RigidBody;
 use bevy::prelude::*;
+use rand::thread_rng;
 
-pub fn check_fail_clicks(
-    mut interaction_query: Query<
-        &Interaction,
-        (Changed<Interaction>, With<Button>, With<PickFailZone>),
-    >,
-    mut lockpick_targets: Query<(Entity, &mut LockPickTarget)>,
+/// Sets up the minigame state for locks that started being picked.
+pub fn start_minigames(
+    lock_pick_targets: Query<(Entity, &Locked), Added<LockPickTarget>>,
     mut commands: Commands,
 ) {
-    for interaction in &mut interaction_query {
-        match *interaction {
-            Interaction::Pressed => {
-                for (e, mut target) in lockpick_targets.iter_mut() {
-                    // println!("failed pick");
-                    target.failed_pick_counter += 1;
-
-                    if target.failed_picks_before_break < target.failed_pick_counter {
-                        // println!("oops, lockpick broke..");
-                        commands.entity(e).remove::<LockPickTarget>();
-                    }
-                }
+    let mut rng = thread_rng();
+
+    for (entity, locked) in lock_pick_targets.iter() {
+        match locked.lock_type {
+            LockType::PinTumbler | LockType::ULock => {
+                commands
+                    .entity(entity)
+                    .insert(SliderMinigame::new(locked, &mut rng));
+            }
+            LockType::CombinationDial {
+                code_length,
+                turn_speed,
+            } => {
+                commands
+                    .entity(entity)
+                    .insert(Dial::new(code_length, turn_speed, &mut rng));
+            }
+            LockType::Chain { cut_time } => {
+                commands.entity(entity).insert(ChainCut::new(cut_time));
             }
-            Interaction::None => {}
-            Interaction::Hovered => {}
         }
     }
 }
 
-pub fn check_success_clicks(
-    mut interaction_query: Query<
-        (Entity, &Interaction),
-        (Changed<Interaction>, With<Button>, With<PickSuccessZone>),
-    >,
-    mut lockpick_targets: Query<(Entity, &Locked, &mut LockPickTarget)>,
+pub fn slide_pick_zones(mut q_slider: Query<&mut SliderMinigame>, time: Res<Time>) {
+    let mut rng = thread_rng();
+
+    for mut slider in q_slider.iter_mut() {
+        slider.advance(time.delta_seconds(), &mut rng);
+    }
+}
+
+pub fn apply_slider_actions(
+    mut input_er: EventReader<LockPickInput>,
+    q_picker: Query<&LockPicker>,
+    mut q_slider: Query<(&mut SliderMinigame, &mut LockPickTarget)>,
     mut commands: Commands,
 ) {
-    for (button_entity, interaction) in &mut interaction_query {
-        match *interaction {
-            Interaction::Pressed => {
-                for (target_entity, lock_setings, mut target) in lockpick_targets.iter_mut() {
-                    // println!("succeded pick");
-                    target.successful_pick_counter += 1;
-
-                    if target.successful_picks_before_unlock < target.successful_pick_counter {
-                        // println!("successful unlock!");
-                        commands
-                            .entity(target_entity)
-                            .remove::<Locked>()
-                            .remove::<LockPickTarget>();
-                    }
-
-                    if lock_setings.move_on_good_pick {
-                        commands.entity(button_entity).insert(RandomizePos);
-                    }
-                }
+    let mut rng = thread_rng();
+
+    for input in input_er.read() {
+        let Some(entity) = session_of(input.picker, &q_picker) else {
+            continue;
+        };
+
+        let Ok((mut slider, mut target)) = q_slider.get_mut(entity) else {
+            continue;
+        };
+
+        match input.action {
+            LockPickAction::MovePick(delta) => slider.move_pick(delta),
+            LockPickAction::PlacePick(position) => slider.place_pick(position),
+            LockPickAction::Commit => {
+                let success = slider.commit(&mut rng);
+                resolve_pick(success, entity, &mut target, &mut commands);
             }
-            Interaction::None => {}
-            Interaction::Hovered => {}
+            LockPickAction::Release => {}
         }
     }
 }
 
+/// The lock a picker is currently picking.
+pub(super) fn session_of(picker: Entity, q_picker: &Query<&LockPicker>) -> Option<Entity> {
+    return q_picker.get(picker).ok().and_then(|picker| picker.target);
+}
+
+/// Counts a pick on the lock, opening it or ending the attempt when there were enough.
+pub(super) fn resolve_pick(
+    success: bool,
+    entity: Entity,
+    target: &mut LockPickTarget,
+    commands: &mut Commands,
+) {
+    if success {
+        if target.succeed() {
+            commands
+                .entity(entity)
+                .remove::<Locked>()
+                .remove::<LockPickTarget>();
+        }
+    } else if target.fail() {
+        commands.entity(entity).remove::<LockPickTarget>();
+    }
+}
+
+/// Puts the lock back to normal after it stopped being picked, the picker is released by the
+/// hook of [`LockPickTarget`].
 pub fn on_remove_lockpick_target(
     mut removals: RemovedComponents<LockPickTarget>,
-    mut lockpick_event_writer: EventWriter<LockPickEvent>,
     mut q_object: Query<&mut RigidBody>,
-    mut q_player: Query<&mut CharacterController>,
+    mut commands: Commands,
 ) {
     for entity in removals.read() {
-        lockpick_event_writer.send(LockPickEvent::StopPick);
-        let mut character_controller = q_player.get_single_mut().unwrap();
-        character_controller.locked = false;
+        // the lock might have been despawned
+        let Some(mut entity_commands) = commands.get_entity(entity) else {
+            continue;
+        };
+
+        if let Ok(mut rigidbody) = q_object.get_mut(entity) {
+            *rigidbody = RigidBody::Dynamic;
+        }
+
+        entity_commands.remove::<(SliderMinigame, Dial, ChainCut)>();
+    }
+}
 
-        let mut rigidbody = q_object.get_mut(entity).unwrap();
-        *rigidbody = RigidBody::Dynamic;
+/// A shift can end in the middle of picking a lock, the session ends before the level is torn down
+/// so the picker is still there to be released.
+pub fn stop_lockpicking(q_target: Query<Entity, With<LockPickTarget>>, mut commands: Commands) {
+    for entity in q_target.iter() {
+        commands.entity(entity).remove::<LockPickTarget>();
     }
 }
 
 pub fn on_remove_lock(mut removals: RemovedComponents<Locked>, mut commands: Commands) {
     for entity in removals.read() {
-        commands.entity(entity).insert(UpPickable);
+        commands.entity(entity).insert(UpPickable::Hand);
     }
 }
@@ -1,129 +1,33 @@
 use super::*;
+use crate::player_controller::Player;
 use bevy::{
     color::palettes::css::{DARK_GREEN, DARK_RED, GRAY, LIGHT_BLUE},
     prelude::*,
     window::{CursorGrabMode, PrimaryWindow},
 };
-use rand::{thread_rng, Rng};
 
-/// All lock picking root ui elements have this component attached.
+/// All lock picking root ui elements have this component attached, every session has its own.
 #[derive(Component)]
-pub struct LockPickMenu;
-
-/// The widget following the mouse of the player.
-#[derive(Component)]
-pub struct LockPickWidget;
+pub struct LockPickMenu {
+    /// The lock that is being picked.
+    pub lock: Entity,
+}
 
-/// Marks a zone that if clicked counst as a failure.
+/// The widget showing the position of the pick.
 #[derive(Component)]
-pub struct PickFailZone;
+pub struct LockPickWidget {
+    pub lock: Entity,
+}
 
-/// Marks a zone that if clicked counst as a success.
+/// The zone showing where a pick counts as a success.
 #[derive(Component)]
-pub struct PickSuccessZone;
-
-/// Positions the lockpick widget at the cursor of the player.
-pub fn adjust_lockpick_position(
-    mut lockpicks: Query<(&LockPickWidget, &mut Style)>,
-    q_windows: Query<&Window, With<PrimaryWindow>>,
-) {
-    if let Some(position) = q_windows.single().cursor_position() {
-        for (_, mut style) in lockpicks.iter_mut() {
-            style.left = Val::Px(position.x)
-        }
-    }
+pub struct PickSuccessZone {
+    pub lock: Entity,
 }
 
-/// Slides the success zones.
-pub fn slide_sliding_pick_zones(
-    mut success_zones: Query<(Entity, &mut Style, &mut SlideTarget)>,
-    mut commands: Commands,
-    time: Res<Time>,
-) {
-    for (e, mut style, mut slide_target) in success_zones.iter_mut() {
-        let mut rng = thread_rng();
-
-        let width = match style.width {
-            Val::Percent(n) => n as i32,
-            _ => {
-                warn!("width only supported for percentage width. Exiting attempt.");
-                commands.entity(e).remove::<SlideTarget>();
-                return;
-            }
-        };
-
-        let current_pos = match style.left {
-            Val::Percent(n) => n as i32,
-            _ => {
-                warn!("length only supported for percentage width. Exiting attempt.");
-                commands.entity(e).remove::<SlideTarget>();
-                return;
-            }
-        } as f32;
-
-        let elapsed_time = time.elapsed_seconds() - slide_target.start_time_secs;
-        let time_ratio = elapsed_time / slide_target.time_to_target;
-
-        // lerp between 0 and target postion (f32) by the time ratio
-        let target_pos_progress = slide_target.target_pos * time_ratio;
-
-        if target_pos_progress >= slide_target.target_pos {
-            // randomize the position of the new target
-            let new_target_pos = rng.gen_range(0..(100 - width)) as f32;
-
-            // println!(
-            //     "target pos, {:#} reached, setting new target, {:#}",
-            //     slide_target.target_pos, new_target_pos
-            // );
-
-            let new_target = SlideTarget {
-                speed: slide_target.speed,
-                start_pos: current_pos,
-                target_pos: new_target_pos,
-                time_to_target: slide_target.time_to_target,
-                start_time_secs: time.elapsed_seconds(),
-            };
-
-            *slide_target = new_target;
-        } else {
-            let target_pos_offset =
-                (slide_target.start_pos - target_pos_progress).clamp(0.0, slide_target.target_pos);
-
-            // println!("target pos offset: {:#?}", target_pos_offset);
-            // println!("target pos: {:#}", slide_target.target_pos);
-            // println!("current pos: {:#}", current_pos);
-
-            // move target
-            style.left = Val::Percent(slide_target.target_pos - target_pos_offset)
-        }
-
-        //println!("target_pos progress {:#} vs actual target: {:#}", target_pos_progress, slide_target.target_pos);
-    }
-}
-
-/// Randomizes the position of the success zones.
-pub fn randomize_lockpick_zone_position(
-    mut success_zones: Query<(Entity, &mut Style), With<RandomizePos>>,
-    mut commands: Commands,
-) {
-    for (e, mut zone) in success_zones.iter_mut() {
-        let width = match zone.width {
-            Val::Percent(n) => n as i32,
-            _ => {
-                warn!("width only supported for percentage width. Exiting attempt.");
-                commands.entity(e).remove::<RandomizePos>();
-                return;
-            }
-        };
-
-        let mut rng = thread_rng();
-        let pos = rng.gen_range(0..(100 - width)) as f32;
-
-        // println!("randomizing pos to {:#}", pos);
-
-        zone.left = Val::Percent(pos);
-        commands.entity(e).remove::<RandomizePos>();
-    }
+/// Whether the session on the lock should get a ui, only players need one.
+pub(super) fn has_ui(target: &LockPickTarget, q_player: &Query<(), With<Player>>) -> bool {
+    return q_player.contains(target.picker);
 }
 
 pub fn despawn_lockpicking_minigame_ui(
@@ -132,49 +36,48 @@ pub fn despawn_lockpicking_minigame_ui(
     mut commands: Commands,
     mut q_windows: Query<&mut Window, With<PrimaryWindow>>,
 ) {
-    for _ in removals.read() {
-        let mut primary_window = q_windows.single_mut();
+    for lock in removals.read() {
+        let mut despawned = false;
 
-        for (e, _) in menus.iter() {
-            commands.entity(e).despawn_recursive();
+        for (e, menu) in menus.iter() {
+            if menu.lock == lock {
+                commands.entity(e).despawn_recursive();
+                despawned = true;
+            }
         }
 
-        primary_window.cursor.grab_mode = CursorGrabMode::Locked;
-        primary_window.cursor.visible = false;
-
-        return;
+        if despawned {
+            let mut primary_window = q_windows.single_mut();
+            primary_window.cursor.grab_mode = CursorGrabMode::Locked;
+            primary_window.cursor.visible = false;
+        }
     }
 }
 
-/// Both spawns and despawns the minigame ui.
+/// Spawns the ui of the sliding zone minigame, it only displays the [`SliderMinigame`] state.
 /// Amazing Ui design btw lmao.
 pub fn spawn_lockpicking_minigame_ui(
-    menus: Query<(Entity, &LockPickMenu)>,
-    lock_pick_targets: Query<(&Locked, &LockPickTarget), Added<LockPickTarget>>,
+    q_slider: Query<(Entity, &SliderMinigame, &LockPickTarget), Added<SliderMinigame>>,
+    q_player: Query<(), With<Player>>,
     mut commands: Commands,
-    time: Res<Time>,
     mut q_windows: Query<&mut Window, With<PrimaryWindow>>,
 ) {
-    for (lock_settings, _) in lock_pick_targets.iter() {
-        // don't respawn menu if it already exists
-        if menus.iter().len() != 0 {
-            return;
+    for (lock, slider, target) in q_slider.iter() {
+        if !has_ui(target, &q_player) {
+            continue;
         }
 
         let mut primary_window = q_windows.single_mut();
         primary_window.cursor.grab_mode = CursorGrabMode::None;
         primary_window.cursor.visible = true;
 
-        let mut success_zone = None;
-        let mut zone_current_pos = None;
-
         commands
             .spawn((
-                LockPickMenu,
+                LockPickMenu { lock },
                 Name::new("lock picking minigame ui"),
                 NodeBundle {
                     style: Style {
-                        width: Val::Percent(80.0),
+                        width: Val::Percent(SLIDER_BAR_WIDTH),
                         height: Val::Percent(10.0),
                         justify_content: JustifyContent::Center,
                         flex_direction: FlexDirection::Column,
@@ -206,16 +109,10 @@ pub fn spawn_lockpicking_minigame_ui(
             .with_children(|parent| {
                 parent
                     .spawn((
-                        ButtonBundle {
+                        NodeBundle {
                             style: Style {
                                 width: Val::Percent(100.0),
                                 height: Val::Percent(100.0),
-                                justify_content: JustifyContent::Center,
-                                flex_direction: FlexDirection::Column,
-                                align_content: AlignContent::Center,
-                                align_items: AlignItems::Center,
-                                justify_self: JustifySelf::Center,
-                                align_self: AlignSelf::Center,
                                 ..default()
                             },
                             background_color: BackgroundColor(Color::Srgba(DARK_RED)),
@@ -229,89 +126,78 @@ pub fn spawn_lockpicking_minigame_ui(
                             ..default()
                         },
                         Name::new("test-bar"),
-                        PickFailZone,
                     ))
                     .with_children(|parent| {
                         // success zone
-                        let width = lock_settings.success_zone_width;
-
-                        let zone = parent
-                            .spawn((
-                                ButtonBundle {
-                                    style: Style {
-                                        width: Val::Percent(width),
-                                        height: Val::Percent(100.0),
-                                        justify_content: JustifyContent::Start,
-                                        flex_direction: FlexDirection::Column,
-                                        align_content: AlignContent::Start,
-                                        align_items: AlignItems::Start,
-                                        justify_self: JustifySelf::Start,
-                                        align_self: AlignSelf::Start,
-                                        ..default()
-                                    },
-                                    background_color: BackgroundColor(Color::Srgba(DARK_GREEN)),
-                                    border_color: BorderColor(Color::BLACK),
-                                    border_radius: BorderRadius {
-                                        top_left: Val::Percent(25.0),
-                                        top_right: Val::Percent(25.0),
-                                        bottom_left: Val::Percent(25.0),
-                                        bottom_right: Val::Percent(25.0),
-                                    },
-                                    z_index: ZIndex::Local(1),
+                        parent.spawn((
+                            NodeBundle {
+                                style: Style {
+                                    position_type: PositionType::Absolute,
+                                    width: Val::Percent(slider.zone_width),
+                                    height: Val::Percent(100.0),
+                                    left: Val::Percent(slider.zone_position),
+                                    ..default()
+                                },
+                                background_color: BackgroundColor(Color::Srgba(DARK_GREEN)),
+                                border_color: BorderColor(Color::BLACK),
+                                border_radius: BorderRadius {
+                                    top_left: Val::Percent(25.0),
+                                    top_right: Val::Percent(25.0),
+                                    bottom_left: Val::Percent(25.0),
+                                    bottom_right: Val::Percent(25.0),
+                                },
+                                z_index: ZIndex::Local(1),
+                                ..default()
+                            },
+                            Name::new("test-bar"),
+                            PickSuccessZone { lock },
+                        ));
+
+                        // lockpick
+                        parent.spawn((
+                            NodeBundle {
+                                style: Style {
+                                    width: Val::Percent(1.0),
+                                    height: Val::Percent(100.0),
+                                    position_type: PositionType::Absolute,
+                                    left: Val::Percent(slider.pick_position),
+                                    // center the widget on the pick position
+                                    margin: UiRect::left(Val::Percent(-0.5)),
                                     ..default()
                                 },
-                                Name::new("test-bar"),
-                                PickSuccessZone,
-                                RandomizePos,
-                            ))
-                            .id();
-                        success_zone = Some(zone);
-                        zone_current_pos = Some(width);
+                                background_color: BackgroundColor(Color::Srgba(GRAY)),
+                                border_color: BorderColor(Color::BLACK),
+                                z_index: ZIndex::Local(2),
+                                ..default()
+                            },
+                            Name::new("lock-pick"),
+                            LockPickWidget { lock },
+                        ));
                     });
             });
+    }
+}
 
-        // lockpick
-        commands.spawn((
-            NodeBundle {
-                style: Style {
-                    width: Val::Percent(2.0),
-                    height: Val::Percent(10.0),
-                    position_type: PositionType::Absolute,
-                    align_self: AlignSelf::Center,
-                    // justify_content: JustifyContent::Center,
-                    // flex_direction: FlexDirection::Column,
-                    // align_content: AlignContent::Center,
-                    // align_items: AlignItems::Center,
-                    padding: UiRect {
-                        left: Val::Px(0.0),
-                        right: Val::Percent(0.0),
-                        top: Val::Percent(5.0),
-                        bottom: Val::Percent(5.0),
-                    },
-                    left: Val::Percent(0.0),
-                    ..default()
-                },
-                background_color: BackgroundColor(Color::Srgba(GRAY)),
-                border_color: BorderColor(Color::BLACK),
-                z_index: ZIndex::Local(2),
-                ..default()
-            },
-            Name::new("lock-pick"),
-            LockPickWidget,
-            LockPickMenu,
-        ));
+/// Moves the success zone and the lockpick widget to where they are in the [`SliderMinigame`].
+pub fn update_lockpicking_minigame_ui(
+    q_slider: Query<&SliderMinigame>,
+    mut q_zone: Query<(&PickSuccessZone, &mut Style), Without<LockPickWidget>>,
+    mut q_pick: Query<(&LockPickWidget, &mut Style), Without<PickSuccessZone>>,
+) {
+    for (zone, mut style) in q_zone.iter_mut() {
+        let Ok(slider) = q_slider.get(zone.lock) else {
+            continue;
+        };
 
-        match &lock_settings.zone_slide_settings {
-            SlideSettings::NoSlide => {}
-            SlideSettings::SlideLinear(settings) => {
-                commands.entity(success_zone.unwrap()).insert(SlideTarget {
-                    speed: settings.speed,
-                    start_pos: zone_current_pos.unwrap(),
-                    target_pos: zone_current_pos.unwrap(),
-                    start_time_secs: time.elapsed_seconds(),
-                    time_to_target: settings.time_to_target,
-                });
-            }
-        }
+        style.left = Val::Percent(slider.zone_position);
+        style.width = Val::Percent(slider.zone_width);
+    }
+
+    for (pick, mut style) in q_pick.iter_mut() {
+        let Ok(slider) = q_slider.get(pick.lock) else {
+            continue;
+        };
+
+        style.left = Val::Percent(slider.pick_position);
     }
 }
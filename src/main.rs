@@ -8,15 +8,22 @@ use asset_loading::AssetLoaderPlugin;
 use avian3d::prelude::*;
 use bevy::{
     asset::AssetMetaCheck,
-    core_pipeline::{prepass::NormalPrepass, Skybox},
+    core_pipeline::prepass::NormalPrepass,
     prelude::*,
 };
 use bevy_camera_extras::*;
 
 // use bevy_inspector_egui::quick::WorldInspectorPlugin;
+use bevy_jam5::bike_owner::BikeOwnerPlugin;
+use bevy_jam5::inventory::InventoryPlugin;
+use bevy_jam5::levels::LevelsPlugin;
+use bevy_jam5::minimap::MinimapPlugin;
+use bevy_jam5::navigation::NavigationPlugin;
 use bevy_jam5::player_car_swap::*;
 use bevy_jam5::player_controller::*;
 use bevy_jam5::points::*;
+use bevy_jam5::police::PolicePlugin;
+use bevy_jam5::resources::Difficulty;
 use bevy_jam5::simulation_state::*;
 use bevy_jam5::{asset_loading, cubemap_factory::*, world_spawning::*, *};
 use bevy_jam5::{car_controller::*, lockpicking::LockPickingPlugin};
@@ -26,6 +33,7 @@ use bevy_outline_post_process::{components::OutlinePostProcessSettings, OutlineP
 fn main() {
     App::new()
         .insert_resource(MovementSettings::default())
+        .init_resource::<Difficulty>()
         .add_plugins((
             DefaultPlugins.set(AssetPlugin {
                 // Wasm builds will check for meta files (that don't exist) if this isn't set.
@@ -55,55 +63,45 @@ fn main() {
                 movement_settings_override: None,
             },
             LockPickingPlugin,
+            BikeOwnerPlugin,
+            PolicePlugin,
+        ))
+        .add_plugins((
+            NavigationPlugin,
+            MinimapPlugin,
+            InventoryPlugin,
+            LevelsPlugin,
             // PhysicsDebugPlugin::default(),
         ))
         .add_plugins((
             rules::plugin,
             pause_menu::plugin,
             home::plugin,
+            gas_station::plugin,
             introduction::plugin,
+            perception::plugin,
+            shift::plugin,
+            damping::plugin,
+            bike_spawner::plugin,
+            time_of_day::plugin,
+            weather::plugin,
+            sky::plugin,
+            camera_follow::plugin,
         ))
         .insert_resource(SubstepCount(50))
         .init_state::<GameState>()
         .insert_resource(Msaa::Off)
-        .init_state::<TestSkyboxState>()
-        .add_systems(
-            Update,
-            test_skybox.run_if(in_state(TestSkyboxState::Waiting)),
-        )
+        .add_systems(Update, setup_camera)
         .insert_resource(MovementSettings::default())
         .run();
 }
 
-#[derive(States, Clone, PartialEq, Eq, Hash, Debug, Default)]
-enum TestSkyboxState {
-    #[default]
-    Waiting,
-    Done,
-}
-
-/// This system adds a skybox to the camera after it is loaded.
-/// `TODO` properly initialize skybox instead.
-fn test_skybox(
-    cameras: Query<Entity, With<Camera>>,
-    mut commands: Commands,
-    mut cubemap_factory: ResMut<CubemapFactory>,
-    assets: Res<AssetServer>,
-    images: Res<Assets<Image>>,
-    mut next_state: ResMut<NextState<TestSkyboxState>>,
-) {
-    if cameras.is_empty() {
-        return;
+/// Every level spawns a new camera, it gets the outlines. The skybox is added by the sky plugin.
+fn setup_camera(q_camera: Query<Entity, Added<Camera3d>>, mut commands: Commands) {
+    for camera_entity in q_camera.iter() {
+        commands.entity(camera_entity).insert((
+            NormalPrepass,
+            OutlinePostProcessSettings::new(1.5, 0.0, false),
+        ));
     }
-
-    commands.entity(cameras.get_single().unwrap()).insert((
-        Skybox {
-            image: cubemap_factory.load_from_folder("sky", assets, images),
-            brightness: 1000.0,
-        },
-        NormalPrepass,
-        OutlinePostProcessSettings::new(1.5, 0.0, false),
-    ));
-
-    next_state.set(TestSkyboxState::Done);
 }
@@ -0,0 +1,59 @@
+use bevy::prelude::*;
+
+/// Shows the entity on the minimap.
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum MinimapIcon {
+    Car,
+    Player,
+    Home,
+    GasStation,
+    IllegalBicycle,
+}
+
+impl MinimapIcon {
+    pub fn color(&self) -> Color {
+        return match self {
+            MinimapIcon::Car => Color::srgb(0.3, 0.6, 1.0),
+            MinimapIcon::Player => Color::WHITE,
+            MinimapIcon::Home => Color::srgb(1.0, 0.1, 0.1),
+            MinimapIcon::GasStation => Color::srgb(1.0, 0.8, 0.1),
+            MinimapIcon::IllegalBicycle => Color::srgb(0.3, 1.0, 0.3),
+        };
+    }
+
+    /// Size of the icon in pixels.
+    pub fn size(&self) -> f32 {
+        return match self {
+            MinimapIcon::Home | MinimapIcon::GasStation => 12.0,
+            MinimapIcon::Car => 10.0,
+            MinimapIcon::Player | MinimapIcon::IllegalBicycle => 6.0,
+        };
+    }
+
+    /// Icons drawn later are on top.
+    pub fn z_index(&self) -> i32 {
+        return match self {
+            MinimapIcon::Home | MinimapIcon::GasStation => 0,
+            MinimapIcon::IllegalBicycle => 1,
+            MinimapIcon::Car => 2,
+            MinimapIcon::Player => 3,
+        };
+    }
+}
+
+#[derive(Component)]
+pub struct MinimapUIRoot;
+
+/// The panel the markers are placed on.
+#[derive(Component)]
+pub struct MinimapUI;
+
+/// A dot on the minimap following `target`.
+#[derive(Component)]
+pub struct MinimapMarker {
+    pub target: Entity,
+}
+
+/// A dot of the GPS route drawn on the minimap.
+#[derive(Component)]
+pub struct RouteDot;
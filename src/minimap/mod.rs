@@ -0,0 +1,8 @@
+mod components;
+mod plugin;
+mod resources;
+mod systems;
+
+pub use components::*;
+pub use plugin::MinimapPlugin;
+pub use resources::*;
@@ -0,0 +1,39 @@
+use bevy::prelude::*;
+
+use crate::{levels::reset_resource, GameState};
+
+use super::resources::*;
+use super::systems::*;
+
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MinimapSettings>()
+            .init_resource::<MapBounds>()
+            .init_resource::<GpsRoute>()
+            .add_systems(
+                OnEnter(GameState::LoadingLevel),
+                (reset_resource::<MapBounds>, reset_resource::<GpsRoute>),
+            )
+            .add_systems(OnEnter(GameState::Playing), setup_minimap_ui)
+            .add_systems(
+                Update,
+                (
+                    update_map_bounds,
+                    (tag_points_of_interest, reveal_illegal_bicycles),
+                    spawn_markers,
+                    toggle_route,
+                    request_route,
+                    update_route,
+                    draw_route,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                PostUpdate,
+                (update_markers, update_route_dots).run_if(in_state(GameState::Playing)),
+            );
+    }
+}
@@ -0,0 +1,69 @@
+use bevy::prelude::*;
+
+#[derive(Resource)]
+pub struct MinimapSettings {
+    /// Size of the minimap in pixels.
+    pub size: f32,
+    /// Illegal bicycles closer than this to the player are shown on the minimap.
+    pub reveal_distance: f32,
+    /// The GPS route to home is shown when the fuel level drops below this fraction of the capacity.
+    pub low_fuel_fraction: f32,
+    /// Seconds between recalculations of the GPS route.
+    pub route_refresh_time: f32,
+    /// Distance between the dots of the route on the minimap in pixels.
+    pub route_dot_spacing: f32,
+}
+
+impl Default for MinimapSettings {
+    fn default() -> Self {
+        Self {
+            size: 200.0,
+            reveal_distance: 15.0,
+            low_fuel_fraction: 0.25,
+            route_refresh_time: 1.0,
+            route_dot_spacing: 6.0,
+        }
+    }
+}
+
+/// The area of the map shown on the minimap.
+#[derive(Resource, Default)]
+pub struct MapBounds(Option<Rect>);
+
+impl MapBounds {
+    pub fn get(&self) -> Option<Rect> {
+        return self.0;
+    }
+
+    pub(crate) fn include(&mut self, rect: Rect) {
+        self.0 = Some(match self.0 {
+            Some(bounds) => bounds.union(rect),
+            None => rect,
+        });
+    }
+
+    /// Position of a point on the map where (0, 0) is the top left and (1, 1) the bottom right corner.
+    pub fn normalize(&self, point: Vec3) -> Option<Vec2> {
+        let bounds = self.0?;
+        let size = bounds.size().max(Vec2::splat(f32::EPSILON));
+
+        return Some(((point.xz() - bounds.min) / size).clamp(Vec2::ZERO, Vec2::ONE));
+    }
+}
+
+/// The route to home shown when the car is low on fuel.
+#[derive(Resource)]
+pub struct GpsRoute {
+    /// Toggled by the player.
+    pub enabled: bool,
+    pub waypoints: Vec<Vec3>,
+}
+
+impl Default for GpsRoute {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            waypoints: Vec::new(),
+        }
+    }
+}
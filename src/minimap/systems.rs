@@ -0,0 +1,366 @@
+use avian3d::collision::Sensor;
+use bevy::{prelude::*, render::primitives::Aabb};
+
+use crate::{
+    car_controller::{components::Fuel, CarController},
+    gas_station::GasStation,
+    home::Home,
+    navigation::{NavPath, PathNotFound, PathRequest, Pathfinding},
+    player_controller::Player,
+    world_spawning::on_spawn::{Bicycle, Illegal, MapElement},
+};
+
+use super::components::*;
+use super::resources::*;
+
+pub fn setup_minimap_ui(settings: Res<MinimapSettings>, mut commands: Commands) {
+    commands
+        .spawn((
+            MinimapUIRoot,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(15.0),
+                    bottom: Val::Px(15.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                MinimapUI,
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(settings.size),
+                        height: Val::Px(settings.size),
+                        border: UiRect::all(Val::Px(2.0)),
+                        overflow: Overflow::clip(),
+                        ..Default::default()
+                    },
+                    background_color: Color::srgba(0.0, 0.0, 0.0, 0.6).into(),
+                    border_color: Color::WHITE.into(),
+                    ..Default::default()
+                },
+            ));
+
+            parent.spawn(TextBundle {
+                style: Style {
+                    margin: UiRect::top(Val::Px(5.0)),
+                    ..Default::default()
+                },
+                text: Text::from_section(
+                    "G - Toggle GPS",
+                    TextStyle {
+                        font_size: 18.0,
+                        color: Color::WHITE,
+                        ..Default::default()
+                    },
+                ),
+                ..Default::default()
+            });
+        });
+}
+
+/// Grows the minimap bounds to fit the geometry of the map.
+pub fn update_map_bounds(
+    q_primitive: Query<(&Aabb, &GlobalTransform, &Parent), Added<Aabb>>,
+    q_map_element: Query<(), With<MapElement>>,
+    mut bounds: ResMut<MapBounds>,
+) {
+    for (aabb, gtransform, parent) in q_primitive.iter() {
+        if q_map_element.contains(parent.get()) {
+            bounds.include(world_rect(aabb, gtransform));
+        }
+    }
+}
+
+pub fn tag_points_of_interest(
+    q_car: Query<Entity, (With<CarController>, Without<MinimapIcon>)>,
+    q_player: Query<Entity, (With<Player>, Without<MinimapIcon>)>,
+    q_home: Query<Entity, (With<Home>, With<Sensor>, Without<MinimapIcon>)>,
+    q_gas_station: Query<Entity, (With<GasStation>, With<Sensor>, Without<MinimapIcon>)>,
+    mut commands: Commands,
+) {
+    for entity in q_car.iter() {
+        commands.entity(entity).insert(MinimapIcon::Car);
+    }
+
+    for entity in q_player.iter() {
+        commands.entity(entity).insert(MinimapIcon::Player);
+    }
+
+    for entity in q_home.iter() {
+        commands.entity(entity).insert(MinimapIcon::Home);
+    }
+
+    for entity in q_gas_station.iter() {
+        commands.entity(entity).insert(MinimapIcon::GasStation);
+    }
+}
+
+/// Illegal bicycles the player has come close to stay on the minimap.
+pub fn reveal_illegal_bicycles(
+    q_player: Query<&GlobalTransform, With<Player>>,
+    q_bicycle: Query<
+        (Entity, &GlobalTransform),
+        (With<Bicycle>, With<Illegal>, Without<MinimapIcon>),
+    >,
+    settings: Res<MinimapSettings>,
+    mut commands: Commands,
+) {
+    let Ok(player_gtransform) = q_player.get_single() else {
+        return;
+    };
+
+    for (entity, gtransform) in q_bicycle.iter() {
+        let distance = player_gtransform
+            .translation()
+            .distance(gtransform.translation());
+
+        if distance <= settings.reveal_distance {
+            commands.entity(entity).insert(MinimapIcon::IllegalBicycle);
+        }
+    }
+}
+
+pub fn spawn_markers(
+    q_icon: Query<(Entity, &MinimapIcon), Added<MinimapIcon>>,
+    q_minimap: Query<Entity, With<MinimapUI>>,
+    mut commands: Commands,
+) {
+    let Ok(minimap_entity) = q_minimap.get_single() else {
+        return;
+    };
+
+    for (entity, icon) in q_icon.iter() {
+        let size = icon.size();
+
+        let marker_entity = commands
+            .spawn((
+                MinimapMarker { target: entity },
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        width: Val::Px(size),
+                        height: Val::Px(size),
+                        margin: UiRect {
+                            left: Val::Px(-size / 2.0),
+                            top: Val::Px(-size / 2.0),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    background_color: icon.color().into(),
+                    border_radius: BorderRadius::MAX,
+                    visibility: Visibility::Hidden,
+                    z_index: ZIndex::Local(icon.z_index()),
+                    ..Default::default()
+                },
+            ))
+            .id();
+
+        commands.entity(minimap_entity).add_child(marker_entity);
+    }
+}
+
+pub fn update_markers(
+    mut q_marker: Query<(Entity, &MinimapMarker, &mut Style, &mut Visibility)>,
+    q_target: Query<(&GlobalTransform, Option<&Aabb>)>,
+    bounds: Res<MapBounds>,
+    mut commands: Commands,
+) {
+    for (entity, marker, mut style, mut visibility) in q_marker.iter_mut() {
+        let Ok((gtransform, aabb)) = q_target.get(marker.target) else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+
+        let Some(position) = bounds.normalize(world_position(gtransform, aabb)) else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+
+        style.left = Val::Percent(position.x * 100.0);
+        style.top = Val::Percent(position.y * 100.0);
+        *visibility = Visibility::Inherited;
+    }
+}
+
+pub fn toggle_route(keyboard_input: Res<ButtonInput<KeyCode>>, mut route: ResMut<GpsRoute>) {
+    if keyboard_input.just_pressed(KeyCode::KeyG) {
+        route.enabled = !route.enabled;
+    }
+}
+
+/// Asks for a new route to the closest home every now and then while the car is low on fuel.
+pub fn request_route(
+    time: Res<Time>,
+    mut since_last_request: Local<f32>,
+    settings: Res<MinimapSettings>,
+    route: Res<GpsRoute>,
+    pathfinding: Option<Res<Pathfinding>>,
+    q_car: Query<(Entity, &GlobalTransform, &Fuel), With<CarController>>,
+    q_home: Query<(&GlobalTransform, Option<&Aabb>), (With<Home>, With<Sensor>)>,
+    mut commands: Commands,
+) {
+    *since_last_request += time.delta_seconds();
+
+    if !route.enabled || pathfinding.is_none() || *since_last_request < settings.route_refresh_time
+    {
+        return;
+    }
+
+    *since_last_request = 0.0;
+
+    for (entity, car_gtransform, fuel) in q_car.iter() {
+        if !is_low_on_fuel(fuel, &settings) {
+            continue;
+        }
+
+        let car_position = car_gtransform.translation();
+
+        let closest_home = q_home
+            .iter()
+            .map(|(gtransform, aabb)| world_position(gtransform, aabb))
+            .min_by(|a, b| {
+                a.distance_squared(car_position)
+                    .total_cmp(&b.distance_squared(car_position))
+            });
+
+        if let Some(destination) = closest_home {
+            commands.entity(entity).insert(PathRequest { destination });
+        }
+    }
+}
+
+pub fn update_route(
+    q_car: Query<(&Fuel, Option<Ref<NavPath>>, Has<PathNotFound>), With<CarController>>,
+    settings: Res<MinimapSettings>,
+    mut route: ResMut<GpsRoute>,
+) {
+    let Ok((fuel, path, not_found)) = q_car.get_single() else {
+        return;
+    };
+
+    if !route.enabled || !is_low_on_fuel(fuel, &settings) || not_found {
+        if !route.waypoints.is_empty() {
+            route.waypoints.clear();
+        }
+        return;
+    }
+
+    if let Some(path) = path {
+        if path.is_changed() {
+            route.waypoints = path.waypoints.clone();
+        }
+    }
+}
+
+pub fn draw_route(route: Res<GpsRoute>, mut gizmos: Gizmos) {
+    gizmos.linestrip(
+        route
+            .waypoints
+            .iter()
+            .map(|waypoint| *waypoint + Vec3::Y * 0.3),
+        Color::srgb(1.0, 0.1, 0.1),
+    );
+}
+
+pub fn update_route_dots(
+    q_route_dot: Query<Entity, With<RouteDot>>,
+    q_minimap: Query<Entity, With<MinimapUI>>,
+    route: Res<GpsRoute>,
+    bounds: Res<MapBounds>,
+    settings: Res<MinimapSettings>,
+    mut commands: Commands,
+) {
+    if !route.is_changed() && !bounds.is_changed() {
+        return;
+    }
+
+    for entity in q_route_dot.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let Ok(minimap_entity) = q_minimap.get_single() else {
+        return;
+    };
+
+    // waypoints in minimap pixels
+    let points: Vec<Vec2> = route
+        .waypoints
+        .iter()
+        .filter_map(|waypoint| bounds.normalize(*waypoint))
+        .map(|position| position * settings.size)
+        .collect();
+
+    for segment in points.windows(2) {
+        let length = segment[0].distance(segment[1]);
+        let steps = (length / settings.route_dot_spacing).ceil().max(1.0) as usize;
+
+        for step in 0..steps {
+            let position = segment[0].lerp(segment[1], step as f32 / steps as f32);
+
+            let dot_entity = commands
+                .spawn((
+                    RouteDot,
+                    NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            left: Val::Px(position.x - 1.5),
+                            top: Val::Px(position.y - 1.5),
+                            width: Val::Px(3.0),
+                            height: Val::Px(3.0),
+                            ..Default::default()
+                        },
+                        background_color: Color::srgb(1.0, 0.1, 0.1).into(),
+                        z_index: ZIndex::Local(-1),
+                        ..Default::default()
+                    },
+                ))
+                .id();
+
+            commands.entity(minimap_entity).add_child(dot_entity);
+        }
+    }
+}
+
+fn is_low_on_fuel(fuel: &Fuel, settings: &MinimapSettings) -> bool {
+    return fuel.get_level() <= fuel.get_capacity() * settings.low_fuel_fraction;
+}
+
+/// Center of the mesh if the entity has one, otherwise its translation.
+fn world_position(gtransform: &GlobalTransform, aabb: Option<&Aabb>) -> Vec3 {
+    return match aabb {
+        Some(aabb) => gtransform.transform_point(aabb.center.into()),
+        None => gtransform.translation(),
+    };
+}
+
+/// The area an axis aligned bounding box covers on the ground.
+fn world_rect(aabb: &Aabb, gtransform: &GlobalTransform) -> Rect {
+    let center = Vec3::from(aabb.center);
+    let half_extents = Vec3::from(aabb.half_extents);
+
+    let mut min = Vec2::splat(f32::MAX);
+    let mut max = Vec2::splat(f32::MIN);
+
+    for corner in 0..8 {
+        let sign = Vec3::new(
+            if corner & 1 == 0 { -1.0 } else { 1.0 },
+            if corner & 2 == 0 { -1.0 } else { 1.0 },
+            if corner & 4 == 0 { -1.0 } else { 1.0 },
+        );
+        let point = gtransform
+            .transform_point(center + half_extents * sign)
+            .xz();
+
+        min = min.min(point);
+        max = max.max(point);
+    }
+
+    return Rect::from_corners(min, max);
+}
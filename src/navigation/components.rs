@@ -0,0 +1,22 @@
+use bevy::{prelude::*, tasks::Task};
+
+/// Asks for a path from the entity to `destination`, replaced by a [`NavPath`] or
+/// [`PathNotFound`] once the query finishes.
+#[derive(Component)]
+pub struct PathRequest {
+    pub destination: Vec3,
+}
+
+/// A path query running in the background.
+#[derive(Component)]
+pub struct PathQuery(pub(super) Task<Option<Vec<Vec3>>>);
+
+/// The waypoints of a found path, starting at the entity.
+#[derive(Component)]
+pub struct NavPath {
+    pub waypoints: Vec<Vec3>,
+}
+
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+pub struct PathNotFound;
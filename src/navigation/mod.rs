@@ -0,0 +1,10 @@
+mod components;
+mod navmesh;
+mod plugin;
+mod resources;
+mod systems;
+
+pub use components::*;
+pub use navmesh::*;
+pub use plugin::NavigationPlugin;
+pub use resources::*;
@@ -0,0 +1,702 @@
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    io::{self, Read, Write},
+};
+
+use bevy::math::{Vec2, Vec3, Vec3Swizzles};
+
+/// Settings describing the agents walking on a [`NavMesh`].
+#[derive(Clone, Debug)]
+pub struct NavMeshSettings {
+    /// Size of a single cell of the mesh on the horizontal plane.
+    pub cell_size: f32,
+    /// Minimum free space above a walkable surface.
+    pub agent_height: f32,
+    /// Minimum distance kept from walls and ledges.
+    pub agent_radius: f32,
+    /// Steepest walkable slope in radians.
+    pub max_slope: f32,
+    /// Highest ledge an agent can step up or down.
+    pub max_step: f32,
+}
+
+impl Default for NavMeshSettings {
+    fn default() -> Self {
+        Self {
+            cell_size: 0.5,
+            agent_height: 1.8,
+            agent_radius: 0.4,
+            max_slope: 40f32.to_radians(),
+            max_step: 0.4,
+        }
+    }
+}
+
+/// Walkable area of the world, stored as a height field over a regular grid on the XZ plane.
+///
+/// Each cell either holds the height of the floor an agent stands on or nothing if the cell
+/// can't be walked on.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NavMesh {
+    origin: Vec2,
+    cell_size: f32,
+    max_step: f32,
+    width: usize,
+    depth: usize,
+    heights: Vec<Option<f32>>,
+}
+
+const MAGIC: &[u8; 4] = b"NAVM";
+const VERSION: u32 = 1;
+/// Cached navmeshes with more cells than this are damaged, no map is that big.
+const MAX_CELLS: usize = 1 << 24;
+
+/// Cells further away from the requested point are not considered when snapping to the mesh.
+const MAX_SNAP_CELLS: i32 = 4;
+
+impl NavMesh {
+    /// Bakes a navmesh from world space triangles.
+    pub fn bake(triangles: &[[Vec3; 3]], settings: &NavMeshSettings) -> Self {
+        let cell_size = settings.cell_size;
+
+        let Some((min, max)) = bounds(triangles) else {
+            return Self {
+                origin: Vec2::ZERO,
+                cell_size,
+                max_step: settings.max_step,
+                width: 0,
+                depth: 0,
+                heights: Vec::new(),
+            };
+        };
+
+        let width = ((max.x - min.x) / cell_size).ceil() as usize + 1;
+        let depth = ((max.y - min.y) / cell_size).ceil() as usize + 1;
+
+        let mut navmesh = Self {
+            origin: min,
+            cell_size,
+            max_step: settings.max_step,
+            width,
+            depth,
+            heights: vec![None; width * depth],
+        };
+
+        // every surface crossing the center of a cell, with whether it is walkable
+        let mut surfaces: Vec<Vec<(f32, bool)>> = vec![Vec::new(); width * depth];
+        let min_normal_y = settings.max_slope.cos();
+
+        for triangle in triangles {
+            let normal = (triangle[1] - triangle[0]).cross(triangle[2] - triangle[0]);
+            let length = normal.length();
+
+            if length <= f32::EPSILON {
+                continue;
+            }
+
+            let walkable = normal.y / length >= min_normal_y;
+
+            let triangle_min = triangle[0].xz().min(triangle[1].xz()).min(triangle[2].xz());
+            let triangle_max = triangle[0].xz().max(triangle[1].xz()).max(triangle[2].xz());
+
+            let (x0, z0) = navmesh.cell_of(triangle_min);
+            let (x1, z1) = navmesh.cell_of(triangle_max);
+
+            for z in z0.max(0)..=z1.min(depth as i32 - 1) {
+                for x in x0.max(0)..=x1.min(width as i32 - 1) {
+                    let center = navmesh.cell_center(x as usize, z as usize);
+
+                    if let Some(height) = height_in_triangle(center, triangle) {
+                        surfaces[z as usize * width + x as usize].push((height, walkable));
+                    }
+                }
+            }
+        }
+
+        // the floor of a cell is the highest walkable surface with enough room above it
+        for (i, cell_surfaces) in surfaces.iter().enumerate() {
+            navmesh.heights[i] = cell_surfaces
+                .iter()
+                .filter(|(height, walkable)| {
+                    *walkable
+                        && !cell_surfaces.iter().any(|(other, _)| {
+                            *other > height + settings.max_step
+                                && *other < height + settings.agent_height
+                        })
+                })
+                .map(|(height, _)| *height)
+                .max_by(|a, b| a.total_cmp(b));
+        }
+
+        navmesh.erode((settings.agent_radius / cell_size).ceil() as i32);
+
+        return navmesh;
+    }
+
+    /// Removes cells closer than `radius` cells to a wall or ledge.
+    fn erode(&mut self, radius: i32) {
+        if radius <= 0 {
+            return;
+        }
+
+        let original = self.heights.clone();
+
+        for z in 0..self.depth as i32 {
+            for x in 0..self.width as i32 {
+                let Some(height) = original[z as usize * self.width + x as usize] else {
+                    continue;
+                };
+
+                let blocked = (-radius..=radius).any(|dz| {
+                    (-radius..=radius).any(|dx| {
+                        let distance = dx.abs().max(dz.abs()) as f32;
+
+                        match self.index(x + dx, z + dz).and_then(|i| original[i]) {
+                            Some(other) => (other - height).abs() > self.max_step * distance,
+                            None => true,
+                        }
+                    })
+                });
+
+                if blocked {
+                    self.heights[z as usize * self.width + x as usize] = None;
+                }
+            }
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        return self.width;
+    }
+
+    pub fn depth(&self) -> usize {
+        return self.depth;
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        return self.cell_size;
+    }
+
+    /// Minimum and maximum corner of the mesh on the XZ plane.
+    pub fn bounds(&self) -> (Vec2, Vec2) {
+        let size = Vec2::new(self.width as f32, self.depth as f32) * self.cell_size;
+        return (self.origin, self.origin + size);
+    }
+
+    /// Height of the floor at a point or `None` if the point is not walkable.
+    pub fn height_at(&self, point: Vec3) -> Option<f32> {
+        let (x, z) = self.cell_of(point.xz());
+        return self.index(x, z).and_then(|i| self.heights[i]);
+    }
+
+    pub fn is_walkable(&self, point: Vec3) -> bool {
+        return self.height_at(point).is_some();
+    }
+
+    /// Finds a path between two points using A*, the result starts at the walkable point
+    /// closest to `from` and ends at the walkable point closest to `to`.
+    pub fn find_path(&self, from: Vec3, to: Vec3) -> Option<Vec<Vec3>> {
+        let start = self.snap(from)?;
+        let goal = self.snap(to)?;
+
+        let mut open = BinaryHeap::new();
+        let mut came_from: Vec<Option<usize>> = vec![None; self.heights.len()];
+        let mut costs = vec![f32::INFINITY; self.heights.len()];
+
+        costs[start] = 0.0;
+        open.push(OpenNode {
+            index: start,
+            estimate: self.heuristic(start, goal),
+        });
+
+        while let Some(OpenNode { index, .. }) = open.pop() {
+            if index == goal {
+                let mut cells = vec![index];
+                let mut current = index;
+
+                while let Some(previous) = came_from[current] {
+                    cells.push(previous);
+                    current = previous;
+                }
+
+                cells.reverse();
+                return Some(self.smooth(&cells));
+            }
+
+            for (neighbour, step_cost) in self.neighbours(index) {
+                let cost = costs[index] + step_cost;
+
+                if cost < costs[neighbour] {
+                    costs[neighbour] = cost;
+                    came_from[neighbour] = Some(index);
+                    open.push(OpenNode {
+                        index: neighbour,
+                        estimate: cost + self.heuristic(neighbour, goal),
+                    });
+                }
+            }
+        }
+
+        return None;
+    }
+
+    /// Closest walkable cell to a point.
+    fn snap(&self, point: Vec3) -> Option<usize> {
+        let (x, z) = self.cell_of(point.xz());
+
+        for radius in 0..=MAX_SNAP_CELLS {
+            let closest = (-radius..=radius)
+                .flat_map(|dz| (-radius..=radius).map(move |dx| (x + dx, z + dz)))
+                .filter_map(|(x, z)| self.index(x, z))
+                .filter(|i| self.heights[*i].is_some())
+                .min_by(|a, b| {
+                    let a = self.cell_point(*a).distance_squared(point);
+                    let b = self.cell_point(*b).distance_squared(point);
+                    a.total_cmp(&b)
+                });
+
+            if closest.is_some() {
+                return closest;
+            }
+        }
+
+        return None;
+    }
+
+    fn neighbours(&self, index: usize) -> impl Iterator<Item = (usize, f32)> + '_ {
+        let x = (index % self.width) as i32;
+        let z = (index / self.width) as i32;
+
+        [
+            (-1, 0),
+            (1, 0),
+            (0, -1),
+            (0, 1),
+            (-1, -1),
+            (-1, 1),
+            (1, -1),
+            (1, 1),
+        ]
+        .into_iter()
+        .filter_map(move |(dx, dz)| {
+            let neighbour = self.index(x + dx, z + dz)?;
+
+            if !self.connected(index, neighbour) {
+                return None;
+            }
+
+            // don't cut corners
+            if dx != 0 && dz != 0 {
+                let a = self.index(x + dx, z)?;
+                let b = self.index(x, z + dz)?;
+
+                if !self.connected(index, a) || !self.connected(index, b) {
+                    return None;
+                }
+
+                return Some((neighbour, self.cell_size * std::f32::consts::SQRT_2));
+            }
+
+            Some((neighbour, self.cell_size))
+        })
+    }
+
+    fn connected(&self, a: usize, b: usize) -> bool {
+        return match (self.heights[a], self.heights[b]) {
+            (Some(a), Some(b)) => (a - b).abs() <= self.max_step,
+            _ => false,
+        };
+    }
+
+    /// Octile distance between two cells.
+    fn heuristic(&self, a: usize, b: usize) -> f32 {
+        let dx = (a % self.width).abs_diff(b % self.width) as f32;
+        let dz = (a / self.width).abs_diff(b / self.width) as f32;
+
+        return self.cell_size * (dx.max(dz) + (std::f32::consts::SQRT_2 - 1.0) * dx.min(dz));
+    }
+
+    /// Drops every waypoint that can be skipped by walking in a straight line.
+    fn smooth(&self, cells: &[usize]) -> Vec<Vec3> {
+        let points: Vec<Vec3> = cells.iter().map(|i| self.cell_point(*i)).collect();
+
+        let mut waypoints = vec![points[0]];
+        let mut anchor = 0;
+
+        for i in 1..points.len() {
+            if !self.straight_line_walkable(points[anchor], points[i]) {
+                anchor = i - 1;
+                waypoints.push(points[anchor]);
+            }
+        }
+
+        if points.len() > 1 {
+            waypoints.push(points[points.len() - 1]);
+        }
+
+        return waypoints;
+    }
+
+    fn straight_line_walkable(&self, from: Vec3, to: Vec3) -> bool {
+        let distance = from.xz().distance(to.xz());
+        let steps = (distance / (self.cell_size * 0.5)).ceil().max(1.0) as usize;
+
+        let mut previous = self.height_at(from);
+
+        for step in 1..=steps {
+            let point = from.lerp(to, step as f32 / steps as f32);
+            let height = self.height_at(point);
+
+            match (previous, height) {
+                (Some(a), Some(b)) if (a - b).abs() <= self.max_step => {}
+                _ => return false,
+            }
+
+            previous = height;
+        }
+
+        return true;
+    }
+
+    fn cell_of(&self, point: Vec2) -> (i32, i32) {
+        let local = (point - self.origin) / self.cell_size;
+        return (local.x.floor() as i32, local.y.floor() as i32);
+    }
+
+    fn cell_center(&self, x: usize, z: usize) -> Vec2 {
+        return self.origin + (Vec2::new(x as f32, z as f32) + 0.5) * self.cell_size;
+    }
+
+    /// Center of a cell at floor height.
+    fn cell_point(&self, index: usize) -> Vec3 {
+        let center = self.cell_center(index % self.width, index / self.width);
+        return Vec3::new(center.x, self.heights[index].unwrap_or(0.0), center.y);
+    }
+
+    fn index(&self, x: i32, z: i32) -> Option<usize> {
+        if x < 0 || z < 0 || x >= self.width as i32 || z >= self.depth as i32 {
+            return None;
+        }
+
+        return Some(z as usize * self.width + x as usize);
+    }
+
+    /// Key identifying the navmesh baked from some geometry, used for caching. It is a FNV-1a
+    /// hash, the hashers of std may change between Rust releases and the key names files on disk.
+    pub fn cache_key(triangles: &[[Vec3; 3]], settings: &NavMeshSettings) -> u64 {
+        const PRIME: u64 = 0x100000001b3;
+
+        let mut hash: u64 = 0xcbf29ce484222325;
+        let mut write = |word: u32| {
+            for byte in word.to_le_bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(PRIME);
+            }
+        };
+
+        write(VERSION);
+        for value in [
+            settings.cell_size,
+            settings.agent_height,
+            settings.agent_radius,
+            settings.max_slope,
+            settings.max_step,
+        ] {
+            write(value.to_bits());
+        }
+
+        for coordinate in triangles
+            .iter()
+            .flatten()
+            .flat_map(|vertex| vertex.to_array())
+        {
+            write(coordinate.to_bits());
+        }
+
+        return hash;
+    }
+
+    pub fn write(&self, writer: &mut impl Write) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+
+        for value in [self.origin.x, self.origin.y, self.cell_size, self.max_step] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+
+        writer.write_all(&(self.width as u32).to_le_bytes())?;
+        writer.write_all(&(self.depth as u32).to_le_bytes())?;
+
+        for height in &self.heights {
+            writer.write_all(&height.unwrap_or(f32::NAN).to_le_bytes())?;
+        }
+
+        return Ok(());
+    }
+
+    pub fn read(reader: &mut impl Read) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+
+        if &magic != MAGIC || read_u32(reader)? != VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a navmesh or an unsupported version",
+            ));
+        }
+
+        let origin = Vec2::new(read_f32(reader)?, read_f32(reader)?);
+        let cell_size = read_f32(reader)?;
+        let max_step = read_f32(reader)?;
+        let width = read_u32(reader)? as usize;
+        let depth = read_u32(reader)? as usize;
+
+        if !(cell_size.is_finite() && cell_size > 0.0) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid cell size {}", cell_size),
+            ));
+        }
+
+        let cells = width
+            .checked_mul(depth)
+            .filter(|cells| (1..=MAX_CELLS).contains(cells))
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid size {}x{}", width, depth),
+                )
+            })?;
+
+        let mut heights = Vec::with_capacity(cells);
+        for _ in 0..cells {
+            let height = read_f32(reader)?;
+            heights.push((!height.is_nan()).then_some(height));
+        }
+
+        return Ok(Self {
+            origin,
+            cell_size,
+            max_step,
+            width,
+            depth,
+            heights,
+        });
+    }
+}
+
+#[derive(PartialEq)]
+struct OpenNode {
+    index: usize,
+    estimate: f32,
+}
+
+impl Eq for OpenNode {}
+
+impl Ord for OpenNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // reversed, the binary heap is a max heap
+        return other.estimate.total_cmp(&self.estimate);
+    }
+}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        return Some(self.cmp(other));
+    }
+}
+
+fn bounds(triangles: &[[Vec3; 3]]) -> Option<(Vec2, Vec2)> {
+    let mut vertices = triangles.iter().flatten().map(|vertex| vertex.xz());
+    let first = vertices.next()?;
+
+    return Some(vertices.fold((first, first), |(min, max), vertex| {
+        (min.min(vertex), max.max(vertex))
+    }));
+}
+
+/// Height of a triangle above a point of the XZ plane, `None` if the point is outside of it.
+fn height_in_triangle(point: Vec2, [a, b, c]: &[Vec3; 3]) -> Option<f32> {
+    let v0 = b.xz() - a.xz();
+    let v1 = c.xz() - a.xz();
+    let v2 = point - a.xz();
+
+    let denominator = v0.perp_dot(v1);
+
+    // vertical triangles don't cover any area
+    if denominator.abs() <= f32::EPSILON {
+        return None;
+    }
+
+    let v = v2.perp_dot(v1) / denominator;
+    let w = v0.perp_dot(v2) / denominator;
+    let u = 1.0 - v - w;
+
+    if u < -1e-4 || v < -1e-4 || w < -1e-4 {
+        return None;
+    }
+
+    return Some(a.y * u + b.y * v + c.y * w);
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    return Ok(u32::from_le_bytes(bytes));
+}
+
+fn read_f32(reader: &mut impl Read) -> io::Result<f32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    return Ok(f32::from_le_bytes(bytes));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two triangles covering a rectangle at a height.
+    fn quad(min: Vec2, max: Vec2, height: f32) -> [[Vec3; 3]; 2] {
+        let a = Vec3::new(min.x, height, min.y);
+        let b = Vec3::new(max.x, height, min.y);
+        let c = Vec3::new(max.x, height, max.y);
+        let d = Vec3::new(min.x, height, max.y);
+
+        return [[a, c, b], [a, d, c]];
+    }
+
+    /// A 20 by 20 floor with a wall along the Z axis from `wall_min_z` to `wall_max_z`.
+    fn walled_plane(wall_min_z: f32, wall_max_z: f32) -> Vec<[Vec3; 3]> {
+        let mut triangles = quad(Vec2::splat(-10.0), Vec2::splat(10.0), 0.0).to_vec();
+
+        // the top of the wall is too high to step onto and too low to walk under
+        triangles.extend(quad(
+            Vec2::new(-1.0, wall_min_z),
+            Vec2::new(1.0, wall_max_z),
+            1.0,
+        ));
+
+        return triangles;
+    }
+
+    #[test]
+    fn bakes_a_flat_plane() {
+        let triangles = quad(Vec2::splat(-10.0), Vec2::splat(10.0), 0.0);
+        let navmesh = NavMesh::bake(&triangles, &NavMeshSettings::default());
+
+        assert_eq!(navmesh.height_at(Vec3::ZERO), Some(0.0));
+        assert!(!navmesh.is_walkable(Vec3::new(20.0, 0.0, 0.0)));
+
+        // the edges are eroded by the radius of the agent
+        assert!(!navmesh.is_walkable(Vec3::new(9.9, 0.0, 0.0)));
+
+        let path = navmesh
+            .find_path(Vec3::new(-5.0, 0.0, 0.0), Vec3::new(5.0, 0.0, 0.0))
+            .unwrap();
+
+        // nothing is in the way, so the path is a straight line
+        assert_eq!(path.len(), 2);
+    }
+
+    #[test]
+    fn path_goes_around_a_wall() {
+        let navmesh = NavMesh::bake(&walled_plane(-10.0, 5.0), &NavMeshSettings::default());
+
+        // the floor under the wall is gone
+        assert_ne!(navmesh.height_at(Vec3::ZERO), Some(0.0));
+
+        let from = Vec3::new(-5.0, 0.0, 0.0);
+        let to = Vec3::new(5.0, 0.0, 0.0);
+        let path = navmesh.find_path(from, to).unwrap();
+
+        assert!(path.first().unwrap().distance(from) < 0.5);
+        assert!(path.last().unwrap().distance(to) < 0.5);
+
+        // the path has to pass the end of the wall
+        assert!(path.iter().any(|point| point.z > 5.0));
+
+        for pair in path.windows(2) {
+            assert!(navmesh.straight_line_walkable(pair[0], pair[1]));
+        }
+
+        for point in &path {
+            assert_eq!(navmesh.height_at(*point), Some(0.0));
+        }
+    }
+
+    #[test]
+    fn no_path_to_a_walled_off_goal() {
+        let navmesh = NavMesh::bake(&walled_plane(-10.0, 10.0), &NavMeshSettings::default());
+
+        let path = navmesh.find_path(Vec3::new(-5.0, 0.0, 0.0), Vec3::new(5.0, 0.0, 0.0));
+        assert_eq!(path, None);
+    }
+
+    #[test]
+    fn write_and_read_back() {
+        let navmesh = NavMesh::bake(&walled_plane(-10.0, 5.0), &NavMeshSettings::default());
+
+        let mut bytes: Vec<u8> = Vec::new();
+        navmesh.write(&mut bytes).unwrap();
+
+        let read = NavMesh::read(&mut bytes.as_slice()).unwrap();
+        assert_eq!(read, navmesh);
+
+        // something that isn't a navmesh is rejected
+        bytes[0] = b'X';
+        assert!(NavMesh::read(&mut bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn damaged_headers_are_rejected() {
+        let navmesh = NavMesh::bake(&walled_plane(-10.0, 5.0), &NavMeshSettings::default());
+
+        let mut bytes: Vec<u8> = Vec::new();
+        navmesh.write(&mut bytes).unwrap();
+
+        // the cell size comes after the magic, the version and the origin, the size after the
+        // cell size and the max step
+        let damaged = |offset: usize, value: [u8; 4]| {
+            let mut bytes = bytes.clone();
+            bytes[offset..offset + 4].copy_from_slice(&value);
+            return NavMesh::read(&mut bytes.as_slice()).map_err(|err| err.kind());
+        };
+
+        for cell_size in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            assert_eq!(
+                damaged(16, cell_size.to_le_bytes()),
+                Err(io::ErrorKind::InvalidData)
+            );
+        }
+
+        assert_eq!(
+            damaged(24, 0u32.to_le_bytes()),
+            Err(io::ErrorKind::InvalidData)
+        );
+        assert_eq!(
+            damaged(24, u32::MAX.to_le_bytes()),
+            Err(io::ErrorKind::InvalidData)
+        );
+        assert_eq!(
+            damaged(28, u32::MAX.to_le_bytes()),
+            Err(io::ErrorKind::InvalidData)
+        );
+    }
+
+    #[test]
+    fn cache_key_depends_on_the_geometry() {
+        let settings = NavMeshSettings::default();
+        let a = walled_plane(-10.0, 5.0);
+        let b = walled_plane(-10.0, 6.0);
+
+        assert_eq!(
+            NavMesh::cache_key(&a, &settings),
+            NavMesh::cache_key(&a, &settings)
+        );
+        assert_ne!(
+            NavMesh::cache_key(&a, &settings),
+            NavMesh::cache_key(&b, &settings)
+        );
+    }
+}
@@ -0,0 +1,25 @@
+use bevy::prelude::*;
+
+use crate::GameState;
+
+use super::resources::*;
+use super::systems::*;
+
+pub struct NavigationPlugin;
+
+impl Plugin for NavigationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NavMeshBakeSettings>()
+            .add_systems(OnEnter(GameState::LoadingLevel), remove_navmesh)
+            .add_systems(OnEnter(GameState::Playing), start_baking)
+            .add_systems(
+                Update,
+                (
+                    finish_baking.run_if(resource_exists::<NavMeshBaking>),
+                    (start_path_queries, poll_path_queries)
+                        .chain()
+                        .run_if(resource_exists::<Pathfinding>),
+                ),
+            );
+    }
+}
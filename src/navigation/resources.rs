@@ -0,0 +1,47 @@
+use std::sync::Arc;
+
+use bevy::{
+    prelude::*,
+    tasks::{AsyncComputeTaskPool, Task},
+};
+
+use super::navmesh::*;
+
+/// Folder the baked navmeshes are cached in.
+pub const NAVMESH_CACHE_DIR: &str = "cache/navmesh";
+
+#[derive(Resource, Default, Clone)]
+pub struct NavMeshBakeSettings(pub NavMeshSettings);
+
+/// The navmesh being baked in the background.
+#[derive(Resource)]
+pub struct NavMeshBaking(pub(super) Task<NavMesh>);
+
+/// Finds paths on the walkable area of the map, available once the navmesh is baked.
+#[derive(Resource, Clone)]
+pub struct Pathfinding {
+    navmesh: Arc<NavMesh>,
+}
+
+impl Pathfinding {
+    pub fn new(navmesh: NavMesh) -> Self {
+        Self {
+            navmesh: Arc::new(navmesh),
+        }
+    }
+
+    pub fn navmesh(&self) -> &NavMesh {
+        return &self.navmesh;
+    }
+
+    pub fn find_path(&self, from: Vec3, to: Vec3) -> Option<Vec<Vec3>> {
+        return self.navmesh.find_path(from, to);
+    }
+
+    /// Runs [`Pathfinding::find_path`] on the async compute task pool.
+    pub fn find_path_async(&self, from: Vec3, to: Vec3) -> Task<Option<Vec<Vec3>>> {
+        let navmesh = self.navmesh.clone();
+
+        return AsyncComputeTaskPool::get().spawn(async move { navmesh.find_path(from, to) });
+    }
+}
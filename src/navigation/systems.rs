@@ -0,0 +1,166 @@
+use bevy::{
+    gltf::{GltfMesh, GltfNode},
+    prelude::*,
+    render::mesh::{Indices, VertexAttributeValues},
+    tasks::{block_on, poll_once, AsyncComputeTaskPool},
+};
+
+use crate::world_spawning::{on_spawn::MapElement, streaming::WorldChunks};
+
+use super::components::*;
+use super::navmesh::*;
+use super::resources::*;
+
+/// Collects the geometry of every [`MapElement`] and bakes it into a navmesh in the background.
+/// The map elements in chunks that aren't loaded are taken straight from the glTF.
+pub fn start_baking(
+    q_map_element: Query<&Children, With<MapElement>>,
+    q_child: Query<(&Handle<Mesh>, &GlobalTransform)>,
+    world_chunks: Res<WorldChunks>,
+    gltf_nodes: Res<Assets<GltfNode>>,
+    gltf_meshes: Res<Assets<GltfMesh>>,
+    meshes: Res<Assets<Mesh>>,
+    settings: Res<NavMeshBakeSettings>,
+    mut commands: Commands,
+) {
+    let mut triangles = Vec::new();
+
+    for children in q_map_element.iter() {
+        for (mesh_handle, gtransform) in q_child.iter_many(children) {
+            if let Some(mesh) = meshes.get(mesh_handle) {
+                append_triangles(mesh, gtransform, &mut triangles);
+            }
+        }
+    }
+
+    for node in world_chunks.unloaded_nodes() {
+        let gtransform = GlobalTransform::from(node.transform);
+        let primitives = gltf_nodes
+            .get(&node.node)
+            .and_then(|gltf_node| gltf_node.mesh.as_ref())
+            .and_then(|handle| gltf_meshes.get(handle))
+            .into_iter()
+            .flat_map(|gltf_mesh| gltf_mesh.primitives.iter());
+
+        for primitive in primitives {
+            if let Some(mesh) = meshes.get(&primitive.mesh) {
+                append_triangles(mesh, &gtransform, &mut triangles);
+            }
+        }
+    }
+
+    let settings = settings.0.clone();
+    let task =
+        AsyncComputeTaskPool::get().spawn(async move { load_or_bake(&triangles, &settings) });
+
+    commands.insert_resource(NavMeshBaking(task));
+}
+
+/// The navmesh of the previous level must not be used for paths on the next one, paths are only
+/// found again once the new level is baked.
+pub fn remove_navmesh(mut commands: Commands) {
+    commands.remove_resource::<Pathfinding>();
+    commands.remove_resource::<NavMeshBaking>();
+}
+
+pub fn finish_baking(mut baking: ResMut<NavMeshBaking>, mut commands: Commands) {
+    if let Some(navmesh) = block_on(poll_once(&mut baking.0)) {
+        commands.insert_resource(Pathfinding::new(navmesh));
+        commands.remove_resource::<NavMeshBaking>();
+    }
+}
+
+pub fn start_path_queries(
+    q_request: Query<(Entity, &PathRequest, &GlobalTransform), Changed<PathRequest>>,
+    pathfinding: Res<Pathfinding>,
+    mut commands: Commands,
+) {
+    for (entity, request, gtransform) in q_request.iter() {
+        let task = pathfinding.find_path_async(gtransform.translation(), request.destination);
+
+        commands
+            .entity(entity)
+            .remove::<(PathRequest, NavPath, PathNotFound)>()
+            .insert(PathQuery(task));
+    }
+}
+
+pub fn poll_path_queries(mut q_query: Query<(Entity, &mut PathQuery)>, mut commands: Commands) {
+    for (entity, mut query) in q_query.iter_mut() {
+        let Some(result) = block_on(poll_once(&mut query.0)) else {
+            continue;
+        };
+
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.remove::<PathQuery>();
+
+        match result {
+            Some(waypoints) => {
+                entity_commands.insert(NavPath { waypoints });
+            }
+            None => {
+                entity_commands.insert(PathNotFound);
+            }
+        }
+    }
+}
+
+fn load_or_bake(triangles: &[[Vec3; 3]], settings: &NavMeshSettings) -> NavMesh {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        use std::{fs, io::BufReader, io::BufWriter, path::Path};
+
+        let key = NavMesh::cache_key(triangles, settings);
+        let path = Path::new(NAVMESH_CACHE_DIR).join(format!("{:016x}.navmesh", key));
+
+        if let Ok(file) = fs::File::open(&path) {
+            match NavMesh::read(&mut BufReader::new(file)) {
+                Ok(navmesh) => return navmesh,
+                Err(err) => warn!("Failed to read cached navmesh, rebaking. Reason: {:#}", err),
+            }
+        }
+
+        let navmesh = NavMesh::bake(triangles, settings);
+
+        let written = fs::create_dir_all(NAVMESH_CACHE_DIR)
+            .and_then(|_| fs::File::create(&path))
+            .and_then(|file| navmesh.write(&mut BufWriter::new(file)));
+
+        if let Err(err) = written {
+            warn!("Failed to cache navmesh. Reason: {:#}", err);
+        }
+
+        return navmesh;
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    return NavMesh::bake(triangles, settings);
+}
+
+/// Appends the world space triangles of a mesh.
+fn append_triangles(mesh: &Mesh, gtransform: &GlobalTransform, triangles: &mut Vec<[Vec3; 3]>) {
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return;
+    };
+
+    let vertices: Vec<Vec3> = positions
+        .iter()
+        .map(|position| gtransform.transform_point(Vec3::from(*position)))
+        .collect();
+
+    let indices: Vec<usize> = match mesh.indices() {
+        Some(Indices::U16(indices)) => indices.iter().map(|i| *i as usize).collect(),
+        Some(Indices::U32(indices)) => indices.iter().map(|i| *i as usize).collect(),
+        None => (0..vertices.len()).collect(),
+    };
+
+    for triangle in indices.chunks_exact(3) {
+        triangles.push([
+            vertices[triangle[0]],
+            vertices[triangle[1]],
+            vertices[triangle[2]],
+        ]);
+    }
+}
@@ -0,0 +1,24 @@
+use bevy::prelude::*;
+
+/// Distance at which an npc counts as arrived at its destination.
+pub const ARRIVE_DISTANCE: f32 = 1.5;
+
+/// Distance between two points ignoring height.
+pub fn horizontal_distance(a: Vec3, b: Vec3) -> f32 {
+    return a.xz().distance(b.xz());
+}
+
+/// Moves an npc towards a target on the horizontal plane, facing the direction it walks in.
+pub fn walk_towards(transform: &mut Transform, target: Vec3, speed: f32, delta_time: f32) {
+    let mut offset = target - transform.translation;
+    offset.y = 0.0;
+
+    let distance = offset.length();
+    if distance < 0.01 {
+        return;
+    }
+
+    let step = (speed * delta_time).min(distance);
+    transform.translation += offset / distance * step;
+    transform.look_to(offset, Vec3::Y);
+}
@@ -0,0 +1,135 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{simulation_state::SimulationState, GameState};
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        perceive
+            .run_if(in_state(GameState::Playing))
+            .run_if(in_state(SimulationState::Running)),
+    );
+}
+
+/// The senses of an npc.
+///
+/// [`Perceivable`] entities are seen when they are inside of the view cone and nothing blocks
+/// the line of sight, [`Noise`]s are heard when the npc is inside of their radius.
+#[derive(Component)]
+pub struct Perception {
+    pub view_distance: f32,
+    /// Half of the opening angle of the view cone in radians.
+    pub view_angle: f32,
+    /// Multiplier applied to the radius of every heard [`Noise`].
+    pub hearing: f32,
+    /// Height of the eyes above the origin of the entity.
+    pub eye_height: f32,
+}
+
+impl Default for Perception {
+    fn default() -> Self {
+        Self {
+            view_distance: 20.0,
+            view_angle: 50f32.to_radians(),
+            hearing: 1.0,
+            eye_height: 0.7,
+        }
+    }
+}
+
+/// Marks entities that can be seen by a [`Perception`].
+#[derive(Component)]
+pub struct Perceivable;
+
+/// Marks an entity that is currently making noise.
+#[derive(Component)]
+pub struct Noise {
+    pub radius: f32,
+}
+
+/// The entities a [`Perception`] noticed during the last update.
+#[derive(Component, Default)]
+pub struct Perceived {
+    pub seen: Vec<Entity>,
+    pub heard: Vec<Entity>,
+}
+
+impl Perceived {
+    pub fn noticed(&self, entity: Entity) -> bool {
+        return self.seen.contains(&entity) || self.heard.contains(&entity);
+    }
+}
+
+fn perceive(
+    mut q_perception: Query<(
+        Entity,
+        &Perception,
+        &mut Perceived,
+        &GlobalTransform,
+        &InheritedVisibility,
+    )>,
+    q_perceivable: Query<(Entity, &GlobalTransform), With<Perceivable>>,
+    q_noise: Query<(Entity, &Noise, &GlobalTransform)>,
+    q_parent: Query<&Parent>,
+    spatial_query: SpatialQuery,
+) {
+    for (entity, perception, mut perceived, gtransform, visibility) in q_perception.iter_mut() {
+        perceived.seen.clear();
+        perceived.heard.clear();
+
+        // npcs that are not in the world (e.g. hidden) don't perceive anything
+        if !visibility.get() {
+            continue;
+        }
+
+        let eye = gtransform.translation() + Vec3::Y * perception.eye_height;
+        let forward = gtransform.forward();
+
+        for (target_entity, target_gtransform) in q_perceivable.iter() {
+            let to_target = target_gtransform.translation() - eye;
+            let distance = to_target.length();
+
+            if distance > perception.view_distance {
+                continue;
+            }
+
+            let Ok(direction) = Dir3::new(to_target) else {
+                continue;
+            };
+
+            if forward.angle_between(*direction) > perception.view_angle {
+                continue;
+            }
+
+            // the target is seen if the first thing the ray hits is the target itself
+            let line_of_sight = match spatial_query.cast_ray(
+                eye,
+                direction,
+                distance,
+                true,
+                SpatialQueryFilter::default().with_excluded_entities([entity]),
+            ) {
+                Some(hit) => {
+                    hit.entity == target_entity
+                        || q_parent
+                            .get(hit.entity)
+                            .is_ok_and(|parent| parent.get() == target_entity)
+                }
+                None => true,
+            };
+
+            if line_of_sight {
+                perceived.seen.push(target_entity);
+            }
+        }
+
+        for (noise_entity, noise, noise_gtransform) in q_noise.iter() {
+            let distance = noise_gtransform.translation().distance(eye);
+
+            if distance <= noise.radius * perception.hearing {
+                perceived.heard.push(noise_entity);
+            }
+        }
+    }
+}
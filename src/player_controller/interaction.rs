@@ -18,6 +18,7 @@ fn interact(
     keys: Res<ButtonInput<KeyCode>>,
     query: SpatialQuery,
     q_camera: Query<&Transform, With<Camera>>,
+    q_player: Query<Entity, With<Player>>,
     q_parent: Query<Option<&Parent>>,
     q_entities: Query<
         (
@@ -62,7 +63,12 @@ fn interact(
         }
 
         if locked.is_some() {
-            lock_pick_ew.send(LockPickEvent::Pick(entity));
+            if let Ok(player_entity) = q_player.get_single() {
+                lock_pick_ew.send(LockPickEvent::Pick {
+                    picker: player_entity,
+                    lock: entity,
+                });
+            }
         }
 
         if mount.is_some() {
@@ -2,53 +2,133 @@ use avian3d::dynamics::rigid_body::RigidBody;
 use bevy::prelude::*;
 
 use crate::{
+    inventory::Inventory,
     lockpicking::*,
-    player_controller::{CharacterController, Player},
+    perception::Noise,
+    player_controller::CharacterController,
 };
 
 use super::interact;
 
+/// Starts and ends the lockpicking session of a [`LockPicker`], sending
+/// [`LockPickEvent::StopPick`] cancels the current session of the picker.
 #[derive(Event)]
 pub enum LockPickEvent {
-    Pick(Entity),
-    StopPick,
+    Pick { picker: Entity, lock: Entity },
+    StopPick { picker: Entity, reason: StopPickReason },
+}
+
+/// Why a lockpicking session ended.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StopPickReason {
+    Unlocked,
+    /// There were too many failed picks.
+    Broken,
+    TimeUp,
+    /// An npc saw or heard the player.
+    Noticed,
+    /// Something bumped into the bike.
+    Knocked,
+    /// Something bumped into the player.
+    Hit,
+    /// The owner of the bike caught the player.
+    Caught,
+    Cancelled,
+}
+
+impl StopPickReason {
+    /// Message shown to the player, `None` if the session ended the regular way.
+    pub fn message(&self) -> Option<&'static str> {
+        return match self {
+            StopPickReason::Unlocked | StopPickReason::Broken | StopPickReason::Cancelled => None,
+            StopPickReason::TimeUp => Some("(You took too long, the lock won't budge.)"),
+            StopPickReason::Noticed => Some("(Someone noticed you!)"),
+            StopPickReason::Knocked => Some("(The bike got knocked, you lost your grip.)"),
+            StopPickReason::Hit => Some("(You got hit and dropped your tools.)"),
+            StopPickReason::Caught => Some("(The owner caught you!)"),
+        };
+    }
+}
+
+/// Sent when a lock can't be picked because the picker doesn't have the needed tool.
+#[derive(Event)]
+pub struct MissingToolEvent {
+    pub picker: Entity,
+    pub tool: Tool,
 }
 
 pub fn plugin(app: &mut App) {
     app.add_event::<LockPickEvent>()
+        .add_event::<MissingToolEvent>()
         .add_systems(Update, lockpick.after(interact));
 }
 
 fn lockpick(
     mut lock_pick_er: EventReader<LockPickEvent>,
-    mut q_player: Query<(Entity, &LockPicker, &mut CharacterController), With<Player>>,
-    mut q_lock: Query<&mut RigidBody>,
+    mut q_picker: Query<(
+        &mut LockPicker,
+        Option<&Inventory>,
+        Option<&mut CharacterController>,
+    )>,
+    mut q_lock: Query<(&mut RigidBody, &Locked)>,
+    mut q_target: Query<&mut LockPickTarget>,
+    mut missing_tool_ew: EventWriter<MissingToolEvent>,
     mut commands: Commands,
 ) {
     for ev in lock_pick_er.read() {
         match ev {
-            LockPickEvent::Pick(entity) => {
-                let (picker_entity, picker, mut character_controller) = q_player.get_single_mut().unwrap();
-        
+            LockPickEvent::Pick { picker: picker_entity, lock } => {
+                let Ok((mut picker, inventory, character_controller)) = q_picker.get_mut(*picker_entity) else {
+                    continue;
+                };
+
                 // dont overwrite lock picking if its already in progress!
                 if picker.target.is_some() {
-                    return;
+                    continue;
+                }
+
+                // somebody else is already picking this lock
+                if q_target.contains(*lock) {
+                    continue;
                 }
-        
-                character_controller.locked = true;
-        
-                let mut rigidbody = q_lock.get_mut(*entity).unwrap();
+
+                let Ok((mut rigidbody, locked)) = q_lock.get_mut(*lock) else {
+                    continue;
+                };
+
+                if let Some(tool) = locked.lock_type.required_tool() {
+                    if !inventory.is_some_and(|inventory| inventory.has(tool.item())) {
+                        missing_tool_ew.send(MissingToolEvent { picker: *picker_entity, tool });
+                        continue;
+                    }
+                }
+
+                if let Some(mut character_controller) = character_controller {
+                    character_controller.locked = true;
+                }
+                picker.target = Some(*lock);
+
+                // picking a lock is not exactly quiet
+                commands.entity(*picker_entity).insert(Noise { radius: 8.0 });
+
                 *rigidbody = RigidBody::Static;
-        
-                commands.entity(*entity).insert(LockPickTarget {
-                    picker: picker_entity,
-                    successful_pick_counter: 0,
-                    failed_pick_counter: 0,
-                    successful_picks_before_unlock: 3,
-                    failed_picks_before_break: 1,
-                });        
+
+                commands
+                    .entity(*lock)
+                    .insert(LockPickTarget::new(*picker_entity, locked));
+            },
+            LockPickEvent::StopPick { picker, reason } => {
+                let Some(target) = q_picker.get(*picker).ok().and_then(|(picker, _, _)| picker.target) else {
+                    continue;
+                };
+
+                let Ok(mut lock_pick_target) = q_target.get_mut(target) else {
+                    continue;
+                };
+
+                lock_pick_target.interrupted = Some(*reason);
+                commands.entity(target).remove::<LockPickTarget>();
             },
-            _ => { },
         }
     }
 }
@@ -1,36 +1,83 @@
-use avian3d::{
-    collision::{Collider, Sensor},
-    dynamics::rigid_body::{ExternalForce, RigidBody},
-};
-use bevy::{ecs::system::QueryLens, prelude::*};
+use avian3d::prelude::*;
+use bevy::prelude::*;
 
 use super::*;
-use crate::player_controller::{PickUpUIPlugin, Player};
+use crate::{
+    damping::{PidConfig, TransformPid},
+    inventory::{Inventory, Item, ItemStack},
+    levels::reset_resource,
+    player_controller::{CollisionMask, PickUpUIPlugin, Player},
+    weather::Weather,
+    GameState,
+};
 pub fn plugin(app: &mut App) {
     app.register_type::<HandConfig>()
         .init_resource::<HandConfig>()
         .init_resource::<Hand>()
+        .init_resource::<ThrowCharge>()
         .add_event::<PickUpEvent>()
         .add_plugins(PickUpUIPlugin)
-        .add_systems(Update, ((pick_up).after(interact), drop, throw))
-        .add_systems(PostUpdate, move_visual);
+        .add_systems(
+            OnEnter(GameState::LoadingLevel),
+            (reset_resource::<Hand>, reset_resource::<ThrowCharge>),
+        )
+        .add_systems(
+            Update,
+            (
+                (pick_up).after(interact),
+                drop,
+                throw,
+                hold.after(pick_up),
+                draw_throw_preview.after(throw),
+                land_thrown,
+            ),
+        );
 }
 
-#[derive(Component)]
-pub struct UpPickable;
+/// Things the player can pick up, and where they go when they are picked up.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum UpPickable {
+    /// Held in the [`Hand`], e.g. bicycles and trash.
+    Hand,
+    /// Small items that go into the [`Inventory`] of the player.
+    Inventory(ItemStack),
+}
+
+impl UpPickable {
+    pub fn item(item: Item, amount: u32) -> Self {
+        return UpPickable::Inventory(ItemStack::new(item, amount));
+    }
+}
 
 #[derive(Resource, Reflect)]
 #[reflect(Resource)]
 pub struct HandConfig {
     offset: Vec3,
-    throw_force: f32,
+    /// Impulse of a throw that isn't charged.
+    min_throw_impulse: f32,
+    /// Impulse of a fully charged throw.
+    max_throw_impulse: f32,
+    /// Seconds it takes to fully charge a throw.
+    throw_charge_time: f32,
+    /// Smooth time of a held object per unit of mass, heavier objects lag behind more.
+    smooth_time_per_mass: f32,
+    /// Held objects that are further away from the hand than this are dropped e.g. when they get
+    /// stuck behind a wall.
+    max_distance: f32,
+    /// Fastest a held object moves towards the hand.
+    max_hold_speed: f32,
 }
 
 impl Default for HandConfig {
     fn default() -> Self {
         Self {
             offset: Vec3::new(0.0, 0.0, -2.0),
-            throw_force: 3000.0,
+            min_throw_impulse: 20.0,
+            max_throw_impulse: 80.0,
+            throw_charge_time: 1.0,
+            smooth_time_per_mass: 0.02,
+            max_distance: 3.0,
+            max_hold_speed: 20.0,
         }
     }
 }
@@ -39,8 +86,6 @@ impl Default for HandConfig {
 pub enum Hand {
     Some {
         entity: Entity,
-        collider: Entity,
-        visual: Entity,
     },
     #[default]
     Empty,
@@ -55,148 +100,156 @@ impl Hand {
 #[derive(Event)]
 pub struct PickUpEvent(pub Entity);
 
+/// An object that was thrown and is still flying, the wind pushes it around.
+#[derive(Component)]
+pub struct Thrown;
+
 fn pick_up(
     mut pick_up_er: EventReader<PickUpEvent>,
     mut hand: ResMut<Hand>,
-    mut q_object: Query<(&mut Visibility, &mut RigidBody, &Children)>,
-    q_child: Query<(
-        &Transform,
-        Option<&Handle<Mesh>>,
-        Option<&Handle<StandardMaterial>>,
-        Option<&Collider>,
-    )>,
-    q_player: Query<(Entity, &GlobalTransform), With<Player>>,
-    q_camera: Query<&GlobalTransform, (With<Camera>, Without<Player>)>,
+    q_up_pickable: Query<&UpPickable>,
+    q_object: Query<(Option<&Mass>, &Children), With<RigidBody>>,
+    q_collider: Query<(), With<Collider>>,
+    mut q_inventory: Query<&mut Inventory, With<Player>>,
     config: Res<HandConfig>,
     mut commands: Commands,
 ) {
-    if !hand.is_empty() {
-        return;
-    }
-
     for ev in pick_up_er.read() {
         let entity = ev.0;
-        let (mut visibility, mut rigidbody, children) = q_object.get_mut(entity).unwrap();
 
-        *visibility = Visibility::Hidden;
-        *rigidbody = RigidBody::Static;
+        let Ok(up_pickable) = q_up_pickable.get(entity) else {
+            continue;
+        };
 
-        let (player_entity, player_gtranform) = q_player.get_single().unwrap();
-        let camera_gtransform = q_camera.get_single().unwrap();
+        if let UpPickable::Inventory(stack) = *up_pickable {
+            let Ok(mut inventory) = q_inventory.get_single_mut() else {
+                continue;
+            };
 
-        let height_offset = Vec3::new(
-            0.0,
-            camera_gtransform.translation().y - player_gtranform.translation().y,
-            0.0,
-        );
+            let left = inventory.add(stack.item, stack.amount);
 
-        let visual_parent = commands.spawn(SpatialBundle::default()).id();
-
-        let collider_parent = commands
-            .spawn(TransformBundle {
-                local: Transform::from_translation(height_offset + config.offset),
-                ..default()
-            })
-            .id();
-
-        for child_entity in children.iter() {
-            let (transform, mesh, material, collider) = q_child.get(*child_entity).unwrap();
-
-            if let Some(collider) = collider {
-                commands.entity(collider_parent).with_children(|parent| {
-                    parent.spawn((
-                        TransformBundle {
-                            local: transform.clone(),
-                            ..default()
-                        },
-                        collider.clone(),
-                    ));
-                });
+            if left == 0 {
+                commands.entity(entity).despawn_recursive();
+            } else {
+                // the rest stays where it is until there is room for it
+                commands
+                    .entity(entity)
+                    .insert(UpPickable::item(stack.item, left));
             }
 
-            if let Some(mesh) = mesh {
-                if let Some(material) = material {
-                    commands.entity(visual_parent).with_children(|parent| {
-                        parent.spawn(PbrBundle {
-                            mesh: mesh.clone(),
-                            material: material.clone(),
-                            ..default()
-                        });
-                    });
-                }
-            }
-
-            commands.entity(*child_entity).insert(Sensor);
+            continue;
         }
 
-        commands.entity(player_entity).add_child(collider_parent);
+        if !hand.is_empty() {
+            continue;
+        }
 
-        *hand = Hand::Some {
-            entity,
-            collider: collider_parent,
-            visual: visual_parent,
+        let Ok((mass, children)) = q_object.get(entity) else {
+            continue;
         };
 
-        return;
+        let mass = mass.map_or(1.0, |mass| mass.0);
+
+        // the object stays a regular dynamic body, it is pulled towards the hand by `hold`
+        commands
+            .entity(entity)
+            .insert((hold_pid(&config, mass), GravityScale(0.0)))
+            .remove::<Thrown>();
+
+        for child_entity in children.iter().filter(|child| q_collider.contains(**child)) {
+            commands
+                .entity(*child_entity)
+                .insert(CollisionLayers::new(CollisionMask::Held, LayerMask::ALL));
+        }
+
+        *hand = Hand::Some { entity };
     }
 }
 
-fn move_visual(
-    mut q_visual: Query<&mut Transform, Without<Camera>>,
-    q_camera: Query<&Transform, With<Camera>>,
-    hand: Res<Hand>,
-    config: Res<HandConfig>,
-) {
-    if let Hand::Some { visual, .. } = *hand {
-        let mut transform = q_visual.get_mut(visual).unwrap();
-        let camera_transform = q_camera.get_single().unwrap();
+/// Heavier objects lag behind and turn slower.
+fn hold_pid(config: &HandConfig, mass: f32) -> TransformPid {
+    let kp = 1.0 / (config.smooth_time_per_mass * mass).max(0.001);
 
-        let rotation = Quat::from_rotation_y(camera_transform.rotation.to_euler(EulerRot::YXZ).0);
-        let rotated_offset = rotation.mul_vec3(config.offset);
+    return TransformPid::new(
+        PidConfig::new(kp, 0.0, 0.0).with_output_limit(config.max_hold_speed),
+        PidConfig::new(kp, 0.0, 0.0),
+    );
+}
 
-        transform.translation = camera_transform.translation + rotated_offset;
-        transform.rotation = rotation;
-    }
+/// Where the held object should be, in front of the camera at the height of the camera.
+fn hand_transform(camera_transform: &Transform, config: &HandConfig) -> Transform {
+    let rotation = Quat::from_rotation_y(camera_transform.rotation.to_euler(EulerRot::YXZ).0);
+    let rotated_offset = rotation.mul_vec3(config.offset);
+
+    return Transform::from_translation(camera_transform.translation + rotated_offset)
+        .with_rotation(rotation);
 }
 
-fn release(
+/// Moves the held object towards the hand by setting its velocity, so walls and other bodies
+/// still block it.
+fn hold(
     mut hand: ResMut<Hand>,
-    mut ql_object: QueryLens<(
-        &mut Transform,
-        Option<&mut RigidBody>,
-        &mut Visibility,
-        &Children,
+    mut q_object: Query<(
+        &Transform,
+        &mut TransformPid,
+        &mut LinearVelocity,
+        &mut AngularVelocity,
     )>,
+    q_camera: Query<&Transform, (With<Camera>, Without<TransformPid>)>,
+    q_children: Query<&Children>,
+    config: Res<HandConfig>,
+    time: Res<Time>,
     mut commands: Commands,
-) -> Option<Entity> {
-    let Hand::Some {
-        entity,
-        collider,
-        visual,
-    } = *hand
+) {
+    let Hand::Some { entity } = *hand else {
+        return;
+    };
+
+    // the held object can stop existing e.g. when it's loaded onto the car
+    if commands.get_entity(entity).is_none() {
+        *hand = Hand::Empty;
+        return;
+    }
+
+    let delta_time = time.delta_seconds();
+
+    let (Ok((transform, mut pid, mut linear_velocity, mut angular_velocity)), Ok(camera_transform)) =
+        (q_object.get_mut(entity), q_camera.get_single())
     else {
-        return None;
+        return;
     };
 
-    let mut q_object = ql_object.query();
+    if delta_time == 0.0 {
+        return;
+    }
 
-    let (visual_transform, _, _, _) = q_object.get(visual).unwrap();
-    let visual_transform = visual_transform.clone();
+    let target = hand_transform(camera_transform, &config);
 
-    let (mut transform, rigidbody, mut visibility, children) = q_object.get_mut(entity).unwrap();
-    let mut rigidbody = rigidbody.unwrap();
+    if transform.translation.distance(target.translation) > config.max_distance {
+        release(&mut hand, &q_children, &mut commands);
+        return;
+    }
 
-    transform.translation = visual_transform.translation;
-    transform.rotation = visual_transform.rotation;
+    // also turns towards the view direction
+    (linear_velocity.0, angular_velocity.0) = pid.update(transform, &target, delta_time);
+}
 
-    commands.entity(collider).despawn_recursive();
-    commands.entity(visual).despawn_recursive();
+/// Lets go of the held object, returns the entity that was held.
+fn release(
+    hand: &mut Hand,
+    q_children: &Query<&Children>,
+    commands: &mut Commands,
+) -> Option<Entity> {
+    let Hand::Some { entity } = *hand else {
+        return None;
+    };
 
-    *visibility = Visibility::Inherited;
-    *rigidbody = RigidBody::Dynamic;
+    commands
+        .entity(entity)
+        .remove::<(TransformPid, GravityScale)>();
 
-    for child_entity in children {
-        commands.entity(*child_entity).remove::<Sensor>();
+    for child_entity in q_children.get(entity).into_iter().flatten() {
+        commands.entity(*child_entity).remove::<CollisionLayers>();
     }
 
     *hand = Hand::Empty;
@@ -206,55 +259,154 @@ fn release(
 
 fn drop(
     buttons: Res<ButtonInput<MouseButton>>,
-    hand: ResMut<Hand>,
-    mut q_object: Query<(
-        &mut Transform,
-        Option<&mut RigidBody>,
-        &mut Visibility,
-        &Children,
-    )>,
-    commands: Commands,
+    mut hand: ResMut<Hand>,
+    q_children: Query<&Children>,
+    mut commands: Commands,
 ) {
     if buttons.just_pressed(MouseButton::Right) {
-        _ = release(hand, q_object.as_query_lens(), commands);
+        _ = release(&mut hand, &q_children, &mut commands);
     }
 }
 
+/// Time the throw button has been held down for, `None` if it isn't.
+#[derive(Resource, Default)]
+pub struct ThrowCharge(Option<f32>);
+
+impl ThrowCharge {
+    /// How much the throw is charged, between 0 and 1.
+    pub fn fraction(&self, config: &HandConfig) -> Option<f32> {
+        return self
+            .0
+            .map(|held| (held / config.throw_charge_time.max(f32::EPSILON)).min(1.0));
+    }
+}
+
+/// Speed the held object is thrown with, heavier objects fly slower.
+fn throw_speed(charge: f32, mass: f32, config: &HandConfig) -> f32 {
+    let impulse = config
+        .min_throw_impulse
+        .lerp(config.max_throw_impulse, charge);
+    return impulse / mass.max(f32::EPSILON);
+}
+
 fn throw(
     buttons: Res<ButtonInput<MouseButton>>,
     q_camera: Query<&Transform, With<Camera>>,
     config: Res<HandConfig>,
-    hand: ResMut<Hand>,
-    mut q_object: Query<
-        (
-            &mut Transform,
-            Option<&mut RigidBody>,
-            &mut Visibility,
-            &Children,
-            Option<&mut ExternalForce>,
-        ),
-        Without<Camera>,
-    >,
-    commands: Commands,
+    mut hand: ResMut<Hand>,
+    mut charge: ResMut<ThrowCharge>,
+    mut q_object: Query<(&mut LinearVelocity, Option<&Mass>)>,
+    q_children: Query<&Children>,
+    time: Res<Time>,
+    mut commands: Commands,
 ) {
+    if hand.is_empty() {
+        charge.0 = None;
+        return;
+    }
+
     if buttons.just_pressed(MouseButton::Left) {
-        let released = release(
-            hand,
-            q_object.transmute_lens::<(
-                &mut Transform,
-                Option<&mut RigidBody>,
-                &mut Visibility,
-                &Children,
-            )>(),
-            commands,
-        );
+        charge.0 = Some(0.0);
+    }
+
+    if let Some(held) = charge.0.as_mut() {
+        *held += time.delta_seconds();
+    }
+
+    if !buttons.just_released(MouseButton::Left) {
+        return;
+    }
+
+    let Some(fraction) = charge.fraction(&config) else {
+        return;
+    };
+
+    charge.0 = None;
+
+    let released = release(&mut hand, &q_children, &mut commands);
+
+    if let Some(entity) = released {
+        let (mut linear_velocity, mass) = q_object.get_mut(entity).unwrap();
+        let camera_transform = q_camera.get_single().unwrap();
+
+        let mass = mass.map_or(1.0, |mass| mass.0);
+        linear_velocity.0 = camera_transform.forward() * throw_speed(fraction, mass, &config);
+
+        commands.entity(entity).insert(Thrown);
+    }
+}
 
-        if let Some(entity) = released {
-            let mut external_force = q_object.get_mut(entity).unwrap().4.unwrap();
-            let camera_transform = q_camera.get_single().unwrap();
+/// Speed below which a thrown object counts as landed.
+const LANDED_SPEED: f32 = 0.5;
 
-            external_force.set_force(camera_transform.forward() * config.throw_force);
-            external_force.persistent = false;
+fn land_thrown(q_thrown: Query<(Entity, &LinearVelocity), With<Thrown>>, mut commands: Commands) {
+    for (entity, linear_velocity) in q_thrown.iter() {
+        if linear_velocity.length() < LANDED_SPEED {
+            commands.entity(entity).remove::<Thrown>();
         }
     }
 }
+
+/// How far into the future the throw preview goes in seconds.
+const THROW_PREVIEW_TIME: f32 = 3.0;
+/// Time between two points of the throw preview in seconds.
+const THROW_PREVIEW_STEP: f32 = 0.05;
+
+/// Draws the arc the held object would fly along if it was thrown now.
+fn draw_throw_preview(
+    hand: Res<Hand>,
+    charge: Res<ThrowCharge>,
+    config: Res<HandConfig>,
+    gravity: Res<Gravity>,
+    weather: Option<Res<Weather>>,
+    q_object: Query<(&Transform, Option<&Mass>, &Children)>,
+    q_camera: Query<&Transform, With<Camera>>,
+    q_player: Query<Entity, With<Player>>,
+    spatial_query: SpatialQuery,
+    mut gizmos: Gizmos,
+) {
+    let (&Hand::Some { entity }, Some(fraction)) = (hand.as_ref(), charge.fraction(&config)) else {
+        return;
+    };
+
+    let (Ok((transform, mass, children)), Ok(camera_transform)) =
+        (q_object.get(entity), q_camera.get_single())
+    else {
+        return;
+    };
+
+    let mass = mass.map_or(1.0, |mass| mass.0);
+    let velocity = camera_transform.forward() * throw_speed(fraction, mass, &config);
+    let acceleration = gravity.0 + weather.map_or(Vec3::ZERO, |weather| weather.wind());
+
+    let filter = SpatialQueryFilter::default()
+        .with_excluded_entities(children.iter().copied().chain(q_player.iter()));
+
+    let color = Color::srgb(1.0, 1.0 - fraction, 0.2);
+    let mut previous = transform.translation;
+    let mut time = 0.0;
+
+    while time < THROW_PREVIEW_TIME {
+        time += THROW_PREVIEW_STEP;
+        let next = transform.translation + velocity * time + 0.5 * acceleration * time * time;
+
+        // stop the arc where it hits something
+        if let Ok(direction) = Dir3::new(next - previous) {
+            if let Some(hit) = spatial_query.cast_ray(
+                previous,
+                direction,
+                previous.distance(next),
+                true,
+                filter.clone(),
+            ) {
+                let hit_point = previous + *direction * hit.time_of_impact;
+                gizmos.line(previous, hit_point, color);
+                gizmos.sphere(hit_point, Quat::IDENTITY, 0.2, color);
+                return;
+            }
+        }
+
+        gizmos.line(previous, next, color);
+        previous = next;
+    }
+}
@@ -32,7 +32,7 @@ pub fn setup_ui(mut commands: Commands) {
                 TextBundle {
                     style: Style::default(),
                     text: Text::from_section(
-                        "(Use [MouseLeftClick] to throw, and [MouseRightClick] to drop.)",
+                        "(Hold [MouseLeftClick] to charge a throw, and [MouseRightClick] to drop.)",
                         middle_of_screen_info_text_style(),
                     ),
                     visibility: Visibility::Hidden,
@@ -98,6 +98,8 @@ impl Default for MovementBundle {
 pub enum CollisionMask {
     Player,
     Car,
+    /// Objects the player is holding, they don't collide with the player.
+    Held,
 }
 
 impl CharacterControllerBundle {
@@ -0,0 +1,16 @@
+use bevy::prelude::*;
+
+/// A police officer patrolling the streets.
+#[derive(Component)]
+pub struct Police;
+
+/// The state machine driving a [`Police`] officer.
+#[derive(Component)]
+pub enum PoliceBehaviour {
+    /// Walking to a random point near the player.
+    Patrolling { destination: Vec3 },
+    /// Running after a suspect, gives up when the timer finishes.
+    Pursuing { target: Entity, timer: Timer },
+    /// Caught the suspect, the shift is over.
+    Arresting,
+}
@@ -0,0 +1,9 @@
+mod components;
+mod plugin;
+mod resources;
+mod systems;
+mod ui;
+
+pub use components::*;
+pub use plugin::PolicePlugin;
+pub use resources::*;
@@ -0,0 +1,37 @@
+use bevy::prelude::*;
+
+use crate::{levels::reset_resource, simulation_state::SimulationState, GameState};
+
+use super::resources::*;
+use super::systems::*;
+use super::ui::*;
+
+pub struct PolicePlugin;
+
+impl Plugin for PolicePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<HeatAction>()
+            .init_resource::<Heat>()
+            .init_resource::<HeatSettings>()
+            .add_plugins(HeatUIPlugin)
+            .add_systems(OnEnter(GameState::LoadingLevel), reset_resource::<Heat>)
+            .add_systems(
+                Update,
+                (
+                    (
+                        raise_heat_when_witnessed,
+                        raise_heat_when_loading,
+                        raise_heat_when_speeding,
+                        decay_heat,
+                    ),
+                    handle_heat_actions,
+                    dispatch_police,
+                    update_police,
+                    move_police,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(in_state(SimulationState::Running)),
+            );
+    }
+}
@@ -0,0 +1,86 @@
+use bevy::prelude::*;
+
+/// How much attention the player has drawn, decays over time.
+#[derive(Resource, Default)]
+pub struct Heat(f32);
+
+impl Heat {
+    pub fn get(&self) -> f32 {
+        return self.0;
+    }
+
+    /// The wanted level, one star for every `heat_per_star` heat.
+    pub fn stars(&self, settings: &HeatSettings) -> u32 {
+        return (self.0 / settings.heat_per_star).floor() as u32;
+    }
+
+    pub(crate) fn increase(&mut self, amount: f32, max: f32) -> &Self {
+        self.0 = (self.0 + amount).min(max);
+        return self;
+    }
+
+    pub(crate) fn decrease(&mut self, amount: f32) -> &Self {
+        self.0 = (self.0 - amount).max(0.0);
+        return self;
+    }
+
+    pub(crate) fn reset(&mut self) -> &Self {
+        self.0 = 0.0;
+        return self;
+    }
+}
+
+#[derive(Resource)]
+pub struct HeatSettings {
+    pub max_heat: f32,
+    pub heat_per_star: f32,
+    /// Heat lost per second.
+    pub decay_rate: f32,
+    /// Heat gained per second while seen seizing a legal bicycle.
+    pub witnessed_seizing_rate: f32,
+    /// Heat gained when seen loading a legal bicycle onto the car.
+    pub witnessed_loading_heat: f32,
+    /// Driving faster than this counts as reckless.
+    pub reckless_speed: f32,
+    /// Heat gained per second while driving recklessly.
+    pub reckless_driving_rate: f32,
+    pub officers_per_star: u32,
+    /// Distance from the player at which officers show up.
+    pub spawn_distance: f32,
+    pub patrol_radius: f32,
+    pub patrol_speed: f32,
+    pub pursuit_speed: f32,
+    pub pursuit_time: f32,
+    pub catch_distance: f32,
+    /// Points lost when getting arrested.
+    pub fine: u32,
+}
+
+impl Default for HeatSettings {
+    fn default() -> Self {
+        Self {
+            max_heat: 100.0,
+            heat_per_star: 25.0,
+            decay_rate: 1.0,
+            witnessed_seizing_rate: 10.0,
+            witnessed_loading_heat: 20.0,
+            reckless_speed: 25.0,
+            reckless_driving_rate: 4.0,
+            officers_per_star: 1,
+            spawn_distance: 40.0,
+            patrol_radius: 30.0,
+            patrol_speed: 2.5,
+            pursuit_speed: 6.5,
+            pursuit_time: 8.0,
+            catch_distance: 1.5,
+            fine: 5,
+        }
+    }
+}
+
+#[derive(Event)]
+pub enum HeatAction {
+    Reset,
+    Increase(f32),
+    Decrease(f32),
+}
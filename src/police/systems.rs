@@ -0,0 +1,289 @@
+use std::f32::consts::TAU;
+
+use avian3d::prelude::LinearVelocity;
+use bevy::prelude::*;
+use rand::{thread_rng, Rng};
+
+use crate::{
+    car_controller::{BicycleLoadedEvent, CarController},
+    lockpicking::LockPicker,
+    npc::{horizontal_distance, walk_towards, ARRIVE_DISTANCE},
+    perception::{Perceived, Perception},
+    player_car_swap::Rider,
+    player_controller::{pick_up::Hand, Player},
+    points::PointsAction,
+    shift::{EndShiftEvent, ShiftEndReason},
+    world_spawning::on_spawn::{Bicycle, Illegal},
+};
+
+use super::components::*;
+use super::resources::*;
+
+fn player_witnessed(player_entity: Entity, q_perceived: &Query<&Perceived>) -> bool {
+    return q_perceived
+        .iter()
+        .any(|perceived| perceived.seen.contains(&player_entity));
+}
+
+pub fn raise_heat_when_witnessed(
+    time: Res<Time>,
+    settings: Res<HeatSettings>,
+    hand: Res<Hand>,
+    q_perceived: Query<&Perceived>,
+    q_player: Query<(Entity, &LockPicker), With<Player>>,
+    q_legal_bicycle: Query<(), (With<Bicycle>, Without<Illegal>)>,
+    mut heat_action_ew: EventWriter<HeatAction>,
+) {
+    for (player_entity, picker) in q_player.iter() {
+        let lockpicking = picker
+            .target
+            .is_some_and(|target| q_legal_bicycle.contains(target));
+
+        let carrying = match *hand {
+            Hand::Some { entity, .. } => q_legal_bicycle.contains(entity),
+            Hand::Empty => false,
+        };
+
+        if (lockpicking || carrying) && player_witnessed(player_entity, &q_perceived) {
+            heat_action_ew.send(HeatAction::Increase(
+                settings.witnessed_seizing_rate * time.delta_seconds(),
+            ));
+        }
+    }
+}
+
+pub fn raise_heat_when_loading(
+    mut bicycle_loaded_er: EventReader<BicycleLoadedEvent>,
+    settings: Res<HeatSettings>,
+    q_perceived: Query<&Perceived>,
+    q_player: Query<Entity, With<Player>>,
+    mut heat_action_ew: EventWriter<HeatAction>,
+) {
+    for ev in bicycle_loaded_er.read() {
+        if ev.illegal {
+            continue;
+        }
+
+        if q_player
+            .iter()
+            .any(|player_entity| player_witnessed(player_entity, &q_perceived))
+        {
+            heat_action_ew.send(HeatAction::Increase(settings.witnessed_loading_heat));
+        }
+    }
+}
+
+pub fn raise_heat_when_speeding(
+    time: Res<Time>,
+    settings: Res<HeatSettings>,
+    q_rider: Query<&Rider, With<Player>>,
+    q_car: Query<&LinearVelocity, With<CarController>>,
+    mut heat_action_ew: EventWriter<HeatAction>,
+) {
+    for rider in q_rider.iter() {
+        let Some(velocity) = rider.ride.and_then(|ride| q_car.get(ride).ok()) else {
+            continue;
+        };
+
+        if velocity.xz().length() > settings.reckless_speed {
+            heat_action_ew.send(HeatAction::Increase(
+                settings.reckless_driving_rate * time.delta_seconds(),
+            ));
+        }
+    }
+}
+
+pub fn decay_heat(
+    time: Res<Time>,
+    settings: Res<HeatSettings>,
+    mut heat_action_ew: EventWriter<HeatAction>,
+) {
+    heat_action_ew.send(HeatAction::Decrease(
+        settings.decay_rate * time.delta_seconds(),
+    ));
+}
+
+pub fn handle_heat_actions(
+    mut heat_action_er: EventReader<HeatAction>,
+    settings: Res<HeatSettings>,
+    mut heat: ResMut<Heat>,
+) {
+    for ev in heat_action_er.read() {
+        match ev {
+            HeatAction::Reset => {
+                heat.reset();
+            }
+            HeatAction::Increase(amount) => {
+                heat.increase(*amount, settings.max_heat);
+            }
+            HeatAction::Decrease(amount) => {
+                heat.decrease(*amount);
+            }
+        }
+    }
+}
+
+/// Spawns officers until there are as many as the wanted level demands, and sends patrolling
+/// officers home when the heat goes down.
+pub fn dispatch_police(
+    heat: Res<Heat>,
+    settings: Res<HeatSettings>,
+    q_police: Query<(Entity, &PoliceBehaviour, &Transform), With<Police>>,
+    q_player: Query<&GlobalTransform, With<Player>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut assets: Local<Option<(Handle<Mesh>, Handle<StandardMaterial>)>>,
+    mut commands: Commands,
+) {
+    let Ok(player_gtransform) = q_player.get_single() else {
+        return;
+    };
+    let player_position = player_gtransform.translation();
+
+    let wanted = heat.stars(&settings) * settings.officers_per_star;
+    let count = q_police.iter().len() as u32;
+
+    if count < wanted {
+        let (mesh, material) = assets
+            .get_or_insert_with(|| {
+                (
+                    meshes.add(Capsule3d::new(0.3, 1.0)),
+                    materials.add(Color::srgb(0.05, 0.1, 0.35)),
+                )
+            })
+            .clone();
+
+        let position = random_point_around(player_position, settings.spawn_distance);
+
+        commands.spawn((
+            Name::new("Police Officer"),
+            PbrBundle {
+                mesh,
+                material,
+                transform: Transform::from_translation(position),
+                ..default()
+            },
+            Police,
+            PoliceBehaviour::Patrolling {
+                destination: random_point_around(player_position, settings.patrol_radius),
+            },
+            Perception::default(),
+            Perceived::default(),
+        ));
+    } else if count > wanted {
+        // only officers that are not after somebody go off duty
+        let farthest = q_police
+            .iter()
+            .filter(|(_, behaviour, _)| matches!(behaviour, PoliceBehaviour::Patrolling { .. }))
+            .max_by(|(_, _, a), (_, _, b)| {
+                a.translation
+                    .distance(player_position)
+                    .total_cmp(&b.translation.distance(player_position))
+            });
+
+        if let Some((entity, ..)) = farthest {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+pub fn update_police(
+    time: Res<Time>,
+    settings: Res<HeatSettings>,
+    mut q_police: Query<(&mut PoliceBehaviour, &Perceived, &Transform), With<Police>>,
+    q_player: Query<(Entity, &GlobalTransform), With<Player>>,
+    mut points_action_ew: EventWriter<PointsAction>,
+    mut end_shift_ew: EventWriter<EndShiftEvent>,
+) {
+    let Ok((player_entity, player_gtransform)) = q_player.get_single() else {
+        return;
+    };
+    let player_position = player_gtransform.translation();
+
+    // the shift ends a few frames after the arrest, the fine is only paid once
+    let mut arrested = q_police
+        .iter()
+        .any(|(behaviour, ..)| matches!(behaviour, PoliceBehaviour::Arresting));
+
+    for (mut behaviour, perceived, transform) in q_police.iter_mut() {
+        let position = transform.translation;
+
+        let next = match &mut *behaviour {
+            PoliceBehaviour::Patrolling { destination } => {
+                if perceived.seen.contains(&player_entity) {
+                    Some(PoliceBehaviour::Pursuing {
+                        target: player_entity,
+                        timer: Timer::from_seconds(settings.pursuit_time, TimerMode::Once),
+                    })
+                } else if horizontal_distance(position, *destination) <= ARRIVE_DISTANCE {
+                    Some(PoliceBehaviour::Patrolling {
+                        destination: random_point_around(player_position, settings.patrol_radius),
+                    })
+                } else {
+                    None
+                }
+            }
+            PoliceBehaviour::Pursuing { target, timer } => {
+                if horizontal_distance(position, player_position) <= settings.catch_distance {
+                    if !arrested {
+                        points_action_ew.send(PointsAction::Decrement(settings.fine));
+                        end_shift_ew.send(EndShiftEvent(ShiftEndReason::Arrested));
+                        arrested = true;
+                    }
+
+                    Some(PoliceBehaviour::Arresting)
+                } else {
+                    // officers only give up once the suspect is out of sight
+                    if perceived.seen.contains(target) {
+                        timer.reset();
+                    }
+
+                    if timer.tick(time.delta()).finished() {
+                        Some(PoliceBehaviour::Patrolling {
+                            destination: player_position,
+                        })
+                    } else {
+                        None
+                    }
+                }
+            }
+            PoliceBehaviour::Arresting => None,
+        };
+
+        if let Some(next) = next {
+            *behaviour = next;
+        }
+    }
+}
+
+pub fn move_police(
+    time: Res<Time>,
+    settings: Res<HeatSettings>,
+    mut q_police: Query<(&PoliceBehaviour, &mut Transform), With<Police>>,
+    q_target: Query<&GlobalTransform, Without<Police>>,
+) {
+    for (behaviour, mut transform) in q_police.iter_mut() {
+        let (target, speed) = match behaviour {
+            PoliceBehaviour::Patrolling { destination } => {
+                (Some(*destination), settings.patrol_speed)
+            }
+            PoliceBehaviour::Pursuing { target, .. } => (
+                q_target
+                    .get(*target)
+                    .ok()
+                    .map(|gtransform| gtransform.translation()),
+                settings.pursuit_speed,
+            ),
+            PoliceBehaviour::Arresting => (None, 0.0),
+        };
+
+        if let Some(target) = target {
+            walk_towards(&mut transform, target, speed, time.delta_seconds());
+        }
+    }
+}
+
+fn random_point_around(center: Vec3, radius: f32) -> Vec3 {
+    let angle = thread_rng().gen_range(0.0..TAU);
+    return center + Vec3::new(angle.cos(), 0.0, angle.sin()) * radius;
+}
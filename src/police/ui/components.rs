@@ -0,0 +1,7 @@
+use bevy::prelude::*;
+
+#[derive(Component)]
+pub struct HeatUIRoot;
+
+#[derive(Component)]
+pub struct HeatUI;
@@ -0,0 +1,16 @@
+use crate::GameState;
+use bevy::prelude::*;
+
+use super::systems::*;
+
+pub struct HeatUIPlugin;
+
+impl Plugin for HeatUIPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Playing), setup_heat_ui)
+            .add_systems(
+                PostUpdate,
+                update_heat_ui.run_if(in_state(GameState::Playing)),
+            );
+    }
+}
@@ -0,0 +1,54 @@
+use bevy::prelude::*;
+
+use super::components::*;
+use crate::police::{Heat, HeatSettings};
+
+pub fn setup_heat_ui(mut commands: Commands) {
+    commands
+        .spawn((
+            HeatUIRoot,
+            NodeBundle {
+                style: Style {
+                    display: Display::Block,
+                    margin: UiRect::horizontal(Val::Auto),
+                    padding: UiRect::all(Val::Px(15.0)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                HeatUI,
+                TextBundle {
+                    style: Style::default(),
+                    text: Text::from_section(
+                        "Wanted: 0",
+                        TextStyle {
+                            font_size: 30.0,
+                            color: Color::WHITE,
+                            ..Default::default()
+                        },
+                    ),
+                    ..Default::default()
+                },
+            ));
+        });
+}
+
+pub fn update_heat_ui(
+    heat: Res<Heat>,
+    settings: Res<HeatSettings>,
+    mut text_query: Query<&mut Text, With<HeatUI>>,
+) {
+    let max_stars = (settings.max_heat / settings.heat_per_star).floor() as u32;
+
+    for mut text in &mut text_query {
+        text.sections[0].value = format!("Wanted: {}/{}", heat.stars(&settings), max_stars);
+        text.sections[0].style.color = if heat.stars(&settings) > 0 {
+            Color::srgb(1.0, 0.3, 0.3)
+        } else {
+            Color::WHITE
+        };
+    }
+}
@@ -32,3 +32,22 @@ impl Default for MovementSettings {
         };
     }
 }
+
+/// Difficulty of the game, applied to the locks when the world is spawned.
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn factor(&self) -> f32 {
+        return match self {
+            Difficulty::Easy => 0.7,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.3,
+        };
+    }
+}
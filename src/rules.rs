@@ -1,19 +1,35 @@
 use bevy::prelude::*;
 
-use crate::{resources::MenuAction, GameState};
+use crate::{
+    levels::CurrentLevel,
+    resources::MenuAction,
+    time_of_day::{DayPhase, TimeOfDay},
+    GameState,
+};
 
 pub fn plugin(app: &mut App) {
     app.add_event::<MenuAction<RulesUi>>()
         .add_systems(OnEnter(GameState::Playing), setup)
         .add_systems(
             Update,
-            (keyboard_input, events_handler).run_if(in_state(GameState::Playing)),
+            (
+                keyboard_input,
+                events_handler,
+                update_rules.run_if(resource_changed::<TimeOfDay>),
+            )
+                .run_if(in_state(GameState::Playing)),
         );
 }
 
 #[derive(Component)]
 pub struct RulesUi;
 
+/// The text of a rule, `index` is the index of the rule in the current level.
+#[derive(Component)]
+pub struct RuleText {
+    pub index: usize,
+}
+
 fn keyboard_input(
     keys: Res<ButtonInput<KeyCode>>,
     mut event_writer: EventWriter<MenuAction<RulesUi>>,
@@ -56,14 +72,8 @@ pub fn events_handler(
     }
 }
 
-fn setup(mut commands: Commands) {
-    let rules = [
-    "Bicycles are prohibited on public roads.",
-    "Yellow bicycles are prohibited from being placed next to street lamps to prevent confusion among drivers at night.",
-    "Bicycles that are a mix of the colors white and red are prohibited at gas stations to maintain aesthetic standards.",
-    "Bicycles colored not not not not blue are prohibited due to the mayor's preference.",
-    "If every witch is accompanied by a dragon, all black bicycles next to trash cans must be collected; otherwise, all white bicycles next to trash cans must be collected.",
-];
+fn setup(level: Res<CurrentLevel>, mut commands: Commands) {
+    let rules = &level.0.rules;
 
     commands
         .spawn((
@@ -157,24 +167,52 @@ fn setup(mut commands: Commands) {
                             ..default()
                         })
                         .with_children(|parent| {
-                            for (i, rule) in rules.into_iter().enumerate() {
-                                parent.spawn(TextBundle {
-                                    style: Style {
-                                        margin: UiRect::top(Val::Px(15.0)),
-                                        ..default()
-                                    },
-                                    text: Text::from_section(
-                                        format!("{}. {}", i, rule),
-                                        TextStyle {
-                                            font_size: 20.0,
-                                            color: Color::BLACK,
+                            for (i, rule) in rules.iter().enumerate() {
+                                let during = match rule.during {
+                                    Some(DayPhase::Day) => " (during the day)",
+                                    Some(DayPhase::Night) => " (at night)",
+                                    None => "",
+                                };
+
+                                parent.spawn((
+                                    RuleText { index: i },
+                                    TextBundle {
+                                        style: Style {
+                                            margin: UiRect::top(Val::Px(15.0)),
                                             ..default()
                                         },
-                                    ),
-                                    ..default()
-                                });
+                                        text: Text::from_section(
+                                            format!("{}. {}{}", i, rule.text, during),
+                                            TextStyle {
+                                                font_size: 20.0,
+                                                color: Color::BLACK,
+                                                ..default()
+                                            },
+                                        ),
+                                        ..default()
+                                    },
+                                ));
                             }
                         });
                 });
         });
 }
+
+/// Rules that are not in effect at the current time of day are grayed out.
+fn update_rules(
+    level: Res<CurrentLevel>,
+    time_of_day: Res<TimeOfDay>,
+    mut q_rule_text: Query<(&RuleText, &mut Text)>,
+) {
+    for (rule_text, mut text) in q_rule_text.iter_mut() {
+        let Some(rule) = level.0.rules.get(rule_text.index) else {
+            continue;
+        };
+
+        text.sections[0].style.color = if rule.in_effect(&time_of_day) {
+            Color::BLACK
+        } else {
+            Color::hsl(0.0, 0.0, 0.5)
+        };
+    }
+}
@@ -0,0 +1,221 @@
+use bevy::{
+    prelude::*,
+    window::{CursorGrabMode, PrimaryWindow},
+};
+
+use crate::{levels::CurrentLevel, points::Points, simulation_state::SimulationState, GameState};
+
+pub fn plugin(app: &mut App) {
+    app.add_event::<EndShiftEvent>()
+        .add_systems(OnEnter(GameState::Playing), start_shift)
+        .add_systems(
+            Update,
+            (
+                tick_shift_timer.run_if(in_state(SimulationState::Running)),
+                end_shift,
+                update_shift_timer_ui,
+            )
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(OnEnter(GameState::ShiftOver), show_summary)
+        .add_systems(Update, leave_summary.run_if(in_state(GameState::ShiftOver)));
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ShiftEndReason {
+    Arrested,
+    TimeUp,
+}
+
+impl ShiftEndReason {
+    pub fn description(&self) -> &'static str {
+        match self {
+            ShiftEndReason::Arrested => "You got arrested by the police.",
+            ShiftEndReason::TimeUp => "Your shift is over, time to go home.",
+        }
+    }
+}
+
+/// Ends the current shift, the first event sent during a frame wins.
+#[derive(Event)]
+pub struct EndShiftEvent(pub ShiftEndReason);
+
+/// How the last shift ended.
+#[derive(Resource)]
+pub struct ShiftSummary {
+    pub reason: ShiftEndReason,
+}
+
+/// Time left in the shift, `None` for levels whose shifts only end when getting arrested.
+#[derive(Resource)]
+pub struct ShiftTimer(Option<Timer>);
+
+impl ShiftTimer {
+    pub fn remaining_secs(&self) -> Option<f32> {
+        return self.0.as_ref().map(|timer| timer.remaining_secs());
+    }
+}
+
+#[derive(Component)]
+pub struct ShiftSummaryUi;
+
+#[derive(Component)]
+pub struct ShiftTimerText;
+
+fn start_shift(level: Res<CurrentLevel>, mut commands: Commands) {
+    let timer = level
+        .0
+        .shift_length
+        .map(|seconds| Timer::from_seconds(seconds, TimerMode::Once));
+
+    if timer.is_some() {
+        commands.spawn((
+            ShiftTimerText,
+            Name::new("Shift Timer"),
+            TextBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(10.0),
+                    justify_self: JustifySelf::Center,
+                    ..default()
+                },
+                text: Text::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 30.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+                ..default()
+            },
+        ));
+    }
+
+    commands.insert_resource(ShiftTimer(timer));
+}
+
+fn tick_shift_timer(
+    time: Res<Time>,
+    mut shift_timer: ResMut<ShiftTimer>,
+    mut end_shift_ew: EventWriter<EndShiftEvent>,
+) {
+    let Some(timer) = shift_timer.0.as_mut() else {
+        return;
+    };
+
+    if timer.tick(time.delta()).just_finished() {
+        end_shift_ew.send(EndShiftEvent(ShiftEndReason::TimeUp));
+    }
+}
+
+fn update_shift_timer_ui(
+    shift_timer: Res<ShiftTimer>,
+    mut q_text: Query<&mut Text, With<ShiftTimerText>>,
+) {
+    let Some(remaining) = shift_timer.remaining_secs() else {
+        return;
+    };
+
+    let seconds = remaining.ceil() as u32;
+    for mut text in q_text.iter_mut() {
+        text.sections[0].value = format!("Shift ends in {}:{:02}", seconds / 60, seconds % 60);
+    }
+}
+
+fn end_shift(
+    mut end_shift_er: EventReader<EndShiftEvent>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut commands: Commands,
+) {
+    let Some(ev) = end_shift_er.read().next() else {
+        return;
+    };
+
+    commands.insert_resource(ShiftSummary { reason: ev.0 });
+    next_state.set(GameState::ShiftOver);
+
+    end_shift_er.clear();
+}
+
+fn show_summary(
+    summary: Res<ShiftSummary>,
+    q_points: Query<&Points>,
+    mut q_windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut commands: Commands,
+) {
+    let mut window = q_windows.single_mut();
+    window.cursor.grab_mode = CursorGrabMode::None;
+    window.cursor.visible = true;
+
+    let points = q_points.iter().map(|points| points.get()).sum::<u32>();
+
+    commands
+        .spawn((
+            ShiftSummaryUi,
+            Name::new("Shift Summary"),
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    display: Display::Flex,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        display: Display::Flex,
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        width: Val::Vh(60.0),
+                        padding: UiRect::all(Val::Px(15.0)),
+                        border: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    background_color: BackgroundColor(Color::srgb(0.8, 0.8, 0.8)),
+                    border_color: BorderColor(Color::BLACK),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for (text, font_size) in [
+                        ("Shift Over".to_string(), 60.0),
+                        (summary.reason.description().to_string(), 20.0),
+                        (format!("Points: {}", points), 30.0),
+                        (
+                            "(Press [Enter] to choose the next district.)".to_string(),
+                            15.0,
+                        ),
+                    ] {
+                        parent.spawn(TextBundle {
+                            style: Style {
+                                margin: UiRect::top(Val::Px(10.0)),
+                                ..default()
+                            },
+                            text: Text::from_section(
+                                text,
+                                TextStyle {
+                                    font_size,
+                                    color: Color::BLACK,
+                                    ..default()
+                                },
+                            ),
+                            ..default()
+                        });
+                    }
+                });
+        });
+}
+
+fn leave_summary(keys: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<GameState>>) {
+    if keys.just_pressed(KeyCode::Enter) {
+        next_state.set(GameState::LevelSelect);
+    }
+}
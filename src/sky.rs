@@ -0,0 +1,269 @@
+use bevy::{core_pipeline::Skybox, prelude::*, render::render_resource::TextureFormat};
+
+use crate::{
+    cubemap_factory::{CubemapFactory, CubemapFailed, CubemapReady},
+    time_of_day::TimeOfDay,
+};
+
+// The sky of the camera changes with the time of day. Every sky is a cubemap that is fully shown
+// at its hour and fades into the next one before that one's hour. Blending cubemaps happens on
+// the CPU, so the blend only moves in `blend_steps` steps, the clock is slow enough that the steps
+// can't be seen.
+//
+// Skies that fail to load are left out, the remaining ones fade into each other.
+
+pub fn plugin(app: &mut App) {
+    app.register_type::<SkySettings>()
+        .init_resource::<SkySettings>()
+        .init_resource::<SkyController>()
+        .add_systems(Startup, load_skies)
+        .add_systems(Update, (track_skies, blend_skies, attach_skybox).chain());
+}
+
+#[derive(Reflect, Clone, Debug)]
+pub struct SkyKeyframe {
+    /// Hour the sky is fully shown at.
+    pub hour: f32,
+    /// Folder with the faces of the cubemap, see `CubemapFactory::load_from_folder`.
+    pub path: String,
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct SkySettings {
+    /// The skies are loaded at startup, changes only apply after a restart.
+    pub keyframes: Vec<SkyKeyframe>,
+    /// Hours it takes for a sky to fade into the next one.
+    pub fade_hours: f32,
+    pub blend_steps: u32,
+}
+
+impl Default for SkySettings {
+    fn default() -> Self {
+        let keyframe = |hour: f32, path: &str| SkyKeyframe {
+            hour,
+            path: path.to_string(),
+        };
+
+        return Self {
+            keyframes: vec![
+                keyframe(TimeOfDay::SUNRISE, "sky/dawn"),
+                keyframe(TimeOfDay::SUNRISE + 2.0, "sky"),
+                keyframe(TimeOfDay::SUNSET, "sky/dusk"),
+                keyframe(TimeOfDay::SUNSET + 1.5, "sky/night"),
+            ],
+            fade_hours: 1.0,
+            blend_steps: 32,
+        };
+    }
+}
+
+struct Sky {
+    hour: f32,
+    cubemap: Handle<Image>,
+    ready: bool,
+}
+
+/// Owns the skybox of the camera.
+#[derive(Resource, Default)]
+pub struct SkyController {
+    skies: Vec<Sky>,
+    /// The image the blends are written to.
+    blended: Option<Handle<Image>>,
+    /// The skies that are blended and the step of the blend.
+    blend: Option<(usize, usize, u32)>,
+    /// The image the skybox of the camera shows.
+    shown: Option<Handle<Image>>,
+}
+
+fn load_skies(
+    settings: Res<SkySettings>,
+    mut controller: ResMut<SkyController>,
+    mut cubemap_factory: ResMut<CubemapFactory>,
+    asset_server: Res<AssetServer>,
+    images: Res<Assets<Image>>,
+) {
+    let mut keyframes = settings.keyframes.clone();
+    keyframes.sort_by(|a, b| a.hour.total_cmp(&b.hour));
+
+    controller.skies = keyframes
+        .into_iter()
+        .map(|keyframe| Sky {
+            hour: keyframe.hour.rem_euclid(24.0),
+            cubemap: cubemap_factory.load_from_folder(
+                keyframe.path,
+                Res::clone(&asset_server),
+                Res::clone(&images),
+            ),
+            ready: false,
+        })
+        .collect();
+}
+
+fn track_skies(
+    mut controller: ResMut<SkyController>,
+    mut ready_er: EventReader<CubemapReady>,
+    mut failed_er: EventReader<CubemapFailed>,
+) {
+    for event in ready_er.read() {
+        for sky in controller.skies.iter_mut() {
+            if sky.cubemap == event.cubemap {
+                sky.ready = true;
+            }
+        }
+    }
+
+    let mut failed = false;
+    for event in failed_er.read() {
+        controller.skies.retain(|sky| sky.cubemap != event.cubemap);
+        failed = true;
+    }
+
+    // the indices of the blend belong to the old list
+    if failed {
+        controller.blend = None;
+    }
+}
+
+fn blend_skies(
+    settings: Res<SkySettings>,
+    time_of_day: Res<TimeOfDay>,
+    mut controller: ResMut<SkyController>,
+    mut images: ResMut<Assets<Image>>,
+    mut q_skybox: Query<&mut Skybox>,
+) {
+    let ready: Vec<usize> = (0..controller.skies.len())
+        .filter(|index| controller.skies[*index].ready)
+        .collect();
+
+    if ready.is_empty() {
+        return;
+    }
+
+    let hours = time_of_day.hours();
+
+    // the last sky that started before now, before the first sky of the day it's the last one of
+    // the day before
+    let position = ready
+        .iter()
+        .rposition(|index| controller.skies[*index].hour <= hours)
+        .unwrap_or(ready.len() - 1);
+
+    let current = ready[position];
+    let next = ready[(position + 1) % ready.len()];
+
+    let gap = (controller.skies[next].hour - controller.skies[current].hour).rem_euclid(24.0);
+    let until_next = (controller.skies[next].hour - hours).rem_euclid(24.0);
+    let fade_hours = settings.fade_hours.min(gap);
+
+    let factor = if current == next || fade_hours <= 0.0 {
+        0.0
+    } else {
+        (1.0 - until_next / fade_hours).clamp(0.0, 1.0)
+    };
+
+    let steps = settings.blend_steps.max(1);
+    let step = (factor * steps as f32).round() as u32;
+
+    let blend = (current, next, step);
+    if controller.blend == Some(blend) {
+        return;
+    }
+    controller.blend = Some(blend);
+
+    let shown = if step == 0 {
+        controller.skies[current].cubemap.clone()
+    } else if step == steps {
+        controller.skies[next].cubemap.clone()
+    } else {
+        let image = blend_cubemaps(
+            images.get(&controller.skies[current].cubemap),
+            images.get(&controller.skies[next].cubemap),
+            step,
+            steps,
+        );
+
+        match (image, controller.blended.clone()) {
+            (Some(image), Some(blended)) => {
+                images.insert(&blended, image);
+                blended
+            }
+            (Some(image), None) => {
+                let blended = images.add(image);
+                controller.blended = Some(blended.clone());
+                blended
+            }
+            (None, _) => {
+                // the skies can't be blended, so the sky switches halfway through the fade
+                let sky = if step * 2 < steps { current } else { next };
+                controller.skies[sky].cubemap.clone()
+            }
+        }
+    };
+
+    for mut skybox in q_skybox.iter_mut() {
+        skybox.image = shown.clone();
+    }
+
+    controller.shown = Some(shown);
+}
+
+/// Mixes two cubemaps, `step` of `steps` of the way from `from` to `to`. Only works for cubemaps
+/// of the same size with a byte per channel.
+fn blend_cubemaps(
+    from: Option<&Image>,
+    to: Option<&Image>,
+    step: u32,
+    steps: u32,
+) -> Option<Image> {
+    let (from, to) = (from?, to?);
+
+    let same_layout = from.texture_descriptor.size == to.texture_descriptor.size
+        && from.texture_descriptor.format == to.texture_descriptor.format
+        && from.data.len() == to.data.len();
+
+    let byte_channels = matches!(
+        from.texture_descriptor.format,
+        TextureFormat::Rgba8Unorm
+            | TextureFormat::Rgba8UnormSrgb
+            | TextureFormat::Bgra8Unorm
+            | TextureFormat::Bgra8UnormSrgb
+    );
+
+    if !same_layout || !byte_channels {
+        return None;
+    }
+
+    let data = from
+        .data
+        .iter()
+        .zip(to.data.iter())
+        .map(|(a, b)| ((*a as u32 * (steps - step) + *b as u32 * step) / steps) as u8)
+        .collect();
+
+    return Some(Image {
+        data,
+        texture_descriptor: from.texture_descriptor.clone(),
+        sampler: from.sampler.clone(),
+        texture_view_descriptor: from.texture_view_descriptor.clone(),
+        asset_usage: from.asset_usage,
+    });
+}
+
+/// Cameras are spawned again for every level, new cameras get the sky that is shown right now.
+fn attach_skybox(
+    controller: Res<SkyController>,
+    q_camera: Query<Entity, (With<Camera3d>, Without<Skybox>)>,
+    mut commands: Commands,
+) {
+    let Some(shown) = &controller.shown else {
+        return;
+    };
+
+    for camera_entity in q_camera.iter() {
+        commands.entity(camera_entity).insert(Skybox {
+            image: shown.clone(),
+            brightness: 1000.0,
+        });
+    }
+}
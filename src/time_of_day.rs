@@ -0,0 +1,311 @@
+use std::f32::consts::PI;
+
+use bevy::{core_pipeline::Skybox, prelude::*, transform::TransformSystem};
+use serde::Deserialize;
+
+use crate::{
+    levels::CurrentLevel,
+    simulation_state::SimulationState,
+    world_spawning::{
+        on_spawn::{Bicycle, Illegal, MapElement},
+        RegisterSpawnKeyword,
+    },
+    GameState,
+};
+
+pub fn plugin(app: &mut App) {
+    app.register_spawn_keyword("Lamp", |commands, extras| {
+        commands.insert((
+            Lamp {
+                light_height: extras.get_f64("light_height").unwrap_or(4.0) as f32,
+            },
+            // lamps are still part of the map
+            MapElement,
+        ));
+    })
+    .register_type::<TimeOfDay>()
+    .init_resource::<TimeOfDay>()
+    .add_systems(OnEnter(GameState::LoadingLevel), start_day)
+    .add_systems(
+        Update,
+        (
+            advance_time
+                .run_if(in_state(GameState::Playing))
+                .run_if(in_state(SimulationState::Running)),
+            light_lamps,
+            (move_sun, update_lamps, update_sky),
+        )
+            .chain(),
+    )
+    .add_systems(
+        PostUpdate,
+        enforce_lamp_rules
+            .after(TransformSystem::TransformPropagate)
+            .run_if(in_state(GameState::Playing)),
+    );
+}
+
+/// Part of the day, rules can be limited to one of them.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum DayPhase {
+    Day,
+    Night,
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct TimeOfDay {
+    /// Hours since midnight, from 0 to 24.
+    hours: f32,
+    /// Seconds a whole day takes.
+    pub day_length: f32,
+}
+
+impl Default for TimeOfDay {
+    fn default() -> Self {
+        return Self {
+            hours: 12.0,
+            day_length: 20.0 * 60.0,
+        };
+    }
+}
+
+impl TimeOfDay {
+    pub const SUNRISE: f32 = 6.0;
+    pub const SUNSET: f32 = 20.0;
+    /// Hours it takes to get light or dark around sunrise and sunset.
+    pub const TWILIGHT: f32 = 1.0;
+
+    pub fn hours(&self) -> f32 {
+        return self.hours;
+    }
+
+    pub fn set_hours(&mut self, hours: f32) {
+        self.hours = hours.rem_euclid(24.0);
+    }
+
+    /// How light it is, 0 at night, 1 during the day and in between around sunrise and sunset.
+    pub fn daylight(&self) -> f32 {
+        let half = Self::TWILIGHT / 2.0;
+        let dawn = ((self.hours - Self::SUNRISE + half) / Self::TWILIGHT).clamp(0.0, 1.0);
+        let dusk = ((Self::SUNSET + half - self.hours) / Self::TWILIGHT).clamp(0.0, 1.0);
+
+        return dawn.min(dusk);
+    }
+
+    pub fn phase(&self) -> DayPhase {
+        return if self.daylight() >= 0.5 {
+            DayPhase::Day
+        } else {
+            DayPhase::Night
+        };
+    }
+
+    /// Angle of the sun above the horizon, it rises at 0, is straight up at PI / 2 and sets at PI.
+    fn sun_elevation(&self) -> f32 {
+        let progress = (self.hours - Self::SUNRISE) / (Self::SUNSET - Self::SUNRISE);
+        return (progress * PI).clamp(0.0, PI);
+    }
+}
+
+/// The directional light that moves with the time of day.
+#[derive(Component)]
+pub struct Sun;
+
+/// A street lamp, it turns on at night.
+#[derive(Component)]
+pub struct Lamp {
+    /// Height of the light above the origin of the lamp.
+    pub light_height: f32,
+}
+
+#[derive(Component)]
+pub struct LampLight;
+
+/// A bicycle that is illegal because it's parked next to a street lamp. Unlike bicycles the map
+/// made illegal, it's legal again once the rule is no longer in effect.
+#[derive(Component)]
+pub struct NextToLamp;
+
+const DAY_ILLUMINANCE: f32 = light_consts::lux::OVERCAST_DAY;
+const NIGHT_ILLUMINANCE: f32 = 0.0;
+
+const DAY_AMBIENT_BRIGHTNESS: f32 = 600.0;
+const NIGHT_AMBIENT_BRIGHTNESS: f32 = 60.0;
+
+const DAY_SKY_BRIGHTNESS: f32 = 1000.0;
+const NIGHT_SKY_BRIGHTNESS: f32 = 40.0;
+
+const LAMP_INTENSITY: f32 = 400_000.0;
+
+fn start_day(level: Res<CurrentLevel>, mut time_of_day: ResMut<TimeOfDay>) {
+    time_of_day.set_hours(level.0.start_hour);
+}
+
+fn advance_time(time: Res<Time>, mut time_of_day: ResMut<TimeOfDay>) {
+    let hours = time_of_day.hours + time.delta_seconds() / time_of_day.day_length * 24.0;
+    time_of_day.set_hours(hours);
+}
+
+fn light_lamps(q_lamp: Query<(Entity, &Lamp), Added<Lamp>>, mut commands: Commands) {
+    for (lamp_entity, lamp) in q_lamp.iter() {
+        commands.entity(lamp_entity).with_children(|parent| {
+            parent.spawn((
+                LampLight,
+                PointLightBundle {
+                    point_light: PointLight {
+                        color: Color::srgb(1.0, 0.85, 0.6),
+                        intensity: 0.0,
+                        range: 15.0,
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(0.0, lamp.light_height, 0.0),
+                    ..default()
+                },
+            ));
+        });
+    }
+}
+
+/// Rules about street lamps only hold during a part of the day, so the bicycles next to lamps are
+/// checked again whenever the part of the day changes and when new bicycles are parked.
+fn enforce_lamp_rules(
+    level: Res<CurrentLevel>,
+    time_of_day: Res<TimeOfDay>,
+    mut last_phase: Local<Option<DayPhase>>,
+    q_lamp: Query<&GlobalTransform, With<Lamp>>,
+    q_new_bicycle: Query<(), Added<Bicycle>>,
+    q_bicycle: Query<(Entity, &GlobalTransform, Has<Illegal>, Has<NextToLamp>), With<Bicycle>>,
+    mut commands: Commands,
+) {
+    let phase = time_of_day.phase();
+    if *last_phase == Some(phase) && q_new_bicycle.is_empty() {
+        return;
+    }
+    *last_phase = Some(phase);
+
+    // the rules in effect forbid bicycles closer than this to a lamp
+    let distance = level
+        .0
+        .rules
+        .iter()
+        .filter(|rule| rule.in_effect(&time_of_day))
+        .filter_map(|rule| rule.near_lamps)
+        .reduce(f32::max);
+
+    for (bicycle_entity, gtransform, illegal, next_to_lamp) in q_bicycle.iter() {
+        let position = gtransform.translation().xz();
+        let near_lamp = distance.is_some_and(|distance| {
+            q_lamp
+                .iter()
+                .any(|lamp| lamp.translation().xz().distance(position) < distance)
+        });
+
+        if near_lamp && !illegal {
+            commands
+                .entity(bicycle_entity)
+                .insert((Illegal, NextToLamp));
+        } else if !near_lamp && next_to_lamp {
+            commands
+                .entity(bicycle_entity)
+                .remove::<(Illegal, NextToLamp)>();
+        }
+    }
+}
+
+fn move_sun(
+    time_of_day: Res<TimeOfDay>,
+    mut q_sun: Query<(&mut Transform, &mut DirectionalLight), With<Sun>>,
+) {
+    for (mut transform, mut light) in q_sun.iter_mut() {
+        transform.rotation =
+            Quat::from_euler(EulerRot::YXZ, -0.7, -time_of_day.sun_elevation(), 0.0);
+        light.illuminance = NIGHT_ILLUMINANCE.lerp(DAY_ILLUMINANCE, time_of_day.daylight());
+    }
+}
+
+fn update_lamps(
+    time_of_day: Res<TimeOfDay>,
+    mut q_lamp_light: Query<&mut PointLight, With<LampLight>>,
+) {
+    let intensity = LAMP_INTENSITY * (1.0 - time_of_day.daylight());
+
+    for mut light in q_lamp_light.iter_mut() {
+        light.intensity = intensity;
+    }
+}
+
+fn update_sky(
+    time_of_day: Res<TimeOfDay>,
+    ambient_light: Option<ResMut<AmbientLight>>,
+    mut q_skybox: Query<&mut Skybox>,
+) {
+    let daylight = time_of_day.daylight();
+
+    if let Some(mut ambient_light) = ambient_light {
+        ambient_light.color =
+            Color::srgb_u8(72, 80, 128).mix(&Color::srgb_u8(224, 208, 208), daylight);
+        ambient_light.brightness = NIGHT_AMBIENT_BRIGHTNESS.lerp(DAY_AMBIENT_BRIGHTNESS, daylight);
+    }
+
+    for mut skybox in q_skybox.iter_mut() {
+        skybox.brightness = NIGHT_SKY_BRIGHTNESS.lerp(DAY_SKY_BRIGHTNESS, daylight);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spawn_bicycle(app: &mut App, x: f32) -> Entity {
+        return app
+            .world_mut()
+            .spawn((Bicycle, GlobalTransform::from_xyz(x, 0.0, 0.0)))
+            .id();
+    }
+
+    #[test]
+    fn bicycles_next_to_lamps_are_illegal_at_night() {
+        let level = serde_json::from_str(
+            r#"{
+                "name": "Test",
+                "map": { "gltf": "test.glb" },
+                "rules": [{ "text": "No lamps.", "during": "night", "near_lamps": 4.0 }]
+            }"#,
+        )
+        .unwrap();
+
+        let mut time_of_day = TimeOfDay::default();
+        time_of_day.set_hours(TimeOfDay::SUNSET + 2.0);
+
+        let mut app = App::new();
+        app.insert_resource(CurrentLevel(level))
+            .insert_resource(time_of_day)
+            .add_systems(Update, enforce_lamp_rules);
+
+        app.world_mut().spawn((
+            Lamp { light_height: 4.0 },
+            GlobalTransform::from_xyz(0.0, 0.0, 0.0),
+        ));
+        let next_to_lamp = spawn_bicycle(&mut app, 2.0);
+        let far_away = spawn_bicycle(&mut app, 10.0);
+        let illegal_anyway = spawn_bicycle(&mut app, 1.0);
+        app.world_mut().entity_mut(illegal_anyway).insert(Illegal);
+
+        let is_illegal = |app: &App, entity: Entity| app.world().get::<Illegal>(entity).is_some();
+
+        app.update();
+        assert!(is_illegal(&app, next_to_lamp));
+        assert!(!is_illegal(&app, far_away));
+        assert!(is_illegal(&app, illegal_anyway));
+
+        app.world_mut()
+            .resource_mut::<TimeOfDay>()
+            .set_hours(TimeOfDay::SUNRISE + 4.0);
+        app.update();
+        assert!(!is_illegal(&app, next_to_lamp));
+        assert!(!is_illegal(&app, far_away));
+        assert!(is_illegal(&app, illegal_anyway));
+    }
+}
@@ -0,0 +1,511 @@
+use std::time::Duration;
+
+use avian3d::prelude::*;
+use bevy::{pbr::NotShadowCaster, prelude::*};
+use rand::{thread_rng, Rng};
+
+use crate::{
+    car_controller::{components::MovementDampingFactor as CarDamping, CarController},
+    levels::reset_resource,
+    player_controller::{
+        pick_up::Thrown, Grounded, MovementDampingFactor as PlayerDamping, Player,
+    },
+    simulation_state::SimulationState,
+    time_of_day::TimeOfDay,
+    GameState,
+};
+
+// The weather changes every few minutes during a shift. The simulation only touches physics
+// components and resources, the fog and the puddle meshes are added by separate systems that do
+// nothing when there is no camera or renderer, so the weather also works in a headless app.
+
+pub fn plugin(app: &mut App) {
+    app.register_type::<WeatherSettings>()
+        .init_resource::<WeatherSettings>()
+        .init_resource::<Weather>()
+        .add_systems(OnEnter(GameState::LoadingLevel), reset_resource::<Weather>)
+        .add_systems(
+            Update,
+            (
+                (change_weather, follow_weather).chain(),
+                remember_dry_damping,
+                (car_grip, spawn_puddles, dry_puddles, player_grip, blow_wind)
+                    .after(follow_weather),
+            )
+                .run_if(in_state(GameState::Playing))
+                .run_if(in_state(SimulationState::Running)),
+        )
+        .add_systems(
+            Update,
+            (update_fog, add_puddle_meshes).run_if(in_state(GameState::Playing)),
+        );
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug, Reflect)]
+pub enum WeatherKind {
+    #[default]
+    Clear,
+    Rain,
+    Fog,
+    Wind,
+}
+
+impl WeatherKind {
+    /// Distance in meters at which things disappear in the fog.
+    fn visibility(&self) -> f32 {
+        return match self {
+            WeatherKind::Clear => 500.0,
+            WeatherKind::Rain => 150.0,
+            WeatherKind::Fog => 40.0,
+            WeatherKind::Wind => 350.0,
+        };
+    }
+
+    /// How wet the roads get, from 0 for dry to 1 for soaked.
+    fn wetness(&self) -> f32 {
+        return match self {
+            WeatherKind::Rain => 1.0,
+            WeatherKind::Fog => 0.3,
+            WeatherKind::Clear | WeatherKind::Wind => 0.0,
+        };
+    }
+
+    /// Acceleration the wind gives to thrown objects.
+    fn wind_strength(&self) -> f32 {
+        return match self {
+            WeatherKind::Wind => 12.0,
+            WeatherKind::Rain => 3.0,
+            WeatherKind::Clear | WeatherKind::Fog => 1.0,
+        };
+    }
+}
+
+/// The current weather. The conditions follow the kind of weather over a few seconds, so a
+/// change of the weather doesn't happen from one frame to the next.
+#[derive(Resource)]
+pub struct Weather {
+    kind: WeatherKind,
+    timer: Timer,
+    wind_direction: Vec3,
+    visibility: f32,
+    wetness: f32,
+    wind: Vec3,
+}
+
+impl Default for Weather {
+    fn default() -> Self {
+        let kind = WeatherKind::default();
+
+        return Self {
+            kind,
+            timer: Timer::default(),
+            wind_direction: Vec3::X,
+            visibility: kind.visibility(),
+            wetness: kind.wetness(),
+            wind: Vec3::ZERO,
+        };
+    }
+}
+
+impl Weather {
+    pub fn kind(&self) -> WeatherKind {
+        return self.kind;
+    }
+
+    /// Changes the weather, the conditions follow it over the transition time.
+    pub fn set_kind(&mut self, kind: WeatherKind, wind_direction: Vec3) {
+        self.kind = kind;
+        self.wind_direction = wind_direction.normalize_or_zero();
+    }
+
+    pub fn visibility(&self) -> f32 {
+        return self.visibility;
+    }
+
+    pub fn wetness(&self) -> f32 {
+        return self.wetness;
+    }
+
+    pub fn wind(&self) -> Vec3 {
+        return self.wind;
+    }
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct WeatherSettings {
+    /// Seconds between two changes of the weather.
+    pub change_interval: f32,
+    /// Seconds the conditions take to follow a change of the weather.
+    pub transition_time: f32,
+    pub rain_chance: f32,
+    pub fog_chance: f32,
+    pub wind_chance: f32,
+    /// Share of the damping of the car that is lost on soaked roads, from 0 to 1.
+    pub car_grip_loss: f32,
+    /// Share of the damping of the player that is lost in a puddle, from 0 to 1.
+    pub puddle_grip_loss: f32,
+    pub max_puddles: usize,
+    /// Seconds between two new puddles while it rains.
+    pub puddle_interval: f32,
+    /// Puddles form at most this far away from the player.
+    pub puddle_distance: f32,
+}
+
+impl Default for WeatherSettings {
+    fn default() -> Self {
+        return Self {
+            change_interval: 150.0,
+            transition_time: 10.0,
+            rain_chance: 0.3,
+            fog_chance: 0.15,
+            wind_chance: 0.15,
+            car_grip_loss: 0.5,
+            puddle_grip_loss: 0.8,
+            max_puddles: 15,
+            puddle_interval: 2.0,
+            puddle_distance: 25.0,
+        };
+    }
+}
+
+/// The damping factor of a body on dry ground, the weather only ever lowers the grip from here.
+#[derive(Component)]
+pub struct DryDamping(pub f32);
+
+/// A puddle left by the rain, it shrinks and disappears when the roads dry up.
+#[derive(Component)]
+pub struct Puddle {
+    pub radius: f32,
+}
+
+fn change_weather(time: Res<Time>, settings: Res<WeatherSettings>, mut weather: ResMut<Weather>) {
+    let change_interval = Duration::from_secs_f32(settings.change_interval.max(1.0));
+    if weather.timer.duration() != change_interval {
+        weather.timer = Timer::new(change_interval, TimerMode::Repeating);
+    }
+
+    if !weather.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let mut rng = thread_rng();
+    let roll = rng.gen::<f32>();
+
+    let kind = if roll < settings.rain_chance {
+        WeatherKind::Rain
+    } else if roll < settings.rain_chance + settings.fog_chance {
+        WeatherKind::Fog
+    } else if roll < settings.rain_chance + settings.fog_chance + settings.wind_chance {
+        WeatherKind::Wind
+    } else {
+        WeatherKind::Clear
+    };
+
+    let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+    weather.set_kind(kind, Vec3::new(angle.cos(), 0.0, angle.sin()));
+}
+
+fn follow_weather(time: Res<Time>, settings: Res<WeatherSettings>, mut weather: ResMut<Weather>) {
+    let t = (time.delta_seconds() / settings.transition_time.max(f32::EPSILON)).min(1.0);
+    let kind = weather.kind;
+
+    weather.visibility = weather.visibility.lerp(kind.visibility(), t);
+    weather.wetness = weather.wetness.lerp(kind.wetness(), t);
+
+    let wind = weather.wind_direction * kind.wind_strength();
+    weather.wind = weather.wind.lerp(wind, t);
+}
+
+fn remember_dry_damping(
+    q_car: Query<(Entity, &CarDamping), Added<CarDamping>>,
+    q_player: Query<(Entity, &PlayerDamping), Added<PlayerDamping>>,
+    mut commands: Commands,
+) {
+    for (entity, damping) in q_car.iter() {
+        commands.entity(entity).insert(DryDamping(damping.0));
+    }
+
+    for (entity, damping) in q_player.iter() {
+        commands.entity(entity).insert(DryDamping(damping.0));
+    }
+}
+
+/// The car slides further on wet roads. A damping factor of 1 doesn't slow down at all.
+fn car_grip(
+    weather: Res<Weather>,
+    settings: Res<WeatherSettings>,
+    mut q_car: Query<(&DryDamping, &mut CarDamping), With<CarController>>,
+) {
+    let grip_loss = weather.wetness * settings.car_grip_loss.clamp(0.0, 1.0);
+
+    for (dry_damping, mut damping) in q_car.iter_mut() {
+        damping.0 = dry_damping.0.lerp(1.0, grip_loss);
+    }
+}
+
+fn spawn_puddles(
+    time: Res<Time>,
+    weather: Res<Weather>,
+    settings: Res<WeatherSettings>,
+    mut timer: Local<Timer>,
+    q_player: Query<(Entity, &Transform), With<Player>>,
+    q_puddle: Query<(), With<Puddle>>,
+    spatial_query: SpatialQuery,
+    mut commands: Commands,
+) {
+    // puddles only form while it's really raining
+    if weather.kind != WeatherKind::Rain || weather.wetness < 0.5 {
+        return;
+    }
+
+    let puddle_interval = Duration::from_secs_f32(settings.puddle_interval.max(0.1));
+    if timer.duration() != puddle_interval {
+        *timer = Timer::new(puddle_interval, TimerMode::Repeating);
+    }
+
+    if !timer.tick(time.delta()).just_finished() || q_puddle.iter().count() >= settings.max_puddles
+    {
+        return;
+    }
+
+    let Ok((player_entity, player_transform)) = q_player.get_single() else {
+        return;
+    };
+
+    let mut rng = thread_rng();
+    let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+    let distance = rng.gen_range(0.0..settings.puddle_distance.max(1.0));
+    let above = player_transform.translation
+        + Vec3::new(angle.cos() * distance, 20.0, angle.sin() * distance);
+
+    let Some(hit) = spatial_query.cast_ray(
+        above,
+        Dir3::NEG_Y,
+        40.0,
+        true,
+        SpatialQueryFilter::default().with_excluded_entities([player_entity]),
+    ) else {
+        return;
+    };
+
+    let position = above + Vec3::NEG_Y * hit.time_of_impact;
+
+    // no puddles on roofs
+    if position.y > player_transform.translation.y + 1.0 {
+        return;
+    }
+
+    commands.spawn((
+        Name::new("Puddle"),
+        Puddle {
+            radius: rng.gen_range(1.0..2.5),
+        },
+        SpatialBundle {
+            transform: Transform::from_translation(position + Vec3::Y * 0.02)
+                .with_scale(Vec3::splat(weather.wetness)),
+            ..default()
+        },
+    ));
+}
+
+fn dry_puddles(
+    weather: Res<Weather>,
+    mut q_puddle: Query<(Entity, &mut Transform), With<Puddle>>,
+    mut commands: Commands,
+) {
+    for (puddle_entity, mut transform) in q_puddle.iter_mut() {
+        if weather.wetness < 0.05 {
+            commands.entity(puddle_entity).despawn_recursive();
+            continue;
+        }
+
+        transform.scale = Vec3::splat(weather.wetness);
+    }
+}
+
+/// The player slips when walking through a puddle.
+fn player_grip(
+    settings: Res<WeatherSettings>,
+    mut q_player: Query<(&Transform, &DryDamping, &mut PlayerDamping, Has<Grounded>), With<Player>>,
+    q_puddle: Query<(&Puddle, &Transform), Without<Player>>,
+) {
+    for (player_transform, dry_damping, mut damping, grounded) in q_player.iter_mut() {
+        let in_puddle = grounded
+            && q_puddle.iter().any(|(puddle, transform)| {
+                let offset = transform.translation - player_transform.translation;
+                offset.xz().length() < puddle.radius * transform.scale.x
+            });
+
+        damping.0 = if in_puddle {
+            dry_damping
+                .0
+                .lerp(1.0, settings.puddle_grip_loss.clamp(0.0, 1.0))
+        } else {
+            dry_damping.0
+        };
+    }
+}
+
+fn blow_wind(
+    time: Res<Time>,
+    weather: Res<Weather>,
+    mut q_thrown: Query<&mut LinearVelocity, With<Thrown>>,
+) {
+    let push = weather.wind * time.delta_seconds();
+
+    for mut linear_velocity in q_thrown.iter_mut() {
+        linear_velocity.0 += push;
+    }
+}
+
+/// Puts the fog of the weather on the cameras, the fog gets darker at night.
+fn update_fog(
+    weather: Res<Weather>,
+    time_of_day: Option<Res<TimeOfDay>>,
+    mut q_camera: Query<(Entity, Option<&mut FogSettings>), With<Camera3d>>,
+    mut commands: Commands,
+) {
+    let daylight = time_of_day.map_or(1.0, |time_of_day| time_of_day.daylight());
+    let color = Color::srgb(0.05, 0.06, 0.09).mix(&Color::srgb(0.6, 0.63, 0.68), daylight);
+    let falloff = FogFalloff::from_visibility(weather.visibility);
+
+    for (camera_entity, fog) in q_camera.iter_mut() {
+        match fog {
+            Some(mut fog) => {
+                fog.color = color;
+                fog.falloff = falloff.clone();
+            }
+            None => {
+                commands.entity(camera_entity).insert(FogSettings {
+                    color,
+                    falloff: falloff.clone(),
+                    ..default()
+                });
+            }
+        }
+    }
+}
+
+/// Gives new puddles a flat, shiny disc. Without a renderer the puddles stay invisible.
+fn add_puddle_meshes(
+    q_puddle: Query<(Entity, &Puddle), Added<Puddle>>,
+    meshes: Option<ResMut<Assets<Mesh>>>,
+    materials: Option<ResMut<Assets<StandardMaterial>>>,
+    mut material: Local<Option<Handle<StandardMaterial>>>,
+    mut commands: Commands,
+) {
+    let (Some(mut meshes), Some(mut materials)) = (meshes, materials) else {
+        return;
+    };
+
+    for (puddle_entity, puddle) in q_puddle.iter() {
+        let material = material
+            .get_or_insert_with(|| {
+                materials.add(StandardMaterial {
+                    base_color: Color::srgba(0.1, 0.12, 0.15, 0.7),
+                    perceptual_roughness: 0.05,
+                    reflectance: 0.8,
+                    alpha_mode: AlphaMode::Blend,
+                    ..default()
+                })
+            })
+            .clone();
+
+        commands.entity(puddle_entity).with_children(|parent| {
+            parent.spawn((
+                PbrBundle {
+                    mesh: meshes.add(Cylinder::new(puddle.radius, 0.01)),
+                    material,
+                    ..default()
+                },
+                NotShadowCaster,
+            ));
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+
+    /// The weather simulation of the car without the rest of the game, every update is a quarter
+    /// of a second, the longest step virtual time takes.
+    fn app() -> App {
+        let mut app = App::new();
+
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+                250,
+            )))
+            .init_resource::<WeatherSettings>()
+            .init_resource::<Weather>()
+            .add_systems(
+                Update,
+                (
+                    follow_weather,
+                    remember_dry_damping,
+                    car_grip.after(follow_weather),
+                ),
+            );
+
+        return app;
+    }
+
+    fn run(app: &mut App, seconds: f32) {
+        for _ in 0..(seconds * 4.0) as usize {
+            app.update();
+        }
+    }
+
+    #[test]
+    fn rain_makes_the_car_slide() {
+        let mut app = app();
+        let car = app.world_mut().spawn((CarController, CarDamping(0.9))).id();
+
+        run(&mut app, 1.0);
+        assert_eq!(app.world().get::<DryDamping>(car).unwrap().0, 0.9);
+        assert_eq!(app.world().get::<CarDamping>(car).unwrap().0, 0.9);
+
+        app.world_mut()
+            .resource_mut::<Weather>()
+            .set_kind(WeatherKind::Rain, Vec3::X);
+
+        // the roads get wetter and the car loses grip step by step
+        let mut previous_wetness = 0.0;
+        let mut previous_damping = 0.9;
+
+        for _ in 0..10 {
+            run(&mut app, 1.0);
+
+            let wetness = app.world().resource::<Weather>().wetness();
+            let damping = app.world().get::<CarDamping>(car).unwrap().0;
+
+            assert!(wetness > previous_wetness && wetness < 1.0);
+            assert!(damping > previous_damping && damping < 1.0);
+
+            previous_wetness = wetness;
+            previous_damping = damping;
+        }
+
+        run(&mut app, 60.0);
+
+        let grip_loss = app.world().resource::<WeatherSettings>().car_grip_loss;
+        let wetness = app.world().resource::<Weather>().wetness();
+        let damping = app.world().get::<CarDamping>(car).unwrap().0;
+
+        assert!((wetness - WeatherKind::Rain.wetness()).abs() < 0.01);
+        assert!((damping - 0.9.lerp(1.0, grip_loss)).abs() < 0.01);
+
+        // and it dries up again
+        app.world_mut()
+            .resource_mut::<Weather>()
+            .set_kind(WeatherKind::Clear, Vec3::X);
+        run(&mut app, 60.0);
+
+        assert!(app.world().resource::<Weather>().wetness() < 0.01);
+        assert!((app.world().get::<CarDamping>(car).unwrap().0 - 0.9).abs() < 0.01);
+    }
+}
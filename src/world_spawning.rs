@@ -1,134 +1,451 @@
 use avian3d::prelude::{Collider, RigidBody};
+use std::sync::Mutex;
+
 use bevy::{
     ecs::system::EntityCommands,
-    gltf::{GltfMesh, GltfNode},
+    gltf::{GltfExtras, GltfMesh, GltfNode},
     prelude::*,
+    utils::{HashMap, HashSet},
 };
 
 use crate::*;
 use on_spawn::*;
 
+pub mod collider_baking;
+pub mod generator;
 pub mod on_spawn;
+pub mod streaming;
 
 use self::{
-    asset_loading::GltfAssets,
-    home::Home,
+    collider_baking::{ColliderCache, ColliderShape},
+    generator::GeneratorSettings,
     player_controller::{pick_up::UpPickable, Player},
+    streaming::{SourceNode, StreamedNode, StreamingSettings, WorldChunks},
+    time_of_day::Sun,
 };
 
-// Marker components can be attached with the SpawnHook based on a function that is provided with the
-// name of the object.
+// Marker components can be attached with the SpawnHook based on the keywords in the name of the
+// object and its glTF extras.
 //
 // Each object is an empty entity with a SpatialBundle that has one or more children (primitives)
 // that contain meshes and materials.
 //
 // The loading of associated data can be done by querying the marker components.
+//
+// Plain map geometry isn't spawned right away, it is loaded in chunks around the player, see
+// [`streaming`].
 
 pub struct SpawnWorldPlugin;
 
 impl Plugin for SpawnWorldPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(on_spawn::plugin)
-            .add_systems(
-                OnEnter(GameState::Spawning),
-                (spawn_world, spawn_after_world).chain(),
-            )
-            .init_resource::<SpawnHook>();
+        app.register_spawn_keyword("Bicycle", |commands, extras| {
+            commands.insert(Bicycle);
+
+            if extras.get_bool("illegal") == Some(true) {
+                commands.insert(Illegal);
+            }
+        })
+        // the rules talk about the colors of bicycles, they are part of their names
+        .register_spawn_descriptors(&BICYCLE_COLORS)
+        .register_spawn_keyword("Illegal", |commands, _| {
+            commands.insert(Illegal);
+        })
+        .register_spawn_keyword("Player", |commands, _| {
+            commands.insert(Player);
+        })
+        .register_spawn_keyword("Car", |commands, _| {
+            commands.insert(Car);
+        })
+        .register_spawn_keyword("Trash", |commands, _| {
+            commands.insert(Trash);
+        })
+        .register_spawn_keyword("ParkingSpot", |commands, extras| {
+            commands.insert(ParkingSpot {
+                illegal: extras.get_bool("illegal").unwrap_or(false),
+            });
+        })
+        .add_plugins((on_spawn::plugin, streaming::plugin))
+        .add_systems(
+            OnEnter(GameState::Spawning),
+            (spawn_world, spawn_after_world).chain(),
+        )
+        .init_resource::<SpawnHook>()
+        .init_resource::<GeneratorSettings>()
+        .init_resource::<ColliderCache>();
     }
 }
 
-type Hook = Box<dyn Fn(&str, &mut EntityCommands) + Send + Sync + 'static>;
-
-#[derive(Resource)]
-pub struct SpawnHook(Hook);
-
-impl Default for SpawnHook {
-    fn default() -> Self {
-        let hook: Hook = Box::new(|name, commands| {
-            let class = name.split('.').next().unwrap_or(name);
-
-            for keyword in class.split(' ') {
-                match keyword {
-                    "Bicycle" => {
-                        commands.insert(Bicycle);
-                    }
-                    "Illegal" => {
-                        commands.insert(Illegal);
-                    }
-                    "Player" => {
-                        commands.insert(Player);
-                    }
-                    "Car" => {
-                        commands.insert(Car);
-                    }
-                    "Home" => {
-                        commands.insert(Home);
-                    }
-                    "Trash" => {
-                        commands.insert(Trash);
-                    }
-                    _ => {
-                        commands.insert(MapElement);
-                    }
-                }
+/// Where the world of the current level is spawned from.
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub enum WorldSource {
+    /// A hand built world like `town.glb`.
+    Gltf(Handle<Gltf>),
+    /// A city made of the pieces in `city_pieces.glb`, the same seed always gives the same city.
+    Generated { seed: u64, blocks: UVec2 },
+}
+
+/// The modular pieces generated cities are made of, bicycles parked during a shift are made of
+/// them too. They are loaded with the first level, levels that aren't generated also work without
+/// them.
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub struct CityPieces(pub Handle<Gltf>);
+
+impl CityPieces {
+    pub const PATH: &'static str = "city_pieces.glb";
+}
+
+type Inserter = Box<dyn Fn(&mut EntityCommands, &NodeExtras) + Send + Sync + 'static>;
+
+/// Registry of the keywords that can appear in the names of objects and the components they add.
+///
+/// Plugins register their keywords with [`RegisterSpawnKeyword::register_spawn_keyword`]. Objects
+/// without any registered keyword become [`MapElement`]s.
+#[derive(Resource, Default)]
+pub struct SpawnHook {
+    inserters: HashMap<String, Inserter>,
+    /// Words in names that only describe the object, they don't insert anything.
+    descriptors: HashSet<String>,
+    /// Unknown keywords that were reported already, names repeat a lot.
+    reported: Mutex<HashSet<String>>,
+}
+
+impl SpawnHook {
+    pub fn register(
+        &mut self,
+        keyword: &str,
+        inserter: impl Fn(&mut EntityCommands, &NodeExtras) + Send + Sync + 'static,
+    ) {
+        if self
+            .inserters
+            .insert(keyword.to_string(), Box::new(inserter))
+            .is_some()
+        {
+            warn!("spawn keyword `{}` was registered twice", keyword);
+        }
+    }
+
+    pub fn describe(&mut self, word: &str) {
+        self.descriptors.insert(word.to_string());
+    }
+
+    pub fn is_registered(&self, keyword: &str) -> bool {
+        return self.inserters.contains_key(keyword);
+    }
+
+    /// Whether any of the keywords in the name is registered.
+    pub fn has_keyword(&self, name: &str) -> bool {
+        return Self::keywords(name).any(|keyword| self.is_registered(keyword));
+    }
+
+    /// The keywords in the name of an object e.g. `Bicycle Illegal.003` has `Bicycle` and `Illegal`.
+    /// Collider suffixes like the one in `Lamp-hull` aren't part of the keyword.
+    pub fn keywords(name: &str) -> impl Iterator<Item = &str> {
+        let class = name.split('.').next().unwrap_or(name);
+        return class
+            .split(' ')
+            .map(|keyword| ColliderShape::split_suffix(keyword).0)
+            .filter(|keyword| !keyword.is_empty());
+    }
+
+    /// Runs the inserters of the keywords in the name, returns the keywords that are unknown.
+    /// Descriptors aren't unknown.
+    pub fn apply<'a>(
+        &self,
+        name: &'a str,
+        extras: &NodeExtras,
+        commands: &mut EntityCommands,
+    ) -> Vec<&'a str> {
+        let mut unknown = Vec::new();
+
+        for keyword in Self::keywords(name) {
+            match self.inserters.get(keyword) {
+                Some(inserter) => inserter(commands, extras),
+                None if self.descriptors.contains(keyword) => {}
+                None => unknown.push(keyword),
             }
-        });
+        }
 
-        return Self(hook);
+        return unknown;
+    }
+
+    /// Warns about an unknown keyword the first time it's seen.
+    fn report_unknown(&self, keyword: &str, name: &str) {
+        let mut reported = self.reported.lock().unwrap();
+
+        if !reported.contains(keyword) {
+            warn!("unknown spawn keyword `{}` in `{}`", keyword, name);
+            reported.insert(keyword.to_string());
+        }
+    }
+}
+
+pub trait RegisterSpawnKeyword {
+    /// Makes objects that have the keyword in their name run the inserter when they are spawned.
+    fn register_spawn_keyword(
+        &mut self,
+        keyword: &str,
+        inserter: impl Fn(&mut EntityCommands, &NodeExtras) + Send + Sync + 'static,
+    ) -> &mut Self;
+
+    /// Makes the words known without inserting anything, e.g. the colors of bicycles.
+    fn register_spawn_descriptors(&mut self, words: &[&str]) -> &mut Self;
+}
+
+impl RegisterSpawnKeyword for App {
+    fn register_spawn_keyword(
+        &mut self,
+        keyword: &str,
+        inserter: impl Fn(&mut EntityCommands, &NodeExtras) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_insert_with(SpawnHook::default)
+            .register(keyword, inserter);
+
+        return self;
+    }
+
+    fn register_spawn_descriptors(&mut self, words: &[&str]) -> &mut Self {
+        let mut spawn_hook = self
+            .world_mut()
+            .get_resource_or_insert_with(SpawnHook::default);
+
+        for word in words {
+            spawn_hook.describe(word);
+        }
+
+        return self;
+    }
+}
+
+/// Custom properties of a glTF node e.g. `{"illegal": true, "value": 3}` set in Blender.
+#[derive(Component, Clone, Default, Debug)]
+pub struct NodeExtras(serde_json::Map<String, serde_json::Value>);
+
+impl NodeExtras {
+    /// Parses the extras of a node, extras that are not a json object are reported and ignored.
+    pub fn parse(name: &str, extras: Option<&GltfExtras>) -> Self {
+        let Some(extras) = extras else {
+            return Self::default();
+        };
+
+        return match serde_json::from_str(&extras.value) {
+            Ok(serde_json::Value::Object(map)) => Self(map),
+            _ => {
+                warn!(
+                    "extras of `{}` are not a json object: {}",
+                    name, extras.value
+                );
+                Self::default()
+            }
+        };
+    }
+
+    pub fn with(mut self, key: &str, value: impl Into<serde_json::Value>) -> Self {
+        self.0.insert(key.to_string(), value.into());
+        return self;
+    }
+
+    /// Adds the properties of `other`, overwriting the ones that are set in both.
+    pub fn extend(&mut self, other: NodeExtras) {
+        self.0.extend(other.0);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.0.is_empty();
+    }
+
+    pub fn get(&self, key: &str) -> Option<&serde_json::Value> {
+        return self.0.get(key);
+    }
+
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        return self.get(key).and_then(|value| value.as_bool());
+    }
+
+    pub fn get_u64(&self, key: &str) -> Option<u64> {
+        return self.get(key).and_then(|value| value.as_u64());
+    }
+
+    pub fn get_f64(&self, key: &str) -> Option<f64> {
+        return self.get(key).and_then(|value| value.as_f64());
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        return self.get(key).and_then(|value| value.as_str());
     }
 }
 
 pub fn spawn_world(
-    gltf_assets: Res<GltfAssets>,
+    world_source: Res<WorldSource>,
+    generator_settings: Res<GeneratorSettings>,
+    streaming_settings: Res<StreamingSettings>,
+    city_pieces: Res<CityPieces>,
     gltfs: Res<Assets<Gltf>>,
     gltf_nodes: Res<Assets<GltfNode>>,
     gltf_meshes: Res<Assets<GltfMesh>>,
+    meshes: Res<Assets<Mesh>>,
     mut commands: Commands,
     spawn_hook: Res<SpawnHook>,
 ) {
-    let world = gltfs.get(&gltf_assets.world).unwrap();
+    let mut nodes = Vec::new();
 
-    for (name, gltf_node) in world
-        .named_nodes
-        .iter()
-        .map(|(k, v)| (k.clone(), gltf_nodes.get(v).unwrap()))
-    {
-        let gltf_mesh = if let Some(handle) = &gltf_node.mesh {
-            gltf_meshes.get(handle).unwrap()
+    match world_source.as_ref() {
+        WorldSource::Gltf(handle) => {
+            let world = gltfs.get(handle).unwrap();
+
+            for (name, node_handle) in world.named_nodes.iter() {
+                let gltf_node = gltf_nodes.get(node_handle).unwrap();
+
+                nodes.push(StreamedNode {
+                    name: name.to_string(),
+                    transform: gltf_node.transform,
+                    node: node_handle.clone(),
+                    extras: NodeExtras::parse(name, gltf_node.extras.as_ref()),
+                });
+            }
+        }
+        WorldSource::Generated { seed, blocks } => {
+            let city_pieces = gltfs.get(&city_pieces.0).unwrap();
+
+            for placed in generator::generate(*seed, *blocks, &generator_settings) {
+                let node_name = placed.piece.node_name();
+                let Some((node_handle, gltf_node)) = city_pieces
+                    .named_nodes
+                    .get(node_name)
+                    .and_then(|handle| Some((handle, gltf_nodes.get(handle)?)))
+                else {
+                    warn!("city piece `{}` is missing", node_name);
+                    continue;
+                };
+
+                let mut extras = NodeExtras::parse(node_name, gltf_node.extras.as_ref());
+                extras.extend(placed.extras);
+
+                nodes.push(StreamedNode {
+                    name: placed.name,
+                    transform: placed.transform * gltf_node.transform,
+                    node: node_handle.clone(),
+                    extras,
+                });
+            }
+        }
+    }
+
+    let mut world_chunks = WorldChunks::new(streaming_settings.chunk_size);
+
+    for node in nodes {
+        // plain map geometry is spawned when the player gets close to it
+        let node = if spawn_hook.has_keyword(&node.name) {
+            node
         } else {
-            println!("{} - empty node", name);
-            continue;
+            match world_chunks.insert(node, &gltf_nodes, &gltf_meshes, &meshes) {
+                Some(node) => node,
+                None => continue,
+            }
         };
 
-        let entity = commands
-            .spawn((
-                Name::new(name.to_string()),
-                SpatialBundle {
-                    transform: gltf_node.transform,
-                    ..default()
-                },
-            ))
-            .id();
+        spawn_node(
+            &node.name,
+            node.transform,
+            &node.node,
+            node.extras,
+            &gltf_nodes,
+            &gltf_meshes,
+            &spawn_hook,
+            &mut commands,
+        );
+    }
 
-        for primitive in gltf_mesh.primitives.iter() {
-            let material = if let Some(it) = &primitive.material {
-                it.clone()
-            } else {
-                Handle::<StandardMaterial>::default()
-            };
-
-            let primitive_entity = commands
-                .spawn(PbrBundle {
-                    mesh: primitive.mesh.clone(),
-                    material,
-                    ..default()
-                })
-                .id();
+    commands.insert_resource(world_chunks);
+}
+
+/// Spawns an object with the meshes of the node, the spawn keywords are taken from the name.
+/// Empty nodes are only spawned when they have a registered keyword, e.g. markers.
+pub(crate) fn spawn_node(
+    name: &str,
+    transform: Transform,
+    node: &Handle<GltfNode>,
+    extras: NodeExtras,
+    gltf_nodes: &Assets<GltfNode>,
+    gltf_meshes: &Assets<GltfMesh>,
+    spawn_hook: &SpawnHook,
+    commands: &mut Commands,
+) -> Option<Entity> {
+    let gltf_node = gltf_nodes.get(node)?;
+
+    if gltf_node.mesh.is_none() && !spawn_hook.has_keyword(name) {
+        debug!(
+            "`{}` is an empty node without a keyword, it isn't spawned",
+            name
+        );
+        return None;
+    }
 
-            commands.entity(entity).add_child(primitive_entity);
+    let entity = commands
+        .spawn((
+            Name::new(name.to_string()),
+            SpatialBundle {
+                transform,
+                ..default()
+            },
+            SourceNode(node.clone()),
+        ))
+        .id();
+
+    spawn_primitives(entity, gltf_node, gltf_meshes, commands);
+
+    let mut entity_commands = commands.entity(entity);
+    let unknown = spawn_hook.apply(name, &extras, &mut entity_commands);
+
+    if !spawn_hook.has_keyword(name) {
+        // plain map geometry
+        entity_commands.insert(MapElement);
+    } else {
+        for keyword in unknown {
+            spawn_hook.report_unknown(keyword, name);
         }
+    }
+
+    if !extras.is_empty() {
+        entity_commands.insert(extras);
+    }
+
+    return Some(entity);
+}
+
+/// Adds the primitives of the mesh of the node as children of the entity.
+pub(crate) fn spawn_primitives(
+    entity: Entity,
+    gltf_node: &GltfNode,
+    gltf_meshes: &Assets<GltfMesh>,
+    commands: &mut Commands,
+) {
+    let gltf_mesh = gltf_node
+        .mesh
+        .as_ref()
+        .map(|handle| gltf_meshes.get(handle).unwrap());
 
-        spawn_hook.0(&*name, &mut commands.entity(entity));
+    let primitives = gltf_mesh
+        .iter()
+        .flat_map(|gltf_mesh| gltf_mesh.primitives.iter());
+
+    for primitive in primitives {
+        let material = if let Some(it) = &primitive.material {
+            it.clone()
+        } else {
+            Handle::<StandardMaterial>::default()
+        };
+
+        let primitive_entity = commands
+            .spawn(PbrBundle {
+                mesh: primitive.mesh.clone(),
+                material,
+                ..default()
+            })
+            .id();
+
+        commands.entity(entity).add_child(primitive_entity);
     }
 }
 
@@ -144,21 +461,24 @@ pub fn spawn_after_world(
         brightness: 600.0,
     });
 
-    // sunlight
-    commands.spawn(DirectionalLightBundle {
-        directional_light: DirectionalLight {
-            illuminance: light_consts::lux::OVERCAST_DAY,
-            shadows_enabled: true,
+    // sunlight, moved by the time of day
+    commands.spawn((
+        Sun,
+        DirectionalLightBundle {
+            directional_light: DirectionalLight {
+                illuminance: light_consts::lux::OVERCAST_DAY,
+                shadows_enabled: true,
 
+                ..default()
+            },
+            transform: Transform {
+                translation: Vec3::new(0.0, 2.0, 0.0),
+                rotation: Quat::from_euler(EulerRot::XYZ, 4.0, -0.7, 0.0),
+                ..default()
+            },
             ..default()
         },
-        transform: Transform {
-            translation: Vec3::new(0.0, 2.0, 0.0),
-            rotation: Quat::from_euler(EulerRot::XYZ, 4.0, -0.7, 0.0),
-            ..default()
-        },
-        ..default()
-    });
+    ));
 
     // a cube to move around
     commands
@@ -168,7 +488,7 @@ pub fn spawn_after_world(
                 ..default()
             },
             RigidBody::Dynamic,
-            UpPickable,
+            UpPickable::Hand,
         ))
         .with_children(|parent| {
             parent.spawn((
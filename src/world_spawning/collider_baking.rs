@@ -0,0 +1,327 @@
+use avian3d::{parry::shape::SharedShape, prelude::Collider};
+use bevy::{
+    prelude::*,
+    render::mesh::{Indices, VertexAttributeValues},
+    utils::HashMap,
+};
+use serde::{Deserialize, Serialize};
+
+use super::NodeExtras;
+
+// Map geometry gets a collider per primitive. Which kind of collider is chosen per node, either
+// with the `collider` extra e.g. `{"collider": "convex_hull"}` or with a suffix on a keyword of the
+// name e.g. `Fence-hull.002`. Nodes without either get a trimesh like before.
+//
+// Convex hulls and convex decompositions are slow to compute, so they are baked once and written
+// to `cache/colliders`, keyed by a hash of the mesh. Later loads read them back.
+
+pub const COLLIDER_CACHE_DIR: &str = "cache/colliders";
+
+/// Bump this when the format of the baked colliders changes, old cache files are then ignored.
+const CACHE_VERSION: u32 = 1;
+
+/// The kind of collider the primitives of a map node get.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColliderShape {
+    /// The bounding box of the mesh, for simple props.
+    Cuboid,
+    ConvexHull,
+    /// A set of convex hulls that follow the mesh, for props with holes or dents.
+    ConvexDecomposition,
+    /// The exact mesh, for the ground and buildings.
+    #[default]
+    Trimesh,
+}
+
+impl ColliderShape {
+    const SUFFIXES: [(&'static str, ColliderShape); 4] = [
+        ("-box", ColliderShape::Cuboid),
+        ("-hull", ColliderShape::ConvexHull),
+        ("-convex", ColliderShape::ConvexDecomposition),
+        ("-trimesh", ColliderShape::Trimesh),
+    ];
+
+    /// Splits the collider suffix off a keyword e.g. `Fence-hull` is `Fence` with a convex hull.
+    pub fn split_suffix(keyword: &str) -> (&str, Option<ColliderShape>) {
+        for (suffix, shape) in Self::SUFFIXES {
+            if let Some(keyword) = keyword.strip_suffix(suffix) {
+                return (keyword, Some(shape));
+            }
+        }
+
+        return (keyword, None);
+    }
+
+    /// The collider shape of a node, the `collider` extra wins over the name.
+    pub fn for_node(name: &str, extras: Option<&NodeExtras>) -> Self {
+        if let Some(value) = extras.and_then(|extras| extras.get("collider")) {
+            match serde_json::from_value(value.clone()) {
+                Ok(shape) => return shape,
+                Err(err) => warn!(
+                    "Failed to read the collider of `{}`, using its name. Reason: {:#}",
+                    name, err
+                ),
+            }
+        }
+
+        let class = name.split('.').next().unwrap_or(name);
+
+        return class
+            .split(' ')
+            .find_map(|keyword| Self::split_suffix(keyword).1)
+            .unwrap_or_default();
+    }
+}
+
+/// Collider geometry that can be written to disk.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum BakedCollider {
+    ConvexHull { points: Vec<[f32; 3]> },
+    ConvexDecomposition { hulls: Vec<Vec<[f32; 3]>> },
+}
+
+impl BakedCollider {
+    fn bake(shape: ColliderShape, vertices: Vec<Vec3>, triangles: Vec<[u32; 3]>) -> Option<Self> {
+        return match shape {
+            ColliderShape::ConvexHull => {
+                let hull = Collider::convex_hull(vertices)?;
+
+                Some(BakedCollider::ConvexHull {
+                    points: hull_points(hull.shape())?,
+                })
+            }
+            ColliderShape::ConvexDecomposition => {
+                let decomposition = Collider::convex_decomposition(vertices, triangles);
+                let compound = decomposition.shape().as_compound()?;
+
+                let hulls = compound
+                    .shapes()
+                    .iter()
+                    .map(|(isometry, hull)| {
+                        let points = hull.as_convex_polyhedron()?.points();
+
+                        Some(
+                            points
+                                .iter()
+                                .map(|point| {
+                                    let point = isometry * point;
+                                    [point.x, point.y, point.z]
+                                })
+                                .collect(),
+                        )
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+
+                Some(BakedCollider::ConvexDecomposition { hulls })
+            }
+            ColliderShape::Cuboid | ColliderShape::Trimesh => None,
+        };
+    }
+
+    fn collider(&self) -> Option<Collider> {
+        return match self {
+            BakedCollider::ConvexHull { points } => Collider::convex_hull(
+                points
+                    .iter()
+                    .map(|point| Vec3::from_array(*point))
+                    .collect(),
+            ),
+            BakedCollider::ConvexDecomposition { hulls } => {
+                let hulls = hulls
+                    .iter()
+                    .map(|points| {
+                        let hull = Collider::convex_hull(
+                            points
+                                .iter()
+                                .map(|point| Vec3::from_array(*point))
+                                .collect(),
+                        )?;
+
+                        Some((Vec3::ZERO, Quat::IDENTITY, hull))
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+
+                Some(Collider::compound(hulls))
+            }
+        };
+    }
+}
+
+fn hull_points(shape: &SharedShape) -> Option<Vec<[f32; 3]>> {
+    return Some(
+        shape
+            .as_convex_polyhedron()?
+            .points()
+            .iter()
+            .map(|point| [point.x, point.y, point.z])
+            .collect(),
+    );
+}
+
+/// Colliders that were baked before, in memory and on disk. The web build only keeps them in
+/// memory.
+#[derive(Resource, Default)]
+pub struct ColliderCache {
+    baked: HashMap<u64, BakedCollider>,
+}
+
+impl ColliderCache {
+    /// Builds the collider of a mesh, baked colliders are reused when the mesh didn't change.
+    /// Falls back to a trimesh when the mesh can't be baked into the shape.
+    pub fn collider(&mut self, name: &str, mesh: &Mesh, shape: ColliderShape) -> Option<Collider> {
+        let (vertices, triangles) = mesh_geometry(mesh)?;
+
+        let collider = match shape {
+            // cuboids and trimeshes are quick to build from the mesh, they aren't worth caching
+            ColliderShape::Cuboid => cuboid(&vertices),
+            ColliderShape::Trimesh => return Some(Collider::trimesh(vertices, triangles)),
+            ColliderShape::ConvexHull | ColliderShape::ConvexDecomposition => self
+                .baked(shape, &vertices, &triangles)
+                .and_then(|baked| baked.collider()),
+        };
+
+        return match collider {
+            Some(collider) => Some(collider),
+            None => {
+                warn!(
+                    "Failed to make a {:?} collider for `{}`, using a trimesh.",
+                    shape, name
+                );
+                Some(Collider::trimesh(vertices, triangles))
+            }
+        };
+    }
+
+    fn baked(
+        &mut self,
+        shape: ColliderShape,
+        vertices: &[Vec3],
+        triangles: &[[u32; 3]],
+    ) -> Option<&BakedCollider> {
+        let key = mesh_hash(shape, vertices, triangles);
+
+        if !self.baked.contains_key(&key) {
+            let baked = match read_baked(key) {
+                Some(baked) => baked,
+                None => {
+                    let baked = BakedCollider::bake(shape, vertices.to_vec(), triangles.to_vec())?;
+                    write_baked(key, &baked);
+                    baked
+                }
+            };
+
+            self.baked.insert(key, baked);
+        }
+
+        return self.baked.get(&key);
+    }
+}
+
+fn read_baked(key: u64) -> Option<BakedCollider> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        use std::{fs, path::Path};
+
+        let path = Path::new(COLLIDER_CACHE_DIR).join(format!("{:016x}.json", key));
+        let contents = fs::read_to_string(path).ok()?;
+
+        return match serde_json::from_str(&contents) {
+            Ok(baked) => Some(baked),
+            Err(err) => {
+                warn!(
+                    "Failed to read cached collider, rebaking. Reason: {:#}",
+                    err
+                );
+                None
+            }
+        };
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    return None;
+}
+
+fn write_baked(key: u64, baked: &BakedCollider) {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        use std::{fs, io, path::Path};
+
+        let path = Path::new(COLLIDER_CACHE_DIR).join(format!("{:016x}.json", key));
+
+        let written = fs::create_dir_all(COLLIDER_CACHE_DIR).and_then(|_| {
+            let contents = serde_json::to_string(baked).map_err(io::Error::from)?;
+            fs::write(path, contents)
+        });
+
+        if let Err(err) = written {
+            warn!("Failed to cache collider. Reason: {:#}", err);
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    let _ = (key, baked);
+}
+
+/// The positions and triangles of a mesh.
+fn mesh_geometry(mesh: &Mesh) -> Option<(Vec<Vec3>, Vec<[u32; 3]>)> {
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return None;
+    };
+
+    let vertices: Vec<Vec3> = positions.iter().map(|p| Vec3::from_array(*p)).collect();
+
+    let indices: Vec<u32> = match mesh.indices() {
+        Some(Indices::U16(indices)) => indices.iter().map(|i| *i as u32).collect(),
+        Some(Indices::U32(indices)) => indices.clone(),
+        None => (0..vertices.len() as u32).collect(),
+    };
+
+    let triangles = indices
+        .chunks_exact(3)
+        .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+        .collect();
+
+    return Some((vertices, triangles));
+}
+
+/// The bounding box of the vertices.
+fn cuboid(vertices: &[Vec3]) -> Option<Collider> {
+    let min = vertices.iter().copied().reduce(Vec3::min)?;
+    let max = vertices.iter().copied().reduce(Vec3::max)?;
+    let size = max - min;
+
+    let center = (min + max) / 2.0;
+    let cuboid = Collider::cuboid(size.x, size.y, size.z);
+
+    return Some(Collider::compound(vec![(center, Quat::IDENTITY, cuboid)]));
+}
+
+/// FNV-1a hash of the geometry, it has to be the same from one run of the game to the next. The
+/// hashers of std don't promise that across Rust releases, so they can't key files on disk.
+fn mesh_hash(shape: ColliderShape, vertices: &[Vec3], triangles: &[[u32; 3]]) -> u64 {
+    const PRIME: u64 = 0x100000001b3;
+
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let mut write = |word: u32| {
+        for byte in word.to_le_bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(PRIME);
+        }
+    };
+
+    write(CACHE_VERSION);
+    write(shape as u32);
+
+    for coordinate in vertices.iter().flat_map(|vertex| vertex.to_array()) {
+        write(coordinate.to_bits());
+    }
+
+    for index in triangles.iter().flatten() {
+        write(*index);
+    }
+
+    return hash;
+}
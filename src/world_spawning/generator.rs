@@ -0,0 +1,396 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::{prelude::*, utils::HashSet};
+use rand::{rngs::StdRng, seq::IteratorRandom, Rng, SeedableRng};
+
+use super::{
+    on_spawn::{BicycleValue, ParkingSpot},
+    NodeExtras,
+};
+
+// The generated city is a grid of square tiles. Every `block_tiles + 1`th row and column is a road,
+// crossings are where two of them meet and the tiles in between form the blocks. The outer ring of
+// each block is sidewalk with the street furniture and parked bicycles, the inside is buildings.
+//
+// Pieces are spawned with names made of spawn keywords, just like the objects in `town.glb`, so
+// the generated city gets the same marker components as the hand built one.
+
+/// The modular pieces of a generated city, each one is a node in `city_pieces.glb` that is
+/// modelled around the origin.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum CityPiece {
+    Road,
+    Crossing,
+    Sidewalk,
+    Building,
+    Lamp,
+    BikeRack,
+    Trash,
+    GasPumps,
+    GasStation,
+    HomeBuilding,
+    Home,
+    Player,
+    Car,
+    Bicycle,
+    /// An empty node, bicycles are parked here during a shift.
+    ParkingSpot,
+}
+
+impl CityPiece {
+    /// The name of the node of the piece in `city_pieces.glb`.
+    pub fn node_name(&self) -> &'static str {
+        return match self {
+            CityPiece::Road => "Road",
+            CityPiece::Crossing => "Crossing",
+            CityPiece::Sidewalk => "Sidewalk",
+            CityPiece::Building => "Building",
+            CityPiece::Lamp => "Lamp",
+            CityPiece::BikeRack => "BikeRack",
+            CityPiece::Trash => "Trash",
+            CityPiece::GasPumps => "GasPumps",
+            CityPiece::GasStation => "GasStation",
+            CityPiece::HomeBuilding => "HomeBuilding",
+            CityPiece::Home => "Home",
+            CityPiece::Player => "Player",
+            CityPiece::Car => "Car",
+            CityPiece::Bicycle => "Bicycle",
+            CityPiece::ParkingSpot => "ParkingSpot",
+        };
+    }
+}
+
+/// A piece placed in the city. The name decides which spawn keywords apply to it.
+#[derive(Clone, Debug)]
+pub struct PlacedPiece {
+    pub piece: CityPiece,
+    pub name: String,
+    pub transform: Transform,
+    pub extras: NodeExtras,
+}
+
+#[derive(Resource, Clone, Debug)]
+pub struct GeneratorSettings {
+    /// Length of the side of a tile, the pieces are modelled to fit one tile.
+    pub tile_size: f32,
+    /// Number of tiles along the side of a block, without the roads around it.
+    pub block_tiles: u32,
+    /// Every gas station takes a block of its own, small cities have fewer of them.
+    pub gas_stations: u32,
+    /// Every how many sidewalk tiles a lamp is placed.
+    pub lamp_spacing: u32,
+    pub rack_chance: f32,
+    pub max_bicycles_per_rack: u32,
+    pub trash_chance: f32,
+    /// Chance of a bicycle being parked illegally on a sidewalk tile, away from the racks.
+    pub stray_bicycle_chance: f32,
+}
+
+impl Default for GeneratorSettings {
+    fn default() -> Self {
+        return Self {
+            tile_size: 8.0,
+            block_tiles: 4,
+            gas_stations: 1,
+            lamp_spacing: 3,
+            rack_chance: 0.25,
+            max_bicycles_per_rack: 3,
+            trash_chance: 0.2,
+            stray_bicycle_chance: 0.15,
+        };
+    }
+}
+
+/// Lays out a city of `blocks` blocks, the same seed and settings always give the same layout.
+pub fn generate(seed: u64, blocks: UVec2, settings: &GeneratorSettings) -> Vec<PlacedPiece> {
+    let blocks = blocks.max(UVec2::ONE);
+    let mut generator = Generator {
+        rng: StdRng::seed_from_u64(seed),
+        settings,
+        pieces: Vec::new(),
+        tiles_since_lamp: 0,
+    };
+
+    let all_blocks = (0..blocks.x).flat_map(move |x| (0..blocks.y).map(move |y| UVec2::new(x, y)));
+    let mut special_blocks = all_blocks
+        .clone()
+        .choose_multiple(&mut generator.rng, 1 + settings.gas_stations as usize)
+        .into_iter();
+
+    let home_block = special_blocks.next().unwrap();
+    let gas_station_blocks: HashSet<UVec2> = special_blocks.collect();
+
+    for block in all_blocks {
+        if block == home_block {
+            generator.block_center(block, CityPiece::HomeBuilding, CityPiece::Home);
+        } else if gas_station_blocks.contains(&block) {
+            generator.block_center(block, CityPiece::GasPumps, CityPiece::GasStation);
+        }
+    }
+
+    let period = settings.block_tiles + 1;
+    let size = blocks * period + 1;
+
+    for x in 0..size.x {
+        for y in 0..size.y {
+            let tile = UVec2::new(x, y);
+            let block = tile / period;
+            let local = tile % period;
+
+            match (local.x == 0, local.y == 0) {
+                (true, true) => {
+                    generator.place(CityPiece::Crossing, tile_transform(tile, settings))
+                }
+                // roads are modelled along the z axis
+                (true, false) => generator.place(CityPiece::Road, tile_transform(tile, settings)),
+                (false, true) => generator.place(
+                    CityPiece::Road,
+                    tile_transform(tile, settings).with_rotation(Quat::from_rotation_y(FRAC_PI_2)),
+                ),
+                (false, false) => match road_side(local, settings.block_tiles) {
+                    Some(facing) => {
+                        let furnished = block != home_block && !gas_station_blocks.contains(&block);
+                        generator.sidewalk(tile, facing, furnished);
+                    }
+                    None if block == home_block || gas_station_blocks.contains(&block) => {}
+                    None => {
+                        let turns = generator.rng.gen_range(0..4) as f32;
+                        generator.place(
+                            CityPiece::Building,
+                            tile_transform(tile, settings)
+                                .with_rotation(Quat::from_rotation_y(turns * FRAC_PI_2)),
+                        );
+                    }
+                },
+            }
+        }
+    }
+
+    // start next to home, with the car on the road in front of it
+    let home_tile = home_block * period;
+    let player_tile = home_tile + UVec2::new(1, period / 2);
+    let car_tile = home_tile + UVec2::new(0, period / 2);
+
+    generator.place(
+        CityPiece::Player,
+        tile_transform(player_tile, settings) * Transform::from_translation(Vec3::Y),
+    );
+    generator.place(
+        CityPiece::Car,
+        tile_transform(car_tile, settings) * Transform::from_translation(Vec3::Y),
+    );
+
+    return generator.pieces;
+}
+
+struct Generator<'a> {
+    rng: StdRng,
+    settings: &'a GeneratorSettings,
+    pieces: Vec<PlacedPiece>,
+    tiles_since_lamp: u32,
+}
+
+impl Generator<'_> {
+    fn place(&mut self, piece: CityPiece, transform: Transform) {
+        self.place_named(piece, piece.node_name(), transform, NodeExtras::default());
+    }
+
+    fn place_named(
+        &mut self,
+        piece: CityPiece,
+        keywords: &str,
+        transform: Transform,
+        extras: NodeExtras,
+    ) {
+        // numbered like duplicates in blender, the keywords are the part before the dot
+        let name = format!("{}.{:03}", keywords, self.pieces.len());
+
+        self.pieces.push(PlacedPiece {
+            piece,
+            name,
+            transform,
+            extras,
+        });
+    }
+
+    /// Places a building and the zone that comes with it in the middle of a block.
+    fn block_center(&mut self, block: UVec2, building: CityPiece, zone: CityPiece) {
+        let period = self.settings.block_tiles + 1;
+        let first_tile = block * period + 1;
+        let last_tile = first_tile + self.settings.block_tiles - 1;
+
+        let center = (tile_transform(first_tile, self.settings).translation
+            + tile_transform(last_tile, self.settings).translation)
+            / 2.0;
+
+        self.place(building, Transform::from_translation(center));
+        self.place(zone, Transform::from_translation(center));
+    }
+
+    fn sidewalk(&mut self, tile: UVec2, facing: Vec3, furnished: bool) {
+        let transform = tile_transform(tile, self.settings).looking_to(facing, Vec3::Y);
+        self.place(CityPiece::Sidewalk, transform);
+
+        if !furnished {
+            return;
+        }
+
+        self.tiles_since_lamp += 1;
+
+        let half_tile = self.settings.tile_size / 2.0;
+        let along = Vec3::new(-facing.z, 0.0, facing.x);
+        let curb = transform.translation + facing * (half_tile - 1.0);
+
+        if self.tiles_since_lamp >= self.settings.lamp_spacing {
+            self.tiles_since_lamp = 0;
+            self.place(CityPiece::Lamp, transform.with_translation(curb));
+        } else if self.rng.gen_bool(self.settings.rack_chance as f64) {
+            let rack = transform.translation + facing * (half_tile - 2.5);
+            self.place(CityPiece::BikeRack, transform.with_translation(rack));
+
+            let spots = self.settings.max_bicycles_per_rack;
+            let count = self.rng.gen_range(0..=spots);
+            for i in 0..spots {
+                let offset = (i as f32 - (spots - 1) as f32 / 2.0) * 0.8;
+                let spot = self.parking_spot(rack + along * offset, facing, false);
+
+                if i < count {
+                    self.bicycle(spot, false);
+                }
+            }
+        } else if self.rng.gen_bool(self.settings.trash_chance as f64) {
+            self.place(
+                CityPiece::Trash,
+                transform.with_translation(curb + along * (half_tile / 2.0)),
+            );
+        }
+
+        // bicycles also get parked along the curb, where they are in the way, tiles of 2 meters or
+        // less have no room to move them around
+        let spread = half_tile - 1.0;
+        let offset = if spread > 0.0 {
+            self.rng.gen_range(-spread..spread)
+        } else {
+            0.0
+        };
+        let spot = self.parking_spot(curb - facing * 0.5 + along * offset, along, true);
+
+        if self.rng.gen_bool(self.settings.stray_bicycle_chance as f64) {
+            self.bicycle(spot, true);
+        }
+    }
+
+    /// Places a parking spot, `length` is the direction the frames of the bicycles point to.
+    fn parking_spot(&mut self, position: Vec3, length: Vec3, illegal: bool) -> Transform {
+        // bicycles are modelled along the x axis
+        let transform = Transform::from_translation(position)
+            .with_rotation(Quat::from_rotation_arc(Vec3::X, length.normalize()));
+
+        self.place_named(
+            CityPiece::ParkingSpot,
+            "ParkingSpot",
+            transform,
+            NodeExtras::default().with("illegal", illegal),
+        );
+
+        return transform;
+    }
+
+    /// Parks a bicycle at a parking spot.
+    fn bicycle(&mut self, spot: Transform, illegal: bool) {
+        let value = BicycleValue::random(&mut self.rng);
+        let keywords = if illegal {
+            "Bicycle Illegal"
+        } else {
+            "Bicycle"
+        };
+
+        let transform = ParkingSpot::bicycle_transform(spot);
+
+        self.place_named(
+            CityPiece::Bicycle,
+            keywords,
+            transform,
+            NodeExtras::default().with("value", value.0),
+        );
+    }
+}
+
+/// The transform of the center of a tile, the first crossing of the city is at the origin.
+fn tile_transform(tile: UVec2, settings: &GeneratorSettings) -> Transform {
+    let position = tile.as_vec2() * settings.tile_size;
+    return Transform::from_xyz(position.x, 0.0, position.y);
+}
+
+/// The direction of the road next to a tile of a block, `None` for tiles inside the block.
+fn road_side(local: UVec2, block_tiles: u32) -> Option<Vec3> {
+    return if local.x == 1 {
+        Some(Vec3::NEG_X)
+    } else if local.x == block_tiles {
+        Some(Vec3::X)
+    } else if local.y == 1 {
+        Some(Vec3::NEG_Z)
+    } else if local.y == block_tiles {
+        Some(Vec3::Z)
+    } else {
+        None
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(pieces: &[PlacedPiece]) -> Vec<(CityPiece, String, Transform)> {
+        return pieces
+            .iter()
+            .map(|placed| (placed.piece, placed.name.clone(), placed.transform))
+            .collect();
+    }
+
+    fn count(pieces: &[PlacedPiece], piece: CityPiece) -> usize {
+        return pieces.iter().filter(|placed| placed.piece == piece).count();
+    }
+
+    #[test]
+    fn same_seed_same_city() {
+        let settings = GeneratorSettings::default();
+        let blocks = UVec2::new(3, 2);
+
+        let city = generate(5, blocks, &settings);
+
+        assert_eq!(layout(&city), layout(&generate(5, blocks, &settings)));
+        assert_ne!(layout(&city), layout(&generate(6, blocks, &settings)));
+    }
+
+    #[test]
+    fn one_home_and_the_requested_gas_stations() {
+        let settings = GeneratorSettings {
+            gas_stations: 2,
+            ..default()
+        };
+
+        for seed in 0..10 {
+            let city = generate(seed, UVec2::new(3, 3), &settings);
+
+            assert_eq!(count(&city, CityPiece::Home), 1);
+            assert_eq!(count(&city, CityPiece::HomeBuilding), 1);
+            assert_eq!(count(&city, CityPiece::GasStation), 2);
+            assert_eq!(count(&city, CityPiece::GasPumps), 2);
+            assert_eq!(count(&city, CityPiece::Player), 1);
+            assert_eq!(count(&city, CityPiece::Car), 1);
+        }
+    }
+
+    #[test]
+    fn small_tiles() {
+        let settings = GeneratorSettings {
+            tile_size: 2.0,
+            stray_bicycle_chance: 1.0,
+            ..default()
+        };
+
+        let city = generate(1, UVec2::new(2, 2), &settings);
+        assert!(count(&city, CityPiece::Bicycle) > 0);
+    }
+}
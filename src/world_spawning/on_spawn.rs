@@ -2,14 +2,18 @@ use bevy::prelude::*;
 
 mod bicycle;
 mod car;
+mod gas_station;
 mod home;
+mod item_pickup;
 mod map;
+mod parking_spot;
 mod player;
 mod trash;
 
 pub use bicycle::*;
 pub use car::*;
 pub use map::*;
+pub use parking_spot::*;
 pub use trash::*;
 
 use crate::GameState;
@@ -17,17 +21,26 @@ use crate::GameState;
 use super::spawn_world;
 
 pub fn plugin(app: &mut App) {
-    app.add_systems(Startup, map::spawn).add_systems(
-        Update,
-        (
-            map::spawn_element,
-            car::spawn,
-            bicycle::spawn,
-            home::spawn,
-            player::spawn,
-            trash::spawn,
+    app.add_systems(Startup, map::spawn)
+        .add_systems(
+            Update,
+            (
+                car::spawn,
+                gas_station::spawn,
+                home::spawn,
+                item_pickup::spawn,
+                parking_spot::spawn,
+                player::spawn,
+                trash::spawn,
+            )
+                .run_if(in_state(GameState::Spawning))
+                .after(spawn_world),
         )
-            .run_if(in_state(GameState::Spawning))
-            .after(spawn_world),
-    );
+        .add_systems(
+            Update,
+            // bicycles and map chunks are also spawned during a shift
+            (map::spawn_element, bicycle::spawn)
+                .run_if(in_state(GameState::Spawning).or_else(in_state(GameState::Playing)))
+                .after(spawn_world),
+        );
 }
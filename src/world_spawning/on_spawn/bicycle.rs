@@ -2,9 +2,14 @@ use avian3d::{
     dynamics::{ccd::SweptCcd, rigid_body::Mass},
     prelude::{Collider, RigidBody},
 };
-use bevy::prelude::*;
+use bevy::{ecs::system::EntityCommands, prelude::*};
+use rand::{thread_rng, Rng};
 
-use crate::lockpicking::{Locked, SlideLinear, SlideSettings};
+use crate::{
+    lockpicking::{LockDifficulty, Locked},
+    resources::Difficulty,
+    world_spawning::NodeExtras,
+};
 
 #[derive(Component)]
 pub struct Bicycle;
@@ -12,34 +17,78 @@ pub struct Bicycle;
 #[derive(Component)]
 pub struct Illegal;
 
-pub(super) fn spawn(q_bicycle: Query<Entity, Added<Bicycle>>, mut commands: Commands) {
-    for bicycle_entity in q_bicycle.iter() {
-        commands
-            .entity(bicycle_entity)
-            .insert((RigidBody::Dynamic, Mass(6.0), SweptCcd::default()))
-            .with_children(|parent| {
-                parent.spawn((
-                    Collider::cuboid(1.6032, 0.58, 0.06),
-                    TransformBundle::from_transform(Transform::from_xyz(-0.017, -0.22, 0.0)),
-                ));
-
-                parent.spawn((
-                    Collider::cuboid(0.7, 0.27, 0.06),
-                    TransformBundle::from_transform(Transform::from_xyz(0.0, 0.2, 0.0)),
-                ));
-
-                parent.spawn((
-                    Collider::cuboid(0.1, 0.1, 0.4),
-                    TransformBundle::from_transform(Transform::from_xyz(0.27, 0.4, 0.0)),
-                ));
-            })
-            .insert(Locked {
-                success_zone_width: 10.0,
-                move_on_good_pick: true,
-                zone_slide_settings: SlideSettings::SlideLinear(SlideLinear {
-                    speed: 10.0,
-                    time_to_target: 1.5,
-                }),
-            });
+/// Colors that appear in the names of bicycles, the rules tell them apart by color.
+pub const BICYCLE_COLORS: [&str; 12] = [
+    "Black", "White", "Red", "Yellow", "Blue", "Green", "Orange", "Pink", "Purple", "Brown",
+    "Gray", "Silver",
+];
+
+/// How much a bicycle is worth, from 1 to [`BicycleValue::MAX`]. Fancier bicycles have harder locks.
+#[derive(Component, Clone, Copy)]
+pub struct BicycleValue(pub u32);
+
+impl BicycleValue {
+    pub const MAX: u32 = 5;
+
+    /// Most bicycles are cheap.
+    pub fn random(rng: &mut impl Rng) -> Self {
+        let value = match rng.gen_range(0..15) {
+            0..=4 => 1,
+            5..=8 => 2,
+            9..=11 => 3,
+            12..=13 => 4,
+            _ => 5,
+        };
+
+        return Self(value);
     }
+
+    /// The value between 0 for the cheapest and 1 for the most expensive bicycles.
+    pub fn fraction(&self) -> f32 {
+        return (self.0.clamp(1, Self::MAX) - 1) as f32 / (Self::MAX - 1) as f32;
+    }
+}
+
+pub(super) fn spawn(
+    q_bicycle: Query<(Entity, Option<&NodeExtras>), Added<Bicycle>>,
+    difficulty: Res<Difficulty>,
+    mut commands: Commands,
+) {
+    let mut rng = thread_rng();
+
+    for (bicycle_entity, extras) in q_bicycle.iter() {
+        // the value can be fixed in the level with a "value" custom property
+        let value = extras
+            .and_then(|extras| extras.get_u64("value"))
+            .map(|value| BicycleValue(value.clamp(1, BicycleValue::MAX as u64) as u32))
+            .unwrap_or_else(|| BicycleValue::random(&mut rng));
+        let lock_difficulty = LockDifficulty::rate(value.fraction(), difficulty.factor());
+
+        let mut bicycle_commands = commands.entity(bicycle_entity);
+        insert_bicycle_physics(&mut bicycle_commands);
+
+        bicycle_commands.insert((value, Locked::random(lock_difficulty, &mut rng)));
+    }
+}
+
+/// Makes the bicycle a dynamic body with colliders, also used when a dormant bicycle is woken up.
+pub(crate) fn insert_bicycle_physics(bicycle_commands: &mut EntityCommands) {
+    bicycle_commands
+        .insert((RigidBody::Dynamic, Mass(6.0), SweptCcd::default()))
+        .with_children(|parent| {
+            parent.spawn((
+                Collider::cuboid(1.6032, 0.58, 0.06),
+                TransformBundle::from_transform(Transform::from_xyz(-0.017, -0.22, 0.0)),
+            ));
+
+            parent.spawn((
+                Collider::cuboid(0.7, 0.27, 0.06),
+                TransformBundle::from_transform(Transform::from_xyz(0.0, 0.2, 0.0)),
+            ));
+
+            parent.spawn((
+                Collider::cuboid(0.1, 0.1, 0.4),
+                TransformBundle::from_transform(Transform::from_xyz(0.27, 0.4, 0.0)),
+            ));
+        });
 }
@@ -0,0 +1,26 @@
+use avian3d::prelude::Collider;
+use bevy::prelude::*;
+
+use crate::player_controller::pick_up::UpPickable;
+
+pub(super) fn spawn(
+    q_item_pickup: Query<(&UpPickable, &Children), Added<UpPickable>>,
+    q_child: Query<&Handle<Mesh>>,
+    meshes: Res<Assets<Mesh>>,
+    mut commands: Commands,
+) {
+    for (up_pickable, children) in q_item_pickup.iter() {
+        // things that go into the hand get their colliders when they are spawned
+        if !matches!(up_pickable, UpPickable::Inventory(_)) {
+            continue;
+        }
+
+        for child_entity in children.iter() {
+            let mesh = meshes.get(q_child.get(*child_entity).unwrap()).unwrap();
+
+            commands
+                .entity(*child_entity)
+                .insert(Collider::trimesh_from_mesh(mesh).unwrap());
+        }
+    }
+}
@@ -1,10 +1,11 @@
-use avian3d::{
-    collision::CollisionMargin,
-    dynamics::ccd::SweptCcd,
-    prelude::{Collider, RigidBody},
-};
+use avian3d::{collision::CollisionMargin, dynamics::ccd::SweptCcd, prelude::RigidBody};
 use bevy::prelude::*;
 
+use crate::world_spawning::{
+    collider_baking::{ColliderCache, ColliderShape},
+    NodeExtras,
+};
+
 #[derive(Resource)]
 pub struct Map(pub Entity);
 
@@ -26,21 +27,29 @@ pub(super) fn spawn(mut commands: Commands) {
 
 pub(super) fn spawn_element(
     map: Res<Map>,
-    q_map_element: Query<(Entity, &Children), Added<MapElement>>,
+    q_map_element: Query<(Entity, &Children, &Name, Option<&NodeExtras>), Added<MapElement>>,
     q_child: Query<&Handle<Mesh>>,
     mut commands: Commands,
     meshes: Res<Assets<Mesh>>,
+    mut collider_cache: ResMut<ColliderCache>,
 ) {
-    for (entity, children) in q_map_element.iter() {
+    for (entity, children, name, extras) in q_map_element.iter() {
         commands.entity(entity).set_parent(map.0);
 
+        let shape = ColliderShape::for_node(name, extras);
+
         // generate colliders for children
         for child in children.iter() {
             let mesh = meshes.get(q_child.get(*child).unwrap()).unwrap();
-            commands.entity(*child).insert((
-                Collider::trimesh_from_mesh(mesh).unwrap(),
-                CollisionMargin(0.05),
-            ));
+
+            let Some(collider) = collider_cache.collider(name, mesh, shape) else {
+                warn!("`{}` has a mesh without positions", name);
+                continue;
+            };
+
+            commands
+                .entity(*child)
+                .insert((collider, CollisionMargin(0.05)));
         }
     }
 }
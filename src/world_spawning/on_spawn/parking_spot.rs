@@ -0,0 +1,34 @@
+use bevy::prelude::*;
+
+/// A place where bicycles get parked, bicycles stand along its x axis.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct ParkingSpot {
+    /// Bicycles parked here are parked illegally.
+    pub illegal: bool,
+}
+
+impl ParkingSpot {
+    /// Height of a parked bicycle above the spot.
+    pub const BICYCLE_HEIGHT: f32 = 0.55;
+
+    /// Where a bicycle parked at the spot is placed.
+    pub fn bicycle_transform(spot_transform: Transform) -> Transform {
+        return spot_transform
+            .with_translation(spot_transform.translation + Vec3::Y * Self::BICYCLE_HEIGHT)
+            .with_scale(Vec3::ONE);
+    }
+}
+
+/// Parking spots are markers, their meshes are only there to place them in the editor.
+pub(super) fn spawn(
+    q_parking_spot: Query<&Children, Added<ParkingSpot>>,
+    mut q_visibility: Query<&mut Visibility>,
+) {
+    for children in q_parking_spot.iter() {
+        for child_entity in children.iter() {
+            if let Ok(mut visibility) = q_visibility.get_mut(*child_entity) {
+                *visibility = Visibility::Hidden;
+            }
+        }
+    }
+}
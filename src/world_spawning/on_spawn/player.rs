@@ -9,7 +9,9 @@ use bevy::{
 use bevy_camera_extras::{CameraControls, CameraDistanceOffset, CameraDistanceOffsetCache, CameraMode};
 
 use crate::{
+    inventory::{Inventory, Item, INVENTORY_SLOTS},
     lockpicking::LockPicker,
+    perception::Perceivable,
     player_controller::{self, Player},
 };
 
@@ -22,6 +24,11 @@ pub(super) fn spawn(q_player: Query<Entity, Added<Player>>, mut commands: Comman
             )
             .with_movement(30.0, 0.92, 7.0, (30.0 as Scalar).to_radians()),
             LockPicker::default(),
+            Inventory::with_items(
+                INVENTORY_SLOTS,
+                &[(Item::Lockpick, 5), (Item::BoltCutters, 1)],
+            ),
+            Perceivable,
             NotShadowCaster,
             NotShadowReceiver,
             Visibility::Hidden,
@@ -14,7 +14,7 @@ pub(super) fn spawn(q_trash: Query<Entity, Added<Trash>>, mut commands: Commands
     for entity in q_trash.iter() {
         commands
             .entity(entity)
-            .insert((RigidBody::Dynamic, Mass(6.0), UpPickable))
+            .insert((RigidBody::Dynamic, Mass(6.0), UpPickable::Hand))
             .with_children(|parent| {
                 parent.spawn((
                     Collider::cuboid(0.63, 1.38, 0.65),
@@ -0,0 +1,313 @@
+use avian3d::prelude::RigidBody;
+use bevy::{
+    gltf::{GltfMesh, GltfNode},
+    prelude::*,
+    utils::HashMap,
+};
+
+use crate::{car_controller::CarController, player_controller::Player, GameState};
+
+use super::{
+    on_spawn::{insert_bicycle_physics, Bicycle},
+    spawn_node, spawn_primitives, NodeExtras, SpawnHook,
+};
+
+// The map is split into square chunks on the ground. The plain geometry of a chunk is spawned when
+// the player or the car gets close to it and despawned again when both are far away, together with
+// the colliders of the geometry. Geometry that is bigger than a chunk, like a ground plane under
+// the whole town, is always loaded.
+//
+// Bicycles carry state that has to survive, like their lock or who owns them, and they move
+// around, so they aren't despawned. Far away bicycles become `Dormant` instead: their meshes and
+// physics are removed, everything else stays on the entity. Other dynamic bodies, like trash cans,
+// become `Dormant` too, they only lose their rigid body so they don't fall through the missing
+// ground. Bodies wake up a bit closer than the chunks are loaded, so the ground around them is
+// always there.
+
+pub fn plugin(app: &mut App) {
+    app.register_type::<StreamingSettings>()
+        .init_resource::<StreamingSettings>()
+        .init_resource::<WorldChunks>()
+        .add_systems(
+            Update,
+            (stream_chunks, stream_bodies)
+                .run_if(in_state(GameState::Spawning).or_else(in_state(GameState::Playing))),
+        );
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct StreamingSettings {
+    /// Length of the side of a chunk, only applies to the next level.
+    pub chunk_size: f32,
+    /// Chunks closer than this to the player or the car are loaded.
+    pub load_distance: f32,
+    /// Chunks further than this from both the player and the car are unloaded. It's a bit more
+    /// than the load distance, so chunks don't flicker in and out at the border.
+    pub unload_distance: f32,
+}
+
+impl Default for StreamingSettings {
+    fn default() -> Self {
+        return Self {
+            chunk_size: 50.0,
+            load_distance: 200.0,
+            unload_distance: 250.0,
+        };
+    }
+}
+
+/// The glTF node an object was spawned from.
+#[derive(Component, Clone)]
+pub struct SourceNode(pub Handle<GltfNode>);
+
+/// A dynamic body in a chunk that isn't loaded. It keeps its state, but has no physics. Bicycles
+/// also have no meshes.
+#[derive(Component)]
+pub struct Dormant;
+
+/// A node of the map that is spawned when its chunk is loaded.
+#[derive(Clone, Debug)]
+pub struct StreamedNode {
+    pub name: String,
+    pub transform: Transform,
+    pub node: Handle<GltfNode>,
+    pub extras: NodeExtras,
+}
+
+#[derive(Default)]
+struct Chunk {
+    nodes: Vec<StreamedNode>,
+    /// The spawned nodes, `None` while the chunk isn't loaded.
+    loaded: Option<Vec<Entity>>,
+}
+
+/// The chunks of the map of the current level.
+#[derive(Resource)]
+pub struct WorldChunks {
+    chunk_size: f32,
+    chunks: HashMap<IVec2, Chunk>,
+}
+
+impl Default for WorldChunks {
+    fn default() -> Self {
+        return Self::new(StreamingSettings::default().chunk_size);
+    }
+}
+
+impl WorldChunks {
+    pub fn new(chunk_size: f32) -> Self {
+        return Self {
+            chunk_size: chunk_size.max(1.0),
+            chunks: HashMap::new(),
+        };
+    }
+
+    /// Adds a node to the chunk its center is in, it is spawned the next time the chunk is loaded.
+    /// Gives the node back when it's too big for a chunk.
+    pub fn insert(
+        &mut self,
+        node: StreamedNode,
+        gltf_nodes: &Assets<GltfNode>,
+        gltf_meshes: &Assets<GltfMesh>,
+        meshes: &Assets<Mesh>,
+    ) -> Option<StreamedNode> {
+        let Some((center, radius)) = node_bounds(&node, gltf_nodes, gltf_meshes, meshes) else {
+            return Some(node);
+        };
+
+        if radius > self.chunk_size / 2.0 {
+            return Some(node);
+        }
+
+        let coordinate = self.chunk_of(center);
+        self.chunks.entry(coordinate).or_default().nodes.push(node);
+
+        return None;
+    }
+
+    pub fn chunk_of(&self, position: Vec3) -> IVec2 {
+        return (position.xz() / self.chunk_size).floor().as_ivec2();
+    }
+
+    /// The nodes of the chunks that aren't loaded right now.
+    pub fn unloaded_nodes(&self) -> impl Iterator<Item = &StreamedNode> {
+        return self
+            .chunks
+            .values()
+            .filter(|chunk| chunk.loaded.is_none())
+            .flat_map(|chunk| chunk.nodes.iter());
+    }
+
+    /// Horizontal distance from the closest anchor to the closest point of the chunk.
+    fn distance(&self, coordinate: IVec2, anchors: &[Vec2]) -> f32 {
+        let min = coordinate.as_vec2() * self.chunk_size;
+        let max = min + self.chunk_size;
+
+        return anchors
+            .iter()
+            .map(|anchor| anchor.distance(anchor.clamp(min, max)))
+            .fold(f32::INFINITY, f32::min);
+    }
+}
+
+/// The world space center of the meshes of a node and the radius of a sphere around them.
+fn node_bounds(
+    node: &StreamedNode,
+    gltf_nodes: &Assets<GltfNode>,
+    gltf_meshes: &Assets<GltfMesh>,
+    meshes: &Assets<Mesh>,
+) -> Option<(Vec3, f32)> {
+    let gltf_mesh = gltf_meshes.get(gltf_nodes.get(&node.node)?.mesh.as_ref()?)?;
+
+    let (min, max) = gltf_mesh
+        .primitives
+        .iter()
+        .filter_map(|primitive| meshes.get(&primitive.mesh)?.compute_aabb())
+        .map(|aabb| (Vec3::from(aabb.min()), Vec3::from(aabb.max())))
+        .reduce(|(min_a, max_a), (min_b, max_b)| (min_a.min(min_b), max_a.max(max_b)))?;
+
+    let center = node.transform.transform_point((min + max) / 2.0);
+    let radius = (max - min).length() / 2.0 * node.transform.scale.max_element();
+
+    return Some((center, radius));
+}
+
+/// Positions the chunks are loaded around, the player and the car.
+fn anchors(q_anchor: &Query<&Transform, Or<(With<Player>, With<CarController>)>>) -> Vec<Vec2> {
+    return q_anchor
+        .iter()
+        .map(|transform| transform.translation.xz())
+        .collect();
+}
+
+fn stream_chunks(
+    settings: Res<StreamingSettings>,
+    mut world_chunks: ResMut<WorldChunks>,
+    q_anchor: Query<&Transform, Or<(With<Player>, With<CarController>)>>,
+    gltf_nodes: Res<Assets<GltfNode>>,
+    gltf_meshes: Res<Assets<GltfMesh>>,
+    spawn_hook: Res<SpawnHook>,
+    mut commands: Commands,
+) {
+    let anchors = anchors(&q_anchor);
+    if anchors.is_empty() {
+        return;
+    }
+
+    let unload_distance = settings.unload_distance.max(settings.load_distance);
+    let coordinates: Vec<IVec2> = world_chunks.chunks.keys().copied().collect();
+
+    for coordinate in coordinates {
+        let distance = world_chunks.distance(coordinate, &anchors);
+        let chunk = world_chunks.chunks.get_mut(&coordinate).unwrap();
+
+        match chunk.loaded.take() {
+            None if distance <= settings.load_distance => {
+                let entities = chunk
+                    .nodes
+                    .iter()
+                    .filter_map(|node| {
+                        spawn_node(
+                            &node.name,
+                            node.transform,
+                            &node.node,
+                            node.extras.clone(),
+                            &gltf_nodes,
+                            &gltf_meshes,
+                            &spawn_hook,
+                            &mut commands,
+                        )
+                    })
+                    .collect();
+
+                chunk.loaded = Some(entities);
+            }
+            Some(entities) if distance > unload_distance => {
+                for entity in entities {
+                    if let Some(entity_commands) = commands.get_entity(entity) {
+                        entity_commands.despawn_recursive();
+                    }
+                }
+            }
+            loaded => chunk.loaded = loaded,
+        }
+    }
+}
+
+fn stream_bodies(
+    settings: Res<StreamingSettings>,
+    world_chunks: Res<WorldChunks>,
+    q_anchor: Query<&Transform, Or<(With<Player>, With<CarController>)>>,
+    // bodies that were just spawned get their physics first
+    q_body: Query<
+        (
+            Entity,
+            &Transform,
+            Option<&RigidBody>,
+            Option<&SourceNode>,
+            Has<Bicycle>,
+            Has<Dormant>,
+        ),
+        (
+            Or<(With<RigidBody>, With<Dormant>)>,
+            Without<Player>,
+            Without<CarController>,
+            // the transform of a child isn't where it is in the world
+            Without<Parent>,
+        ),
+    >,
+    gltf_nodes: Res<Assets<GltfNode>>,
+    gltf_meshes: Res<Assets<GltfMesh>>,
+    mut commands: Commands,
+) {
+    let anchors = anchors(&q_anchor);
+    if anchors.is_empty() {
+        return;
+    }
+
+    // the ground under a body can be in the next chunk
+    let margin = world_chunks.chunk_size * 1.5;
+    let load_distance = settings.load_distance - margin;
+    let unload_distance = settings.unload_distance.max(settings.load_distance) - margin;
+
+    for (body_entity, transform, rigid_body, source_node, bicycle, dormant) in q_body.iter() {
+        let coordinate = world_chunks.chunk_of(transform.translation);
+        let distance = world_chunks.distance(coordinate, &anchors);
+
+        // bicycles are rebuilt from their node, so their meshes can go too
+        let rebuilt_node = source_node.filter(|_| bicycle);
+
+        if dormant && distance <= load_distance {
+            if let Some(source_node) = rebuilt_node {
+                let Some(gltf_node) = gltf_nodes.get(&source_node.0) else {
+                    continue;
+                };
+
+                spawn_primitives(body_entity, gltf_node, &gltf_meshes, &mut commands);
+
+                let mut bicycle_commands = commands.entity(body_entity);
+                bicycle_commands.remove::<Dormant>();
+                insert_bicycle_physics(&mut bicycle_commands);
+            } else {
+                commands
+                    .entity(body_entity)
+                    .remove::<Dormant>()
+                    .insert(RigidBody::Dynamic);
+            }
+        } else if !dormant
+            && matches!(rigid_body, Some(RigidBody::Dynamic))
+            && distance > unload_distance
+        {
+            let mut body_commands = commands.entity(body_entity);
+
+            // the meshes and the colliders of a bicycle are children of it, other bodies keep
+            // their colliders, without a rigid body they don't move
+            if rebuilt_node.is_some() {
+                body_commands.despawn_descendants();
+            }
+
+            body_commands.remove::<RigidBody>().insert(Dormant);
+        }
+    }
+}